@@ -1287,8 +1287,8 @@ mod ir_tests {
    #[test]
     fn ir_lex() {
         {
-            let toks = lex_ir("%add a, b, c\n");
-            assert!(toks.len() == 6);
+            let toks = lex_ir("%add a, b, c\n").unwrap();
+            assert!(toks.len() == 8);
             assert!(matches!(toks[0], IRTok::Add));
             assert!(matches!(toks[1], IRTok::Var(_)));
             assert!(matches!(toks[2], IRTok::Comma));
@@ -1298,8 +1298,8 @@ mod ir_tests {
         }
 
         {
-            let toks = lex_ir("%func main,,,\n");
-            assert!(toks.len() == 5);
+            let toks = lex_ir("%func main,,,\n").unwrap();
+            assert!(toks.len() == 7);
             assert!(matches!(toks[0], IRTok::Func));
             assert!(matches!(toks[1], IRTok::Var(_)));
             assert!(matches!(toks[2], IRTok::Comma));
@@ -1308,8 +1308,8 @@ mod ir_tests {
         }
 
         {
-            let toks = lex_ir("%func,main,,,\n");
-            assert!(toks.len() == 6);
+            let toks = lex_ir("%func,main,,,\n").unwrap();
+            assert!(toks.len() == 8);
             assert!(matches!(toks[0], IRTok::Func));
             assert!(matches!(toks[1], IRTok::Comma));
             assert!(matches!(toks[2], IRTok::Var(_)));
@@ -1319,8 +1319,8 @@ mod ir_tests {
         }
 
         {
-            let toks = lex_ir("%mov [arr+0], 100\n");
-            assert!(toks.len() == 8);
+            let toks = lex_ir("%mov [arr+0], 100\n").unwrap();
+            assert!(toks.len() == 10);
             assert!(matches!(toks[0], IRTok::Mov));
             assert!(matches!(toks[1], IRTok::LBrace));
             assert!(matches!(toks[2], IRTok::Var(_)));
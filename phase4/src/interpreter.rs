@@ -1,17 +1,82 @@
 pub fn execute_ir(code: &str) {
+    let bytecode = match build_bytecode(code) {
+    Some(bytecode) => {
+        println!("Valid IR. Executing Generated Bytecode...");
+        bytecode
+    }
+    None => return,
+    };
+
+    let stdin = io::stdin();
+    run_program(&stdin, &mut io::stdout(), &bytecode);
+}
+
+// prints the canonical IR of the program, i.e. the IR exactly as the interpreter
+// understood it: comments and whitespace removed and labels renamed in order.
+pub fn disassemble_ir(code: &str) {
+    if let Some(bytecode) = build_bytecode(code) {
+        print!("{}", disassemble(&bytecode));
+    }
+}
+
+// compiles the IR into the binary bytecode format, which can be saved as a '.ttb' file.
+pub fn compile_ir(code: &str) -> Option<Vec<u8>> {
+    let bytecode = build_bytecode(code)?;
+    Some(write_bytecode(&bytecode))
+}
+
+// runs a program from the contents of a '.ttb' file without parsing any IR.
+pub fn execute_bytecode_file(bytes: &[u8]) {
+    let bytecode = match read_bytecode(bytes) {
+    Ok(bytecode) => bytecode,
+    Err(e) => {
+        println!("***Error. Invalid bytecode file. {e}");
+        return;
+    }
+    };
+
+    let stdin = io::stdin();
+    run_program(&stdin, &mut io::stdout(), &bytecode);
+}
+
+// prints the canonical IR of a program from the contents of a '.ttb' file.
+pub fn disassemble_bytecode_file(bytes: &[u8]) {
+    match read_bytecode(bytes) {
+    Ok(bytecode) => print!("{}", disassemble(&bytecode)),
+    Err(e) => println!("***Error. Invalid bytecode file. {e}"),
+    }
+}
+
+// runs the IR and returns everything the program printed. used to test the compiler.
+#[cfg(test)]
+pub fn execute_ir_output(code: &str) -> Result<String, String> {
+    let tokens = lex_ir(code)?;
+    let program = match parse_ir(&tokens, &mut 0) {
+    Ok(program) => program,
+    Err(e) => return Err(e.to_string()),
+    };
+    let main = program.functions.iter().find(|f| f.name == "main").unwrap();
+    let mut heap: Vec<Vec<i64>> = vec![];
+    let mut globals = create_memory(&program.globals, &mut heap);
+    let mut output: Vec<u8> = vec![];
+    match run_bytecode(&io::stdin(), &mut output, main, &program, &mut globals, &mut heap, &vec![]) {
+    Ok(_) => Ok(String::from_utf8(output).unwrap()),
+    Err(e) if e.exit_code.is_some() => Ok(String::from_utf8(output).unwrap()),
+    Err(e) => Err(e.to_string()),
+    }
+}
+
+fn build_bytecode(code: &str) -> Option<Program> {
     let tokens = match lex_ir(code) {
     Ok(tokens) => tokens,
     Err(e) => {
         println!("***Error. {e}");
-        return;
+        return None;
     }
     };
 
-    let bytecode = match parse_ir(&tokens, &mut 0) {
-    Ok(bytecode) => {
-        println!("Valid IR. Executing Generated Bytecode...");
-        bytecode
-    }
+    match parse_ir(&tokens, &mut 0) {
+    Ok(bytecode) => Some(bytecode),
 
     Err(e) => {
         println!("***Error. Invalid Bytecode.");
@@ -24,16 +89,14 @@ pub fn execute_ir(code: &str) {
         }
         println!("------------------");
         println!("{e}");
-        return;
+        None
     }
 
-    };
-
-    let stdin = io::stdin();
-    run_program(&stdin, &bytecode);
+    }
 }
 
 use std::io;
+use std::io::Write;
 
 fn lex_ir(code: &str) -> Result<Vec<IRTok>, String> {
     let mut tokens: Vec<IRTok> = vec![];
@@ -63,9 +126,12 @@ fn lex_ir(code: &str) -> Result<Vec<IRTok>, String> {
             tokens.push(token);
         }
 
-        '0'..='9' => {
+        '0'..='9' | '-' => {
             let start = i;
             i += 1;
+            if c == '-' && !(i < array.len() && array[i].is_ascii_digit()) {
+                return Err(String::from("'-' must be followed by a number, such as '-1'"));
+            }
             while i < array.len() {
                 let digit = array[i] as char;
                 if digit >= '0' && digit <= '9' {
@@ -74,13 +140,77 @@ fn lex_ir(code: &str) -> Result<Vec<IRTok>, String> {
                     break;
                 }
             }
+
+            // floats have a fraction or an exponent, such as '2.5' or '1e-7'.
+            let mut is_float = false;
+            if i + 1 < array.len() && array[i] == b'.' && array[i + 1].is_ascii_digit() {
+                is_float = true;
+                i += 1;
+                while i < array.len() && array[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            if i < array.len() && (array[i] == b'e' || array[i] == b'E') {
+                let sign = if i + 1 < array.len() && (array[i + 1] == b'-' || array[i + 1] == b'+') { 1 } else { 0 };
+                if i + 1 + sign < array.len() && array[i + 1 + sign].is_ascii_digit() {
+                    is_float = true;
+                    i += 1 + sign;
+                    while i < array.len() && array[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+
             let end = i;
             let string_token = &code[start..end];
-            let number_value = string_token.parse::<i32>().unwrap();
+            if is_float {
+                match string_token.parse::<f64>() {
+                Ok(number) if number.is_finite() => tokens.push(IRTok::Decimal(number)),
+                _ => return Err(format!("number '{}' does not fit in a 64 bit float", string_token)),
+                }
+                continue;
+            }
+            let number_value = match string_token.parse::<i64>() {
+            Ok(number) => number,
+            Err(_) => return Err(format!("number '{}' does not fit in a 64 bit integer", string_token)),
+            };
             let token = IRTok::Num(number_value);
             tokens.push(token);
         }
 
+        // string constants such as "hello\n". the escapes are \n, \t, \r, \0, \\ and \".
+        '"' => {
+            i += 1;
+            let mut value: Vec<u8> = vec![];
+            loop {
+                if i >= array.len() || array[i] == b'\n' {
+                    return Err(String::from("string constant is missing the closing '\"'"));
+                }
+                match array[i] {
+                b'"' => break,
+                b'\\' if i + 1 < array.len() => {
+                    value.push(match array[i + 1] {
+                    b'n' => b'\n',
+                    b't' => b'\t',
+                    b'r' => b'\r',
+                    b'0' => 0,
+                    b'\\' => b'\\',
+                    b'"' => b'"',
+                    other => return Err(format!("unknown escape '\\{}' in string constant", other as char)),
+                    });
+                    i += 2;
+                }
+                byte => {
+                    value.push(byte);
+                    i += 1;
+                }
+                }
+            }
+            i += 1;
+            // the code is valid utf-8, and escapes are ascii, so the string is too.
+            tokens.push(IRTok::Text(String::from_utf8(value).unwrap()));
+        }
+
         '+' => {
           tokens.push(IRTok::Plus);
           i += 1;
@@ -145,11 +275,12 @@ fn lex_ir(code: &str) -> Result<Vec<IRTok>, String> {
         }
 
         ';' => {
+            // comments go until the end of the line. the newline itself
+            // still ends the instruction.
             i += 1;
             while i < array.len() {
                 let c = array[i] as char;
                 if c == '\n' {
-                    i += 1;
                     break;
                 }
                 i += 1;
@@ -182,9 +313,12 @@ fn lex_ir(code: &str) -> Result<Vec<IRTok>, String> {
 
 const MAX_LINE: usize = 2000000;
 
-fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result< Vec<FunctionBytecode>, IRError> {
+fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Program, IRError> {
     let mut serialized_line: usize = 1;
     let mut vector: Vec<FunctionBytecode> = vec![];
+    let mut globals: HashMap<String, VariableType> = HashMap::new();
+    let mut strings: Vec<String> = vec![];
+    let mut string_ids: HashMap<String, usize> = HashMap::new();
     let mut has_main: bool = false;
 
     while !matches!(tokens[*idx], IRTok::End) {
@@ -193,8 +327,18 @@ fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result< Vec<FunctionBytecod
             *idx += 1;
             continue;
         }
+
+        if matches!(tokens[*idx], IRTok::Global | IRTok::GlobalLong | IRTok::GlobalArray | IRTok::GlobalStr | IRTok::GlobalFloat | IRTok::GlobalFloatArray) {
+            parse_global(serialized_line, &mut globals, tokens, idx)?;
+            continue;
+        }
+
+        if matches!(tokens[*idx], IRTok::Data) {
+            parse_data(serialized_line, &mut strings, &mut string_ids, tokens, idx)?;
+            continue;
+        }
         
-        let bytecode = parse_func_ir(&mut serialized_line, tokens, idx)?;
+        let bytecode = parse_func_ir(&mut serialized_line, &globals, tokens, idx)?;
         for func in &vector {
           if func.name.eq(&bytecode.name) {
             return error(MAX_LINE, format!("Error. Two functions with the same name {}", func.name));
@@ -210,6 +354,15 @@ fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result< Vec<FunctionBytecod
       return error(MAX_LINE, format!("Error. Generated code does not have a main."));
     }
 
+    // the values every function returns, so that calls can be checked.
+    let mut returns = vec![];
+    for function in &vector {
+        match return_kinds(function, &globals) {
+        Ok(kinds) => returns.push(kinds),
+        Err(e) => return error(MAX_LINE, format!("Error. {e}")),
+        }
+    }
+
     // todo: this is not the correct line numbers. but I dunno how to get better line numbers...
     for func_id in 0..vector.len() {
         for instr_id in 0..vector[func_id].body.len() {
@@ -219,7 +372,34 @@ fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result< Vec<FunctionBytecod
                          if params.len() != vector[call_value].parameters {
                              return error(MAX_LINE, format!("Error. Invalid parameter passing to '{func_name}'. Expected {} number of parameters. Got {} number of parameters.", vector[call_value].parameters, params.len()));
                          }
-                         vector[func_id].body[instr_id] = Bytecode::Call(*r, call_value, params.to_vec());
+                         for (i, param) in params.iter().enumerate() {
+                             let passed = operand_kind(&vector[func_id], &globals, param);
+                             let expected = variable_kind(&vector[call_value], &globals, i as i32);
+                             if passed != expected {
+                                 return error(MAX_LINE, format!("Error. Invalid parameter passing to '{func_name}'. Parameter {} must be {expected}.", i + 1));
+                             }
+                         }
+                         if let Err(e) = check_call_dests(&vector[func_id], &globals, r, func_name, &returns[call_value]) {
+                             return error(MAX_LINE, format!("Error. {e}"));
+                         }
+                         vector[func_id].body[instr_id] = Bytecode::Call(r.to_vec(), call_value, params.to_vec());
+                    } else if let Some(builtin) = BUILTINS.iter().position(|(name, _, _, _)| name == func_name) {
+                         // a function of the program with the same name as a builtin replaces it.
+                         let (_, builtin, parameters, count) = BUILTINS[builtin];
+                         if params.len() != parameters {
+                             return error(MAX_LINE, format!("Error. Invalid parameter passing to '{func_name}'. Expected {} number of parameters. Got {} number of parameters.", parameters, params.len()));
+                         }
+                         for (i, param) in params.iter().enumerate() {
+                             if let Op::Var(id) = param {
+                                 if variable_kind(&vector[func_id], &globals, *id) != "an integer" {
+                                     return error(MAX_LINE, format!("Error. Invalid parameter passing to '{func_name}'. Parameter {} must be an integer.", i + 1));
+                                 }
+                             }
+                         }
+                         if let Err(e) = check_call_dests(&vector[func_id], &globals, r, func_name, &Some(vec!["an integer"; count])) {
+                             return error(MAX_LINE, format!("Error. {e}"));
+                         }
+                         vector[func_id].body[instr_id] = Bytecode::Builtin(r.to_vec(), builtin, params.to_vec());
                     } else {
                          return error(MAX_LINE, format!("Error. Undeclared function '{}'", func_name));
                     }
@@ -227,10 +407,36 @@ fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result< Vec<FunctionBytecod
                     return error(MAX_LINE, format!("Internal Interpreter Error."));
                 }
             }
+
+            // string operands that are not string variables are string constants.
+            for operand in string_operands(&mut vector[func_id].body[instr_id]) {
+                if let StrOp::Data(name) = operand {
+                    if let IRTok::Var(string_name) = &tokens[*name] {
+                        match string_ids.get(string_name) {
+                        Some(id) => *name = *id,
+                        None => return error(MAX_LINE, format!("Error. '{}' is not a string variable or a string constant", string_name)),
+                        }
+                    } else {
+                        return error(MAX_LINE, format!("Internal Interpreter Error."));
+                    }
+                }
+            }
         }
     }
 
-    return Ok(vector);
+    return Ok(Program {
+        globals,
+        strings,
+        functions: vector,
+    });
+
+    fn string_operands(bytecode: &mut Bytecode) -> Vec<&mut StrOp> {
+        match bytecode {
+        Bytecode::OutStr(src) | Bytecode::StrMov(_, src) | Bytecode::StrLen(_, src) | Bytecode::CharAt(_, src, _) => vec![src],
+        Bytecode::Concat(_, src1, src2) | Bytecode::StrCmp(_, src1, src2) => vec![src1, src2],
+        _ => vec![],
+        }
+    }
 
     fn find_func_id(v: &Vec<FunctionBytecode>, func_name: &str) -> Option<usize> {
         for (i, function) in v.iter().enumerate() {
@@ -242,9 +448,91 @@ fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result< Vec<FunctionBytecod
     }
 }
 
+// global variables are declared outside of functions, before they are used.
+// global variables have negative ids, so they never clash with local variables.
+fn parse_global(serialized_line: usize, globals: &mut HashMap<String, VariableType>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<(), IRError> {
+    let kind = next_result(tokens, idx);
+    let is_array = matches!(kind, IRTok::GlobalArray | IRTok::GlobalFloatArray);
+    let is_string = matches!(kind, IRTok::GlobalStr);
+    let is_long = matches!(kind, IRTok::GlobalLong);
+    let is_float = matches!(kind, IRTok::GlobalFloat | IRTok::GlobalFloatArray);
+    let ident = match next_result(tokens, idx) {
+    IRTok::Var(ident) => ident,
+    _ => return error(serialized_line, String::from("invalid global. expected format like '%global variable' or '%global[] array, 10'")),
+    };
+
+    if let Some(_) = globals.get(ident) {
+        return error(serialized_line, format!("invalid global. identifier '{}' declared too many times", ident));
+    }
+
+    let id = -(globals.len() as i32) - 1;
+    if is_array {
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(serialized_line, String::from("invalid global. expected format like '%global[] array, 10'"));
+        }
+        let num = match next_result(tokens, idx) {
+        IRTok::Num(num) => *num,
+        _ => return error(serialized_line, String::from("invalid global. expected format like '%global[] array, 10'")),
+        };
+        if num <= 0 {
+            return error(serialized_line, String::from("array size cannot be less than or equal to zero."));
+        }
+        if num > i32::MAX as i64 {
+            return error(serialized_line, String::from("array size does not fit in a 32 bit integer."));
+        }
+        if is_float {
+            globals.insert(ident.clone(), VariableType::FloatArrayVar(id, num as i32));
+        } else {
+            globals.insert(ident.clone(), VariableType::ArrayVar(id, num as i32));
+        }
+    } else if is_string {
+        globals.insert(ident.clone(), VariableType::StringVar(id));
+    } else if is_long {
+        globals.insert(ident.clone(), VariableType::LongVar(id));
+    } else if is_float {
+        globals.insert(ident.clone(), VariableType::FloatVar(id));
+    } else {
+        globals.insert(ident.clone(), VariableType::IntVar(id));
+    }
+
+    if !matches!(tokens[*idx], IRTok::EndInstr | IRTok::End) {
+        return error(serialized_line, String::from("expected newline."));
+    }
+    Ok(())
+}
+
+// string constants are declared outside of functions, such as '%data greeting, "hello\n"'.
+// they are printed with '%outs greeting'.
+fn parse_data(serialized_line: usize, strings: &mut Vec<String>, string_ids: &mut HashMap<String, usize>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<(), IRError> {
+    *idx += 1;
+    let ident = match next_result(tokens, idx) {
+    IRTok::Var(ident) => ident,
+    _ => return error(serialized_line, String::from("invalid string constant. expected format like '%data name, \"text\"'")),
+    };
+    if string_ids.contains_key(ident) {
+        return error(serialized_line, format!("invalid string constant. '{}' declared too many times", ident));
+    }
+    if !matches!(next_result(tokens, idx), IRTok::Comma) {
+        return error(serialized_line, String::from("invalid string constant. expected format like '%data name, \"text\"'"));
+    }
+    let value = match next_result(tokens, idx) {
+    IRTok::Text(value) => value,
+    _ => return error(serialized_line, String::from("invalid string constant. expected format like '%data name, \"text\"'")),
+    };
+
+    string_ids.insert(ident.clone(), strings.len());
+    strings.push(value.clone());
+    if !matches!(tokens[*idx], IRTok::EndInstr | IRTok::End) {
+        return error(serialized_line, String::from("expected newline."));
+    }
+    Ok(())
+}
+
+// 'exit_code' is set when the program ends early by calling 'exit', which is not an error.
 struct IRError {
     line: usize,
     message: String,
+    exit_code: Option<i64>,
 }
 
 impl fmt::Display for IRError {
@@ -258,12 +546,112 @@ impl fmt::Display for IRError {
 }
 
 fn error<T>(line: usize, message: String) -> Result<T, IRError> {
-    Err(IRError {line: line, message: message})
+    Err(IRError {line, message, exit_code: None})
+}
+
+fn exit<T>(code: i64) -> Result<T, IRError> {
+    Err(IRError {line: MAX_LINE, message: format!("Exit code {code}"), exit_code: Some(code)})
+}
+
+// the functions every program can call without declaring them.
+#[derive(Debug, Clone, Copy)]
+enum Builtin {
+    Abs,
+    Min,
+    Max,
+    Pow,
+    Gcd,
+    SqrtInt,
+    Assert,
+    Exit,
+}
+
+// the name of every builtin, its number of parameters and the number of values it returns.
+// every parameter is an integer.
+const BUILTINS: [(&str, Builtin, usize, usize); 8] = [
+    ("abs", Builtin::Abs, 1, 1),
+    ("min", Builtin::Min, 2, 1),
+    ("max", Builtin::Max, 2, 1),
+    ("pow", Builtin::Pow, 2, 1),
+    ("gcd", Builtin::Gcd, 2, 1),
+    ("sqrt_int", Builtin::SqrtInt, 1, 1),
+    ("assert", Builtin::Assert, 1, 0),
+    ("exit", Builtin::Exit, 1, 0),
+];
+
+fn builtin_index(builtin: Builtin) -> usize {
+    BUILTINS.iter().position(|(_, other, _, _)| *other as usize == builtin as usize).unwrap()
+}
+
+// runs a builtin on its arguments, in 64 bits. the result is wrapped to the width of the variable
+// it is stored in, like any other instruction, except for 'abs' and 'gcd', which are never negative.
+fn run_builtin(builtin: Builtin, arguments: &[i64]) -> Result<Vec<i64>, IRError> {
+    let value = match builtin {
+    Builtin::Abs => arguments[0].wrapping_abs(),
+    Builtin::Min => arguments[0].min(arguments[1]),
+    Builtin::Max => arguments[0].max(arguments[1]),
+    Builtin::Pow => {
+        let (base, exponent) = (arguments[0], arguments[1]);
+        if exponent < 0 {
+            return error(MAX_LINE, format!("Runtime Error: pow({base}, {exponent}) has a negative exponent."));
+        }
+        let mut result: i64 = 1;
+        let mut square = base;
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.wrapping_mul(square);
+            }
+            square = square.wrapping_mul(square);
+            exponent >>= 1;
+        }
+        result
+    }
+    Builtin::Gcd => {
+        let (mut a, mut b) = (arguments[0].unsigned_abs(), arguments[1].unsigned_abs());
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a as i64
+    }
+    Builtin::SqrtInt => {
+        let n = arguments[0];
+        if n < 0 {
+            return error(MAX_LINE, format!("Runtime Error: sqrt_int({n}) of a negative number."));
+        }
+        // the float square root can be off by one for large numbers.
+        let mut root = (n as f64).sqrt() as i64;
+        while root > 0 && root.checked_mul(root).map_or(true, |square| square > n) {
+            root -= 1;
+        }
+        while (root + 1).checked_mul(root + 1).map_or(false, |square| square <= n) {
+            root += 1;
+        }
+        root
+    }
+    Builtin::Assert => {
+        if arguments[0] == 0 {
+            return error(MAX_LINE, String::from("Runtime Error: Assertion failed."));
+        }
+        return Ok(vec![]);
+    }
+    Builtin::Exit => {
+        return exit(arguments[0]);
+    }
+    };
+
+    // 'abs(-2147483648)' would wrap back to a negative number, so it is an error instead.
+    if matches!(builtin, Builtin::Abs | Builtin::Gcd) && i32::try_from(value).is_err() {
+        let name = BUILTINS[builtin_index(builtin)].0;
+        let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).collect();
+        return error(MAX_LINE, format!("Runtime Error: {name}({}) does not fit in an int.", arguments.join(", ")));
+    }
+    Ok(vec![value])
 }
 
 use std::fmt;
 
-fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<FunctionBytecode, IRError> {
+fn parse_func_ir(serialized_line: &mut usize, globals: &HashMap<String, VariableType>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<FunctionBytecode, IRError> {
     let mut function_bytecode = FunctionBytecode {
         name:String::from(""),
         parameters:0,
@@ -293,7 +681,8 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
         loop {
             match next_result(tokens, idx) {
             IRTok::RParen => break,
-            IRTok::Int => {
+            IRTok::Int | IRTok::Long | IRTok::IntArray | IRTok::Str | IRTok::Float | IRTok::FloatArray => {
+                let kind = &tokens[*idx - 1];
                 match &next_result(tokens, idx) {
                 IRTok::Var(param) => {
                     if let Some(_) = function_bytecode.variables.get(param) {
@@ -302,7 +691,15 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
                     } else {
                         function_bytecode.parameters += 1;
                         let id = get_id(&mut function_bytecode);
-                        function_bytecode.variables.insert(param.clone(), VariableType::IntVar(id));
+                        let vartype = match kind {
+                        IRTok::IntArray => VariableType::ArrayRef(id),
+                        IRTok::Str => VariableType::StringVar(id),
+                        IRTok::Long => VariableType::LongVar(id),
+                        IRTok::Float => VariableType::FloatVar(id),
+                        IRTok::FloatArray => VariableType::FloatArrayRef(id),
+                        _ => VariableType::IntVar(id),
+                        };
+                        function_bytecode.variables.insert(param.clone(), vartype);
                     }
                     if matches!(tokens[*idx], IRTok::Comma) {
                         *idx += 1;
@@ -333,7 +730,7 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
     let mut labels_hash: HashMap<String, usize> = Default::default();
     let mut line: usize = 0;
     loop {
-        let bytecode = parse_instruction(serialized_line, line, &mut function_bytecode, &mut labels_hash, tokens, idx)?;
+        let bytecode = parse_instruction(serialized_line, line, &mut function_bytecode, globals, &mut labels_hash, tokens, idx)?;
         if matches!(bytecode, Bytecode::End) {
             break;
         }
@@ -381,6 +778,21 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
                 return error(*serialized_line, String::from("Internal Compiler Error."));
             }
         }
+        Bytecode::Switch(src, default, table) => {
+            let mut jumps = vec![];
+            for index in std::iter::once(default).chain(table.iter()) {
+                if let IRTok::Label(label_name) = &tokens[*index] {
+                    if let Some(id) = labels_hash.get(label_name) {
+                        jumps.push(*id);
+                    } else {
+                        return error(*serialized_line, format!("Error. invalid label {}", label_name));
+                    }
+                } else {
+                    return error(*serialized_line, String::from("Internal Compiler Error."));
+                }
+            }
+            function_bytecode.body[i] = Bytecode::Switch(src.clone(), jumps[0], jumps[1..].to_vec());
+        }
         _ => {}
         }
     }
@@ -389,6 +801,12 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
 
 use std::collections::HashMap;
 
+struct Program {
+    globals: HashMap<String, VariableType>,
+    strings: Vec<String>,
+    functions: Vec<FunctionBytecode>,
+}
+
 struct FunctionBytecode {
     name: String,
     parameters: usize,
@@ -403,14 +821,191 @@ fn get_id(function: &mut FunctionBytecode) -> i32 {
     id
 }
 
-fn read_integer_value(variables: &HashMap<i32, i32>, op: &Op) -> i32 {
+// the variables of a program. every function call has its own local variables,
+// and the global variables are shared by every function call.
+// the arrays themselves live on the heap, so that arrays can be passed by reference. the heap holds
+// the elements of int arrays wrapped to 32 bits, and the bits of the elements of float arrays.
+// strings are values, so every string variable owns its own string.
+struct Memory {
+    variables: HashMap<i32, i32>,
+    longs: HashMap<i32, i64>,
+    arrays: HashMap<i32, usize>,
+    strings: HashMap<i32, String>,
+    floats: HashMap<i32, f64>,
+}
+
+// local variables have ids starting from 0, and global variables have negative ids.
+struct Frame<'a> {
+    locals: Memory,
+    globals: &'a mut Memory,
+    heap: &'a mut Vec<Vec<i64>>,
+}
+
+// a value passed to or returned from a function. integers, strings and floats are copied, and arrays
+// are passed by reference.
+#[derive(Clone)]
+enum Argument {
+    Int(i64),
+    Array(usize),
+    Str(String),
+    Float(f64),
+}
+
+fn create_memory(variables: &HashMap<String, VariableType>, heap: &mut Vec<Vec<i64>>) -> Memory {
+    let mut memory = Memory {
+        variables: HashMap::new(),
+        longs: HashMap::new(),
+        arrays: HashMap::new(),
+        strings: HashMap::new(),
+        floats: HashMap::new(),
+    };
+    for (_, vartype) in variables {
+         match vartype {
+         VariableType::IntVar(id) => {
+             memory.variables.insert(*id, 0);
+         }
+
+         // the bits of 0.0 are zero, so float arrays are zeroes too.
+         VariableType::ArrayVar(id, len) | VariableType::FloatArrayVar(id, len) => {
+             // create an array of zeroes.
+             let arr = vec![0i64; *len as usize];
+             memory.arrays.insert(*id, heap.len());
+             heap.push(arr);
+         }
+
+         // array parameters refer to the array of the caller, set when the function is called.
+         VariableType::ArrayRef(_) | VariableType::FloatArrayRef(_) => {}
+
+         VariableType::StringVar(id) => {
+             memory.strings.insert(*id, String::new());
+         }
+
+         VariableType::LongVar(id) => {
+             memory.longs.insert(*id, 0);
+         }
+
+         VariableType::FloatVar(id) => {
+             memory.floats.insert(*id, 0.0);
+         }
+
+         }
+    }
+    memory
+}
+
+fn read_integer_value(frame: &Frame, op: &Op) -> i64 {
     match op {
     Op::Num(num) => *num,
-    Op::Var(id) => variables[&id],
+    Op::Var(id) => {
+        let memory = if *id < 0 { &*frame.globals } else { &frame.locals };
+        match memory.variables.get(id) {
+        Some(value) => *value as i64,
+        None => memory.longs[id],
+        }
+    }
+    }
+}
+
+// ints wrap around to 32 bits, and longs keep all 64 bits.
+fn write_integer(frame: &mut Frame, id: &i32, value: i64) {
+    let memory = if *id < 0 { &mut *frame.globals } else { &mut frame.locals };
+    match memory.variables.get_mut(id) {
+    Some(variable) => *variable = value as i32,
+    None => *memory.longs.get_mut(id).unwrap() = value,
+    }
+}
+
+fn array_index(frame: &Frame, id: &i32) -> usize {
+    if *id < 0 {
+        frame.globals.arrays[id]
+    } else {
+        frame.locals.arrays[id]
+    }
+}
+
+fn array_mut<'a>(frame: &'a mut Frame, id: &i32) -> &'a mut Vec<i64> {
+    let index = array_index(frame, id);
+    &mut frame.heap[index]
+}
+
+fn string_mut<'a>(frame: &'a mut Frame, id: &i32) -> &'a mut String {
+    if *id < 0 {
+        frame.globals.strings.get_mut(id).unwrap()
+    } else {
+        frame.locals.strings.get_mut(id).unwrap()
+    }
+}
+
+fn read_string_value<'a>(frame: &'a Frame, program: &'a Program, op: &StrOp) -> &'a str {
+    match op {
+    StrOp::Data(id) => &program.strings[*id],
+    StrOp::Var(id) if *id < 0 => &frame.globals.strings[id],
+    StrOp::Var(id) => &frame.locals.strings[id],
+    }
+}
+
+fn float_mut<'a>(frame: &'a mut Frame, id: &i32) -> &'a mut f64 {
+    if *id < 0 {
+        frame.globals.floats.get_mut(id).unwrap()
+    } else {
+        frame.locals.floats.get_mut(id).unwrap()
+    }
+}
+
+fn read_float_value(frame: &Frame, op: &FloatOp) -> f64 {
+    match op {
+    FloatOp::Num(num) => *num,
+    FloatOp::Var(id) if *id < 0 => frame.globals.floats[id],
+    FloatOp::Var(id) => frame.locals.floats[id],
+    }
+}
+
+// floats are printed the shortest way that reads back as the same float, and always
+// with a '.' or an exponent, such as '2.0', '0.1' or '1e-7'. the IR writes floats the same way.
+fn format_float(value: f64) -> String {
+    format!("{:?}", value)
+}
+
+// stores a value passed to or returned from a function in the variable 'id'.
+fn write_argument(frame: &mut Frame, id: &i32, argument: &Argument) {
+    match argument {
+    Argument::Int(value) => write_integer(frame, id, *value),
+    Argument::Array(index) => {frame.locals.arrays.insert(*id, *index);}
+    Argument::Str(value) => *string_mut(frame, id) = value.clone(),
+    Argument::Float(value) => *float_mut(frame, id) = *value,
+    }
+}
+
+// the value a call stores when the function reaches '%endfunc' without returning one.
+fn zero_argument(frame: &Frame, id: &i32) -> Argument {
+    let memory = if *id < 0 { &*frame.globals } else { &frame.locals };
+    if memory.strings.contains_key(id) {
+        Argument::Str(String::new())
+    } else if memory.floats.contains_key(id) {
+        Argument::Float(0.0)
+    } else {
+        Argument::Int(0)
+    }
+}
+
+fn read_argument(frame: &Frame, op: &Op) -> Argument {
+    match op {
+    Op::Var(id) if frame.locals.arrays.contains_key(id) || frame.globals.arrays.contains_key(id) => {
+        Argument::Array(array_index(frame, id))
+    }
+    Op::Var(id) if frame.locals.strings.contains_key(id) || frame.globals.strings.contains_key(id) => {
+        let value = if *id < 0 { &frame.globals.strings[id] } else { &frame.locals.strings[id] };
+        Argument::Str(value.clone())
+    }
+    Op::Var(id) if frame.locals.floats.contains_key(id) || frame.globals.floats.contains_key(id) => {
+        Argument::Float(read_float_value(frame, &FloatOp::Var(*id)))
+    }
+    _ => Argument::Int(read_integer_value(frame, op)),
     }
 }
 
-fn run_program(stdin: &io::Stdin, calls: &Vec<FunctionBytecode>) {
+fn run_program(stdin: &io::Stdin, output: &mut dyn Write, program: &Program) {
+    let calls = &program.functions;
     let entry_point = {
         let mut result = None;
         for func in calls {
@@ -426,40 +1021,39 @@ fn run_program(stdin: &io::Stdin, calls: &Vec<FunctionBytecode>) {
         }
         }
     };
-    match run_bytecode(stdin, entry_point, calls, &vec![]) {
-    Ok(n) => println!("Run successful. Exit code {}", n),
+    let mut heap: Vec<Vec<i64>> = vec![];
+    let mut globals = create_memory(&program.globals, &mut heap);
+    match run_bytecode(stdin, output, entry_point, program, &mut globals, &mut heap, &vec![]) {
+    Ok(values) => {
+        let code = match values.first() {
+        Some(Argument::Int(code)) => *code,
+        _ => 0,
+        };
+        println!("Run successful. Exit code {}", code);
+    }
+    Err(IRError { exit_code: Some(code), .. }) => println!("Run successful. Exit code {}", code),
     Err(e) => println!("{}", e),
     }
 }
 
-fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<FunctionBytecode>, parameters: &Vec<i32>) -> Result<i32, IRError>  {
-    let mut variables: HashMap<i32, i32> = HashMap::new();
-    let mut arrays: HashMap<i32, Vec<i32>> = HashMap::new();
-
-    // setup local variables
-    for (_, vartype) in &function.variables {
-         match vartype {
-         VariableType::IntVar(id) => {
-             variables.insert(*id, 0);
-         }
-
-         VariableType::ArrayVar(id, len) => {
-             // create an array of zeroes.
-             let arr = vec![0i32; *len as usize];
-             arrays.insert(*id, arr);
-         }
-
-         }
-    }
+// returns the values of the '%ret' that ended the function. a function that reaches '%endfunc'
+// returns nothing, and a call storing values from it stores zeros.
+fn run_bytecode(stdin: &io::Stdin, output: &mut dyn Write, function: &FunctionBytecode, program: &Program, globals: &mut Memory, heap: &mut Vec<Vec<i64>>, parameters: &[Argument]) -> Result<Vec<Argument>, IRError>  {
+    // setup local variables. the arrays of this function are freed when it returns.
+    let heap_start = heap.len();
+    let mut frame = Frame {
+        locals: create_memory(&function.variables, heap),
+        globals,
+        heap,
+    };
     if parameters.len() != function.parameters {
          let e = format!("Runtime Error. Incorrect number of parameters passed to the function. Expected {}, got {} parameters", function.parameters, parameters.len());
          return error(MAX_LINE, e);
     }
 
     // hopefully this covers everything needed for parameter passing...
-    for (i, value) in parameters.iter().enumerate() {
-        let variable = variables.get_mut(&(i as i32)).unwrap();
-        *variable = *value;
+    for (i, argument) in parameters.iter().enumerate() {
+        write_argument(&mut frame, &(i as i32), argument);
     }
 
     // a lot of unwraps, but we already checked everything
@@ -473,14 +1067,13 @@ fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<Func
             break;
         }
 
-        Bytecode::Int(id) => {
-            let value = variables.get_mut(id).unwrap();
-            *value = 0;
+        Bytecode::Int(id) | Bytecode::Long(id) => {
+            write_integer(&mut frame, id, 0);
             instr_pointer += 1;
         }
 
-        Bytecode::IntArray(id, len) => {
-            let array = arrays.get_mut(id).unwrap();
+        Bytecode::IntArray(id, len) | Bytecode::FloatArray(id, len) => {
+            let array = array_mut(&mut frame, id);
             for i in 0..*len {
                  let idx = i as usize;
                  array[idx] ^= array[idx];
@@ -489,8 +1082,23 @@ fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<Func
         }
 
         Bytecode::Out(value) => {
-            let num = read_integer_value(&variables, value);
-            println!("{}", num);
+            let num = read_integer_value(&frame, value);
+            let _ = writeln!(output, "{}", num);
+            instr_pointer += 1;
+        }
+
+        // characters and strings are printed without a newline.
+        Bytecode::OutChar(value) => {
+            let num = read_integer_value(&frame, value);
+            match u32::try_from(num).ok().and_then(char::from_u32) {
+            Some(c) => { let _ = write!(output, "{}", c); }
+            _ => return error(MAX_LINE, format!("Runtime Error: {} is not a character.", num)),
+            }
+            instr_pointer += 1;
+        }
+
+        Bytecode::OutStr(src) => {
+            let _ = write!(output, "{}", read_string_value(&frame, program, src));
             instr_pointer += 1;
         }
 
@@ -500,14 +1108,14 @@ fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<Func
                 match stdin.read_line(&mut buf) {
                 Ok(_) => {
                      let token = buf.trim_end();
-                     match token.parse::<i32>() {
-                     Ok(num) => {
-                          let dest = variables.get_mut(id).unwrap();
-                          *dest = num;
+                     let is_long = if *id < 0 { frame.globals.longs.contains_key(id) } else { frame.locals.longs.contains_key(id) };
+                     match token.parse::<i64>() {
+                     Ok(num) if is_long || i32::try_from(num).is_ok() => {
+                          write_integer(&mut frame, id, num);
                           break;
                      }
 
-                     Err(_) => {
+                     _ => {
                          println!("User Input Error. '{}' is not a valid number.", token);
                          buf.clear();
                      }
@@ -526,24 +1134,41 @@ fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<Func
             instr_pointer += 1;
         }
 
+        // reads a line, without the newline at the end. the end of the input reads an empty string.
+        Bytecode::InStr(id) => {
+            let mut buf = String::with_capacity(64);
+            if let Err(e) = stdin.read_line(&mut buf) {
+                return error(MAX_LINE, format!("Runtime Error: Failed to read from standard input. {e}"));
+            }
+            if buf.ends_with('\n') {
+                buf.pop();
+                if buf.ends_with('\r') {
+                    buf.pop();
+                }
+            }
+            let dest = string_mut(&mut frame, id);
+            *dest = buf;
+            instr_pointer += 1;
+        }
+
         Bytecode::Mov(MemWrite::IntVar(dest), src) => {
-            let num = read_memory(&variables, &arrays, src)?;
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num;
+            let num = read_memory(&frame, src)?;
+            write_integer(&mut frame, dest, num);
             instr_pointer += 1;
         }
 
         Bytecode::Mov(MemWrite::ArrayWrite(dest, index), src) => {
-            let num = read_memory(&variables, &arrays, src)?;
-            let dest = arrays.get_mut(dest).unwrap();
-            let index = read_integer_value(&variables, index);
+            let num = read_memory(&frame, src)?;
+            let index = read_integer_value(&frame, index);
+            let dest = array_mut(&mut frame, dest);
             if index < 0 {
                 let e = format!("Runtime Error: Array out of bounds. Value {}. Array Length {}", index, dest.len());
                 return error(MAX_LINE, e);
             }
             let i = index as usize;
             if i < dest.len() {
-                dest[i] = num;
+                // int arrays hold ints, so the value wraps around to 32 bits.
+                dest[i] = num as i32 as i64;
                 instr_pointer += 1;
             } else {
                 let e = format!("Runtime Error: Array out of bounds. Value {}. Array Length {}", i, dest.len());
@@ -551,100 +1176,143 @@ fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<Func
             }
         }
 
+        // arithmetic is done in 64 bits, and wraps around to the width of dest, just like
+        // integers in hardware. so with an int dest, it wraps around at 32 bits.
         Bytecode::Add(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 + num2;
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, num1.wrapping_add(num2));
             instr_pointer += 1;
         }
 
         Bytecode::Sub(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 - num2;
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, num1.wrapping_sub(num2));
             instr_pointer += 1;
         }
 
         Bytecode::Mult(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 * num2;
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, num1.wrapping_mul(num2));
             instr_pointer += 1;
         }
 
         Bytecode::Div(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
             if num2 == 0 {
                 let e = String::from("Error. Attempt to divide by zero.");
                 return error(MAX_LINE, e);
             }
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 / num2;
+            write_integer(&mut frame, dest, num1.wrapping_div(num2));
             instr_pointer += 1;
         }
 
         Bytecode::Mod(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
             if num2 == 0 {
                 let e = String::from("Error. Attempt to divide by zero.");
                 return error(MAX_LINE, e);
             }
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 % num2;
+            write_integer(&mut frame, dest, num1.wrapping_rem(num2));
             instr_pointer += 1;
         }
 
-        Bytecode::LessThan(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 < num2) as i32;
+        // bitwise operators.
+        Bytecode::And(dest, src1, src2) => {
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, num1 & num2);
             instr_pointer += 1;
         }
 
-        Bytecode::LessEqual(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 <= num2) as i32;
+        Bytecode::Or(dest, src1, src2) => {
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, num1 | num2);
             instr_pointer += 1;
         }
 
-        Bytecode::Equal(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 == num2) as i32;
+        Bytecode::Xor(dest, src1, src2) => {
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, num1 ^ num2);
             instr_pointer += 1;
         }
 
-        Bytecode::NotEqual(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 != num2) as i32;
+        Bytecode::Not(dest, src) => {
+            let num = read_integer_value(&frame, src);
+            write_integer(&mut frame, dest, !num);
             instr_pointer += 1;
         }
 
-        Bytecode::GreaterThan(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 > num2) as i32;
+        // shifting by the width of dest or more shifts out every bit. %shr is an arithmetic shift,
+        // so negative numbers become -1 rather than 0. shifting by a negative amount is an error.
+        Bytecode::Shl(dest, src1, src2) => {
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            if num2 < 0 {
+                let e = format!("Runtime Error. Shift by a negative amount {}.", num2);
+                return error(MAX_LINE, e);
+            }
+            write_integer(&mut frame, dest, if num2 >= 64 { 0 } else { num1 << num2 });
             instr_pointer += 1;
         }
 
-        Bytecode::GreaterEqual(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 >= num2) as i32;
-            instr_pointer += 1;
+        Bytecode::Shr(dest, src1, src2) => {
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            if num2 < 0 {
+                let e = format!("Runtime Error. Shift by a negative amount {}.", num2);
+                return error(MAX_LINE, e);
+            }
+            write_integer(&mut frame, dest, num1 >> num2.min(63));
+            instr_pointer += 1;
+        }
+
+        Bytecode::LessThan(dest, src1, src2) => {
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, (num1 < num2) as i64);
+            instr_pointer += 1;
+        }
+
+        Bytecode::LessEqual(dest, src1, src2) => {
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, (num1 <= num2) as i64);
+            instr_pointer += 1;
+        }
+
+        Bytecode::Equal(dest, src1, src2) => {
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, (num1 == num2) as i64);
+            instr_pointer += 1;
+        }
+
+        Bytecode::NotEqual(dest, src1, src2) => {
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, (num1 != num2) as i64);
+            instr_pointer += 1;
+        }
+
+        Bytecode::GreaterThan(dest, src1, src2) => {
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, (num1 > num2) as i64);
+            instr_pointer += 1;
+        }
+
+        Bytecode::GreaterEqual(dest, src1, src2) => {
+            let num1 = read_integer_value(&frame, src1);
+            let num2 = read_integer_value(&frame, src2);
+            write_integer(&mut frame, dest, (num1 >= num2) as i64);
+            instr_pointer += 1;
         }
 
         Bytecode::Label => {
@@ -656,7 +1324,7 @@ fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<Func
         }
 
         Bytecode::BranchIf(src1, jump) => {
-            let num1 = read_integer_value(&variables, src1);
+            let num1 = read_integer_value(&frame, src1);
             match num1 {
             0 => {instr_pointer += 1;}
             1 => {instr_pointer = *jump;}
@@ -668,7 +1336,7 @@ fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<Func
         }
 
         Bytecode::BranchIfn(src1, jump) => {
-            let num1 = read_integer_value(&variables, src1);
+            let num1 = read_integer_value(&frame, src1);
             match num1 {
             0 => {instr_pointer = *jump;}
             1 => {instr_pointer += 1;}
@@ -679,41 +1347,347 @@ fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<Func
             }
         }
 
-        Bytecode::Call(dest, function_index, parameters) => {
-             let function = &calls[*function_index];
+        // values outside of the table jump to the default.
+        Bytecode::Switch(src1, default, table) => {
+            let num1 = read_integer_value(&frame, src1);
+            if num1 >= 0 && (num1 as usize) < table.len() {
+                instr_pointer = table[num1 as usize];
+            } else {
+                instr_pointer = *default;
+            }
+        }
+
+        Bytecode::Call(dests, function_index, parameters) => {
+             let function = &program.functions[*function_index];
              let mut pass = vec![];
              for p in parameters {
-                  let num1: i32 = read_integer_value(&variables, p);
-                  pass.push(num1);
+                  pass.push(read_argument(&frame, p));
              }
 
-             let eax = run_bytecode(stdin, function, calls, &pass)?;
-             let dest = variables.get_mut(dest).unwrap();
-             *dest = eax;
+             let values = run_bytecode(stdin, output, function, program, frame.globals, frame.heap, &pass)?;
+             for (i, dest) in dests.iter().enumerate() {
+                  let value = values.get(i).cloned().unwrap_or_else(|| zero_argument(&frame, dest));
+                  write_argument(&mut frame, dest, &value);
+             }
              instr_pointer += 1;
         }
 
-        Bytecode::Return(src1) => {
-            let num = read_integer_value(&variables, src1);
-            return Ok(num);
+        Bytecode::Builtin(dests, builtin, parameters) => {
+            let arguments: Vec<i64> = parameters.iter().map(|p| read_integer_value(&frame, p)).collect();
+            let values = run_builtin(*builtin, &arguments)?;
+            for (i, dest) in dests.iter().enumerate() {
+                write_integer(&mut frame, dest, values.get(i).copied().unwrap_or(0));
+            }
+            instr_pointer += 1;
+        }
+
+        Bytecode::Bounds(index, length) => {
+            let index = read_integer_value(&frame, index);
+            let length = read_integer_value(&frame, length);
+            if index < 0 || index >= length {
+                let e = format!("Runtime Error: Array out of bounds. Index {}. Dimension Length {}.", index, length);
+                return error(MAX_LINE, e);
+            }
+            instr_pointer += 1;
+        }
+
+        Bytecode::Len(dest, array) => {
+            let length = frame.heap[array_index(&frame, array)].len() as i64;
+            write_integer(&mut frame, dest, length);
+            instr_pointer += 1;
+        }
+
+        Bytecode::Return(srcs) => {
+            let values = srcs.iter().map(|src| read_argument(&frame, src)).collect();
+            frame.heap.truncate(heap_start);
+            return Ok(values);
+        }
+
+        // strings are indexed by character, not by byte.
+        Bytecode::Str(id) => {
+            let value = string_mut(&mut frame, id);
+            value.clear();
+            instr_pointer += 1;
+        }
+
+        Bytecode::StrMov(dest, src) => {
+            let value = String::from(read_string_value(&frame, program, src));
+            let dest = string_mut(&mut frame, dest);
+            *dest = value;
+            instr_pointer += 1;
+        }
+
+        Bytecode::Concat(dest, src1, src2) => {
+            let value = String::from(read_string_value(&frame, program, src1)) + read_string_value(&frame, program, src2);
+            let dest = string_mut(&mut frame, dest);
+            *dest = value;
+            instr_pointer += 1;
+        }
+
+        Bytecode::StrLen(dest, src) => {
+            let length = read_string_value(&frame, program, src).chars().count() as i64;
+            write_integer(&mut frame, dest, length);
+            instr_pointer += 1;
+        }
+
+        Bytecode::CharAt(dest, src, index) => {
+            let index = read_integer_value(&frame, index);
+            let value = read_string_value(&frame, program, src);
+            let c = if index >= 0 { value.chars().nth(index as usize) } else { None };
+            let c = match c {
+            Some(c) => c as i64,
+            None => {
+                let e = format!("Runtime Error: String index out of bounds. Index {}. String Length {}.", index, value.chars().count());
+                return error(MAX_LINE, e);
+            }
+            };
+            write_integer(&mut frame, dest, c);
+            instr_pointer += 1;
+        }
+
+        // -1, 0 or 1, as the first string is before, equal to or after the second string.
+        Bytecode::StrCmp(dest, src1, src2) => {
+            let order = read_string_value(&frame, program, src1).cmp(read_string_value(&frame, program, src2));
+            write_integer(&mut frame, dest, order as i64);
+            instr_pointer += 1;
+        }
+
+        Bytecode::Chr(dest, src) => {
+            let num = read_integer_value(&frame, src);
+            let c = match u32::try_from(num).ok().and_then(char::from_u32) {
+            Some(c) => c,
+            _ => return error(MAX_LINE, format!("Runtime Error: {} is not a character.", num)),
+            };
+            let dest = string_mut(&mut frame, dest);
+            *dest = c.to_string();
+            instr_pointer += 1;
+        }
+
+        Bytecode::Float(id) => {
+            *float_mut(&mut frame, id) = 0.0;
+            instr_pointer += 1;
+        }
+
+        Bytecode::FloatMov(dest, src) => {
+            let value = read_float_value(&frame, src);
+            *float_mut(&mut frame, dest) = value;
+            instr_pointer += 1;
+        }
+
+        // the elements of a float array are stored as the bits of the float.
+        Bytecode::FloatArrayRead(dest, array, index) => {
+            let index = read_integer_value(&frame, index);
+            let array = &frame.heap[array_index(&frame, array)];
+            if index < 0 || index as usize >= array.len() {
+                return error(MAX_LINE, format!("Runtime Error: Array out of bounds. Index {}. Array Length {}.", index, array.len()));
+            }
+            let value = f64::from_bits(array[index as usize] as u64);
+            *float_mut(&mut frame, dest) = value;
+            instr_pointer += 1;
+        }
+
+        Bytecode::FloatArrayWrite(array, index, src) => {
+            let value = read_float_value(&frame, src);
+            let index = read_integer_value(&frame, index);
+            let array = array_mut(&mut frame, array);
+            if index < 0 || index as usize >= array.len() {
+                return error(MAX_LINE, format!("Runtime Error: Array out of bounds. Index {}. Array Length {}.", index, array.len()));
+            }
+            array[index as usize] = value.to_bits() as i64;
+            instr_pointer += 1;
+        }
+
+        Bytecode::OutFloat(src) => {
+            let _ = writeln!(output, "{}", format_float(read_float_value(&frame, src)));
+            instr_pointer += 1;
+        }
+
+        Bytecode::InFloat(id) => {
+            let mut buf = String::with_capacity(64);
+            loop {
+                match stdin.read_line(&mut buf) {
+                Ok(_) => {
+                     let token = buf.trim_end();
+                     match token.parse::<f64>() {
+                     Ok(num) if num.is_finite() => {
+                          *float_mut(&mut frame, id) = num;
+                          break;
+                     }
+
+                     _ => {
+                         println!("User Input Error. '{}' is not a valid number.", token);
+                         buf.clear();
+                     }
+
+                     }
+                }
+
+                Err(e) => {
+                     println!("Error. Failed to read from standard input correctly.");
+                     println!("{e}");
+                     println!("Please try again.");
+                }
+
+                }
+            }
+            instr_pointer += 1;
+        }
+
+        // float arithmetic follows IEEE 754, so dividing by zero gives inf or NaN instead of an error.
+        Bytecode::FloatAdd(dest, src1, src2) | Bytecode::FloatSub(dest, src1, src2)
+        | Bytecode::FloatMult(dest, src1, src2) | Bytecode::FloatDiv(dest, src1, src2) => {
+            let a = read_float_value(&frame, src1);
+            let b = read_float_value(&frame, src2);
+            let value = match instr {
+            Bytecode::FloatAdd(..) => a + b,
+            Bytecode::FloatSub(..) => a - b,
+            Bytecode::FloatMult(..) => a * b,
+            _ => a / b,
+            };
+            *float_mut(&mut frame, dest) = value;
+            instr_pointer += 1;
+        }
+
+        Bytecode::FloatLessThan(dest, src1, src2) | Bytecode::FloatLessEqual(dest, src1, src2)
+        | Bytecode::FloatNotEqual(dest, src1, src2) | Bytecode::FloatEqual(dest, src1, src2)
+        | Bytecode::FloatGreaterEqual(dest, src1, src2) | Bytecode::FloatGreaterThan(dest, src1, src2) => {
+            let a = read_float_value(&frame, src1);
+            let b = read_float_value(&frame, src2);
+            let value = match instr {
+            Bytecode::FloatLessThan(..) => a < b,
+            Bytecode::FloatLessEqual(..) => a <= b,
+            Bytecode::FloatNotEqual(..) => a != b,
+            Bytecode::FloatEqual(..) => a == b,
+            Bytecode::FloatGreaterEqual(..) => a >= b,
+            _ => a > b,
+            };
+            write_integer(&mut frame, dest, value as i64);
+            instr_pointer += 1;
+        }
+
+        Bytecode::IntToFloat(dest, src) => {
+            let value = read_integer_value(&frame, src) as f64;
+            *float_mut(&mut frame, dest) = value;
+            instr_pointer += 1;
+        }
+
+        // the fraction is dropped, so 2.7 becomes 2 and -2.7 becomes -2.
+        Bytecode::FloatToInt(dest, src) => {
+            let value = read_float_value(&frame, src);
+            let is_long = if *dest < 0 { frame.globals.longs.contains_key(dest) } else { frame.locals.longs.contains_key(dest) };
+            // -min is one past the largest value, and is exact as a float, unlike i64::MAX.
+            let min = if is_long { i64::MIN as f64 } else { i32::MIN as f64 };
+            let value = value.trunc();
+            if !(value >= min && value < -min) {
+                let kind = if is_long { "a long" } else { "an int" };
+                return error(MAX_LINE, format!("Runtime Error: {} does not fit in {}.", format_float(read_float_value(&frame, src)), kind));
+            }
+            write_integer(&mut frame, dest, value as i64);
+            instr_pointer += 1;
+        }
+
+        }
+    }
+
+    frame.heap.truncate(heap_start);
+    Ok(vec![])
+}
+
+// what a variable holds, such as "a float". the values passed to and returned from a function
+// must be of the same kind as the variables receiving them.
+fn variable_kind(function: &FunctionBytecode, globals: &HashMap<String, VariableType>, id: i32) -> &'static str {
+    let variables = if id < 0 { globals } else { &function.variables };
+    if variables.values().any(|v| matches!(v, VariableType::ArrayVar(i, _) | VariableType::ArrayRef(i) if *i == id)) {
+        "an array"
+    } else if variables.values().any(|v| matches!(v, VariableType::StringVar(i) if *i == id)) {
+        "a string"
+    } else if variables.values().any(|v| matches!(v, VariableType::FloatVar(i) if *i == id)) {
+        "a float"
+    } else if variables.values().any(|v| matches!(v, VariableType::FloatArrayVar(i, _) | VariableType::FloatArrayRef(i) if *i == id)) {
+        "a float array"
+    } else {
+        "an integer"
+    }
+}
+
+fn operand_kind(function: &FunctionBytecode, globals: &HashMap<String, VariableType>, op: &Op) -> &'static str {
+    match op {
+    Op::Var(id) => variable_kind(function, globals, *id),
+    Op::Num(_) => "an integer",
+    }
+}
+
+// the kinds of the values a function returns. every '%ret' of a function returns the same number and
+// kinds of values, and a function without a '%ret' can be called for any number of values.
+fn return_kinds(function: &FunctionBytecode, globals: &HashMap<String, VariableType>) -> Result<Option<Vec<&'static str>>, String> {
+    let mut kinds: Option<Vec<&'static str>> = None;
+    for bytecode in &function.body {
+        if let Bytecode::Return(srcs) = bytecode {
+            let returned: Vec<&'static str> = srcs.iter().map(|src| operand_kind(function, globals, src)).collect();
+            match &kinds {
+            Some(kinds) if kinds.len() != returned.len() => {
+                return Err(format!("Function '{}' returns {} values in one place and {} in another.", function.name, kinds.len(), returned.len()));
+            }
+            Some(kinds) if *kinds != returned => {
+                return Err(format!("Function '{}' returns {} in one place and {} in another.", function.name, kinds.join(", "), returned.join(", ")));
+            }
+            _ => {kinds = Some(returned);}
+            }
         }
+    }
+    if function.name == "main" && kinds.iter().flatten().any(|kind| *kind != "an integer") {
+        return Err(String::from("Function 'main' can only return an integer exit code."));
+    }
+    Ok(kinds)
+}
 
+// checks that a call stores the values a function returns in variables of the same kinds.
+fn check_call_dests(caller: &FunctionBytecode, globals: &HashMap<String, VariableType>, dests: &[i32], name: &str, returns: &Option<Vec<&'static str>>) -> Result<(), String> {
+    let returns = match returns {
+    Some(returns) => returns,
+    None => return Ok(()),
+    };
+    if dests.len() != returns.len() {
+        return Err(format!("'{name}' returns {} values, but the call stores {} values.", returns.len(), dests.len()));
+    }
+    for (i, (dest, kind)) in dests.iter().zip(returns).enumerate() {
+        let stored = variable_kind(caller, globals, *dest);
+        if stored != *kind {
+            return Err(format!("Return value {} of '{name}' is {kind}, but it is stored in {stored}.", i + 1));
         }
     }
+    Ok(())
+}
 
-    return Ok(0);
+// local variables and parameters are looked up first, so a local variable hides a
+// global variable with the same name from its declaration to the end of the function.
+fn lookup_variable<'a>(function: &'a FunctionBytecode, globals: &'a HashMap<String, VariableType>, ident: &String) -> Option<&'a VariableType> {
+    match function.variables.get(ident) {
+    Some(variable) => Some(variable),
+    None => globals.get(ident),
+    }
 }
 
-fn lookup_integer_variable_id(line: usize, function: &FunctionBytecode, ident: &String) -> Result<Op, IRError> {
-    if let Some(id) = function.variables.get(ident) {
+fn lookup_integer_variable_id(line: usize, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, ident: &String) -> Result<Op, IRError> {
+    if let Some(id) = lookup_variable(function, globals, ident) {
          match id {
-         VariableType::IntVar(id) => Ok(Op::Var(*id)),
+         VariableType::IntVar(id) | VariableType::LongVar(id) => Ok(Op::Var(*id)),
 
-         VariableType::ArrayVar(_,_) => {
+         VariableType::ArrayVar(_,_) | VariableType::ArrayRef(_) | VariableType::FloatArrayVar(_,_) | VariableType::FloatArrayRef(_) => {
              let f = format!("invalid operand. '{}' is an array, not an integer.", ident);
              return error(line, f);
          }
 
+         VariableType::StringVar(_) => {
+             let f = format!("invalid operand. '{}' is a string, not an integer.", ident);
+             return error(line, f);
+         }
+
+         VariableType::FloatVar(_) => {
+             let f = format!("invalid operand. '{}' is a float, not an integer.", ident);
+             return error(line, f);
+         }
+
          }
     } else {
          let f = format!("invalid instruction. identifier '{}' has not been declared.", ident);
@@ -721,16 +1695,26 @@ fn lookup_integer_variable_id(line: usize, function: &FunctionBytecode, ident: &
     }
 }
 
-fn lookup_variable_dest_id(line: usize, function: &FunctionBytecode, ident: &String) -> Result<i32, IRError> {
-    if let Some(id) = function.variables.get(ident) {
+fn lookup_variable_dest_id(line: usize, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, ident: &String) -> Result<i32, IRError> {
+    if let Some(id) = lookup_variable(function, globals, ident) {
          match id {
-         VariableType::IntVar(id) => Ok(*id),
+         VariableType::IntVar(id) | VariableType::LongVar(id) => Ok(*id),
 
-         VariableType::ArrayVar(_,_) => {
+         VariableType::ArrayVar(_,_) | VariableType::ArrayRef(_) | VariableType::FloatArrayVar(_,_) | VariableType::FloatArrayRef(_) => {
              let f = format!("invalid operand. '{}' is an array, not an integer.", ident);
              return error(line, f);
          }
 
+         VariableType::StringVar(_) => {
+             let f = format!("invalid operand. '{}' is a string, not an integer.", ident);
+             return error(line, f);
+         }
+
+         VariableType::FloatVar(_) => {
+             let f = format!("invalid operand. '{}' is a float, not an integer.", ident);
+             return error(line, f);
+         }
+
          }
     } else {
          let f = format!("invalid instruction. identifier '{}' has not been declared.", ident);
@@ -738,7 +1722,74 @@ fn lookup_variable_dest_id(line: usize, function: &FunctionBytecode, ident: &Str
     }
 }
 
-fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut FunctionBytecode, labels_hash: &mut HashMap<String, usize>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Bytecode, IRError> {
+fn lookup_float_variable_id(line: usize, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, ident: &String) -> Result<i32, IRError> {
+    match lookup_variable(function, globals, ident) {
+    Some(VariableType::FloatVar(id)) => Ok(*id),
+    Some(_) => error(line, format!("invalid operand. '{}' is not a float.", ident)),
+    None => error(line, format!("invalid instruction. identifier '{}' has not been declared.", ident)),
+    }
+}
+
+// a float variable or a number. integers such as '2' can be used as floats too.
+// parses the 'array + index]' of an element of a float array, after the '['.
+fn parse_float_element(line: usize, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<(i32, Op), IRError> {
+    let array = match next_result(tokens, idx) {
+    IRTok::Var(ident) => match lookup_variable(function, globals, ident) {
+        Some(VariableType::FloatArrayVar(id, _)) | Some(VariableType::FloatArrayRef(id)) => *id,
+        Some(_) => return error(line, format!("invalid '%mov' statement. {} is not a float array.", ident)),
+        None => return error(line, format!("invalid instruction. no such identifier '{}'.", ident)),
+    },
+    _ => return error(line, String::from("invalid '%mov' statement. expected '%mov x, [array + index]'")),
+    };
+    if !matches!(next_result(tokens, idx), IRTok::Plus) {
+        return error(line, String::from("invalid '%mov' statement. expected '%mov x, [array + index]'"));
+    }
+    let index = match next_result(tokens, idx) {
+    IRTok::Var(ident) => lookup_integer_variable_id(line, function, globals, ident)?,
+    IRTok::Num(num) => Op::Num(*num),
+    _ => return error(line, String::from("invalid '%mov' statement. expected '%mov x, [array + index]'")),
+    };
+    if !matches!(next_result(tokens, idx), IRTok::RBrace) {
+        return error(line, String::from("invalid '%mov' statement. expected '%mov x, [array + index]'"));
+    }
+    Ok((array, index))
+}
+
+fn parse_float_operand(line: usize, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<FloatOp, IRError> {
+    match next_result(tokens, idx) {
+    IRTok::Var(ident) => Ok(FloatOp::Var(lookup_float_variable_id(line, function, globals, ident)?)),
+    IRTok::Decimal(num) => Ok(FloatOp::Num(*num)),
+    IRTok::Num(num) => Ok(FloatOp::Num(*num as f64)),
+    _ => error(line, String::from("invalid operand. expected a float variable or a number.")),
+    }
+}
+
+fn lookup_string_variable_id(line: usize, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, ident: &String) -> Result<i32, IRError> {
+    match lookup_variable(function, globals, ident) {
+    Some(VariableType::StringVar(id)) => Ok(*id),
+    Some(_) => error(line, format!("invalid operand. '{}' is not a string.", ident)),
+    None => error(line, format!("invalid instruction. identifier '{}' has not been declared.", ident)),
+    }
+}
+
+// a string variable, or the name of a string constant. string constants can be declared
+// after the functions that use them, so their names are looked up once the whole program is parsed.
+fn parse_string_operand(line: usize, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, tokens: &[IRTok], idx: &mut usize) -> Result<StrOp, IRError> {
+    match &tokens[*idx] {
+    IRTok::Var(ident) => {
+        let op = match lookup_variable(function, globals, ident) {
+        Some(VariableType::StringVar(id)) => StrOp::Var(*id),
+        Some(_) => return error(line, format!("invalid operand. '{}' is not a string.", ident)),
+        None => StrOp::Data(*idx),
+        };
+        *idx += 1;
+        Ok(op)
+    }
+    _ => error(line, String::from("invalid operand. expected a string variable or a string constant.")),
+    }
+}
+
+fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut FunctionBytecode, globals: &HashMap<String, VariableType>, labels_hash: &mut HashMap<String, usize>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Bytecode, IRError> {
 
     // get rid of newlines.
     while matches!(tokens[*idx], IRTok::EndInstr) {
@@ -749,8 +1800,9 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
     let bytecode: Bytecode;
     match &tokens[*idx] {
 
-    // declarations.
-    IRTok::Int => {
+    // declarations. '%long' variables hold 64 bit integers.
+    IRTok::Int | IRTok::Long => {
+        let is_long = matches!(tokens[*idx], IRTok::Long);
         *idx += 1;
         let ident = match next_result(tokens, idx) {
         IRTok::Var(ident) => ident,
@@ -760,6 +1812,10 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         if let Some(_) = function.variables.get(ident) {
              let f = format!("invalid instruction. identifier '{}' declared too many times", ident);
              return error(*serialized_line, f);
+        } else if is_long {
+             let id = get_id(function);
+             function.variables.insert(ident.clone(), VariableType::LongVar(id));
+             bytecode = Bytecode::Long(id);
         } else {
              let id = get_id(function);
              function.variables.insert(ident.clone(), VariableType::IntVar(id));
@@ -767,7 +1823,32 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         }
     }
 
-    IRTok::IntArray => {
+    IRTok::Str | IRTok::Float => {
+        let is_float = matches!(tokens[*idx], IRTok::Float);
+        *idx += 1;
+        let ident = match next_result(tokens, idx) {
+        IRTok::Var(ident) => ident,
+        _ if is_float => return error(*serialized_line, String::from("invalid instruction. expected identifier like '%float variable'")),
+        _ => return error(*serialized_line, String::from("invalid instruction. expected identifier like '%string variable'")),
+        };
+
+        if let Some(_) = function.variables.get(ident) {
+             let f = format!("invalid instruction. identifier '{}' declared too many times", ident);
+             return error(*serialized_line, f);
+        } else if is_float {
+             let id = get_id(function);
+             function.variables.insert(ident.clone(), VariableType::FloatVar(id));
+             bytecode = Bytecode::Float(id);
+        } else {
+             let id = get_id(function);
+             function.variables.insert(ident.clone(), VariableType::StringVar(id));
+             bytecode = Bytecode::Str(id);
+        }
+    }
+
+    // '%float[] array, 10' is declared like '%int[]'.
+    IRTok::IntArray | IRTok::FloatArray => {
+        let is_float = matches!(tokens[*idx], IRTok::FloatArray);
         *idx += 1;
         let ident = match next_result(tokens, idx) {
         IRTok::Var(ident) => ident,
@@ -789,27 +1870,36 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         if num <= 0 {
           return error(*serialized_line, String::from("array size cannot be less than or equal to zero."));
         }
+        if num > i32::MAX as i64 {
+          return error(*serialized_line, String::from("array size does not fit in a 32 bit integer."));
+        }
 
         if let Some(_) = function.variables.get(ident) {
              let f = format!("invalid instruction. identifier '{}' declared too many times", ident);
              return error(*serialized_line, f);
+        } else if is_float {
+             let id = get_id(function);
+             function.variables.insert(ident.clone(), VariableType::FloatArrayVar(id, num as i32));
+             bytecode = Bytecode::FloatArray(id, num as i32);
         } else {
              let id = get_id(function);
-             function.variables.insert(ident.clone(), VariableType::ArrayVar(id, num));
-             bytecode = Bytecode::IntArray(id, num);
+             function.variables.insert(ident.clone(), VariableType::ArrayVar(id, num as i32));
+             bytecode = Bytecode::IntArray(id, num as i32);
         }
     }
 
     // function calling routines.
+    // a call stores every value the function returns, such as '%call q, r, divmod(a, b)'.
     IRTok::Call => {
         *idx += 1;
-        let op = match next_result(tokens, idx) {
-        IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, ident)?,
-        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%call value, function(a,b)'")),
-        };
-
-        if !matches!(next_result(tokens, idx), IRTok::Comma) {
-            return error(*serialized_line, String::from("invalid instruction. missing comma ',' in between '%call value, function(a,b)'"));
+        let mut dests = vec![];
+        while let (Some(IRTok::Var(ident)), Some(IRTok::Comma)) = (tokens.get(*idx), tokens.get(*idx + 1)) {
+            let dest = match lookup_variable(function, globals, ident) {
+            Some(VariableType::FloatVar(id)) | Some(VariableType::StringVar(id)) => *id,
+            _ => lookup_variable_dest_id(*serialized_line, function, globals, ident)?,
+            };
+            dests.push(dest);
+            *idx += 2;
         }
 
         let func_id = match tokens[*idx] {
@@ -831,7 +1921,16 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
             IRTok::RParen => break,
             IRTok::Var(ident) => {
                 *idx += 1;
-                let param = lookup_integer_variable_id(*serialized_line, function, ident)?;
+                // integers, arrays, strings and floats can all be passed. parse_ir checks that they match the parameters.
+                let param = match lookup_variable(function, globals, ident) {
+                Some(VariableType::IntVar(id)) | Some(VariableType::LongVar(id)) | Some(VariableType::ArrayVar(id, _)) | Some(VariableType::ArrayRef(id))
+                | Some(VariableType::StringVar(id)) | Some(VariableType::FloatVar(id)) | Some(VariableType::FloatArrayVar(id, _))
+                | Some(VariableType::FloatArrayRef(id)) => Op::Var(*id),
+                None => {
+                    let f = format!("invalid instruction. identifier '{}' has not been declared.", ident);
+                    return error(*serialized_line, f);
+                }
+                };
                 parameters.push(param);
                 if matches!(tokens[*idx], IRTok::Comma) {
                     *idx += 1;
@@ -853,50 +1952,161 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         if !matches!(next_result(tokens, idx), IRTok::RParen) {
             return error(*serialized_line, String::from("invalid instruction. missing ')' in between '%call value, function(a,b)'"));
         }
-        bytecode = Bytecode::Call(op, func_id, parameters);
+        bytecode = Bytecode::Call(dests, func_id, parameters);
     }
 
+    // returns any number of values, such as '%ret q, r'.
     IRTok::Return => {
         *idx += 1;
-        let op = match next_result(tokens, idx) {
-        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
+        let mut srcs = vec![];
+        while !matches!(tokens[*idx], IRTok::EndInstr) {
+            if !srcs.is_empty() && !matches!(next_result(tokens, idx), IRTok::Comma) {
+                return error(*serialized_line, String::from("invalid instruction. missing comma ',' in between '%ret a, b'"));
+            }
+            let op = match next_result(tokens, idx) {
+            IRTok::Var(ident) => match lookup_variable(function, globals, ident) {
+                Some(VariableType::FloatVar(id)) | Some(VariableType::StringVar(id)) => Op::Var(*id),
+                _ => lookup_integer_variable_id(*serialized_line, function, globals, ident)?,
+            },
+            IRTok::Num(num) => Op::Num(*num),
+            _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%ret variable'")),
+            };
+            srcs.push(op);
+        }
+        bytecode = Bytecode::Return(srcs);
+    }
+
+    // checks one index of a multi-dimensional array, such as '%bounds j, 5'.
+    IRTok::Bounds => {
+        *idx += 1;
+        let index = match next_result(tokens, idx) {
+        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, globals, ident)?,
+        IRTok::Num(num) => Op::Num(*num),
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%bounds index, 10'")),
+        };
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(*serialized_line, String::from("invalid instruction. expected format like '%bounds index, 10'"));
+        }
+        let length = match next_result(tokens, idx) {
+        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, globals, ident)?,
         IRTok::Num(num) => Op::Num(*num),
-        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%ret variable'")),
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%bounds index, 10'")),
+        };
+        bytecode = Bytecode::Bounds(index, length);
+    }
+
+    // the length of an array or a string, such as '%len n, array'.
+    IRTok::Len => {
+        *idx += 1;
+        let dest = match next_result(tokens, idx) {
+        IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, globals, ident)?,
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%len length, array'")),
+        };
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(*serialized_line, String::from("invalid instruction. expected format like '%len length, array'"));
+        }
+        let array = match &tokens[*idx] {
+        IRTok::Var(ident) => {
+            match lookup_variable(function, globals, ident) {
+            Some(VariableType::ArrayVar(id, _)) | Some(VariableType::ArrayRef(id))
+            | Some(VariableType::FloatArrayVar(id, _)) | Some(VariableType::FloatArrayRef(id)) => Some(*id),
+            Some(VariableType::IntVar(_)) | Some(VariableType::LongVar(_)) | Some(VariableType::FloatVar(_)) => {
+                let f = format!("invalid '%len' statement. {} is a number, not an array.", ident);
+                return error(*serialized_line, f);
+            }
+            Some(VariableType::StringVar(_)) | None => None,
+            }
+        }
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%len length, array'")),
+        };
+        bytecode = match array {
+        Some(array) => {
+            *idx += 1;
+            Bytecode::Len(dest, array)
+        }
+        None => Bytecode::StrLen(dest, parse_string_operand(*serialized_line, function, globals, tokens, idx)?),
         };
-        bytecode = Bytecode::Return(op);
     }
 
     // input/output routines.
     IRTok::Out => {
         *idx += 1;
         let src = match next_result(tokens, idx) {
-        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
+        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, globals, ident)?,
         IRTok::Num(num) => Op::Num(*num),
         _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%out variable'")),
         };
         bytecode = Bytecode::Out(src);
     }
 
-    IRTok::In => {
+    IRTok::OutChar => {
         *idx += 1;
         let src = match next_result(tokens, idx) {
-        IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, ident)?,
+        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, globals, ident)?,
+        IRTok::Num(num) => Op::Num(*num),
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%outc variable'")),
+        };
+        bytecode = Bytecode::OutChar(src);
+    }
+
+    IRTok::OutStr => {
+        *idx += 1;
+        if !matches!(tokens[*idx], IRTok::Var(_)) {
+            return error(*serialized_line, String::from("invalid instruction. expected format like '%outs string'"));
+        }
+        bytecode = Bytecode::OutStr(parse_string_operand(*serialized_line, function, globals, tokens, idx)?);
+    }
+
+    // '%input' into a string reads a whole line, and into a float reads a number such as '2.5'.
+    IRTok::In => {
+        *idx += 1;
+        bytecode = match next_result(tokens, idx) {
+        IRTok::Var(ident) if matches!(lookup_variable(function, globals, ident), Some(VariableType::StringVar(_))) => {
+            Bytecode::InStr(lookup_string_variable_id(*serialized_line, function, globals, ident)?)
+        }
+        IRTok::Var(ident) if matches!(lookup_variable(function, globals, ident), Some(VariableType::FloatVar(_))) => {
+            Bytecode::InFloat(lookup_float_variable_id(*serialized_line, function, globals, ident)?)
+        }
+        IRTok::Var(ident) => Bytecode::In(lookup_variable_dest_id(*serialized_line, function, globals, ident)?),
         _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%input variable'")),
         };
-        bytecode = Bytecode::In(src);
     }
 
     // mathematical operators.
     IRTok::Mov => {
         *idx += 1;
         match next_result(tokens, idx) {
-        IRTok::Var(ident) => {
-            let dest = lookup_variable_dest_id(*serialized_line, function, ident)?;
+        // copying a string, such as '%mov name, _str1'.
+        IRTok::Var(ident) if matches!(lookup_variable(function, globals, ident), Some(VariableType::StringVar(_))) => {
+            let dest = lookup_string_variable_id(*serialized_line, function, globals, ident)?;
+            if !matches!(next_result(tokens, idx), IRTok::Comma) {
+                return error(*serialized_line, String::from("invalid instruction. missing comma. expected format like '%mov variable, 10'"));
+            }
+            bytecode = Bytecode::StrMov(dest, parse_string_operand(*serialized_line, function, globals, tokens, idx)?);
+        }
 
+        // copying a float, such as '%mov x, 2.5', or reading an element of a float array, such as '%mov x, [a + i]'.
+        IRTok::Var(ident) if matches!(lookup_variable(function, globals, ident), Some(VariableType::FloatVar(_))) => {
+            let dest = lookup_float_variable_id(*serialized_line, function, globals, ident)?;
             if !matches!(next_result(tokens, idx), IRTok::Comma) {
                 return error(*serialized_line, String::from("invalid instruction. missing comma. expected format like '%mov variable, 10'"));
             }
-            
+            if matches!(tokens[*idx], IRTok::LBrace) {
+                *idx += 1;
+                let (array, index) = parse_float_element(*serialized_line, function, globals, tokens, idx)?;
+                bytecode = Bytecode::FloatArrayRead(dest, array, index);
+            } else {
+                bytecode = Bytecode::FloatMov(dest, parse_float_operand(*serialized_line, function, globals, tokens, idx)?);
+            }
+        }
+
+        IRTok::Var(ident) => {
+            let dest = lookup_variable_dest_id(*serialized_line, function, globals, ident)?;
+
+            if !matches!(next_result(tokens, idx), IRTok::Comma) {
+                return error(*serialized_line, String::from("invalid instruction. missing comma. expected format like '%mov variable, 10'"));
+            }
+            
             let src = match next_result(tokens, idx) {
             IRTok::Var(ident) => {
                 if matches!(tokens[*idx], IRTok::LParen) {
@@ -904,14 +2114,24 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
                 }
 
 
-                if let Some(id) = function.variables.get(ident) {
+                if let Some(id) = lookup_variable(function, globals, ident) {
                      match id {
-                     VariableType::IntVar(id) => MemRead::IntVar(*id),
+                     VariableType::IntVar(id) | VariableType::LongVar(id) => MemRead::IntVar(*id),
          
-                     VariableType::ArrayVar(_,_) => {
+                     VariableType::ArrayVar(_,_) | VariableType::ArrayRef(_) | VariableType::FloatArrayVar(_,_) | VariableType::FloatArrayRef(_) => {
                          let f = format!("invalid '%mov' statement. {} is an array, not an integer.", ident);
                          return error(*serialized_line, f);
                      }
+
+                     VariableType::StringVar(_) => {
+                         let f = format!("invalid '%mov' statement. {} is a string, not an integer.", ident);
+                         return error(*serialized_line, f);
+                     }
+
+                     VariableType::FloatVar(_) => {
+                         let f = format!("invalid '%mov' statement. {} is a float, not an integer. convert it with '%ftoi'.", ident);
+                         return error(*serialized_line, f);
+                     }
          
                      }
                 } else {
@@ -923,9 +2143,9 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
             IRTok::LBrace => {
                 match (next_result(tokens,idx), next_result(tokens,idx), next_result(tokens,idx), next_result(tokens,idx)) {
                 (IRTok::Var(array), IRTok::Plus, IRTok::Num(index), IRTok::RBrace) => {
-                    if let Some(id) = function.variables.get(array) {
+                    if let Some(id) = lookup_variable(function, globals, array) {
                         match id {
-                        VariableType::ArrayVar(id,_) => {
+                        VariableType::ArrayVar(id,_) | VariableType::ArrayRef(id) => {
                             MemRead::ArrayRead(*id, Op::Num(*index))
                         }
                         _ => {
@@ -941,11 +2161,11 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
                 }
 
                 (IRTok::Var(array), IRTok::Plus, IRTok::Var(variable), IRTok::RBrace) => {
-                    if let Some(id) = function.variables.get(array) {
+                    if let Some(id) = lookup_variable(function, globals, array) {
                         match id {
-                        VariableType::ArrayVar(id,_) => {
+                        VariableType::ArrayVar(id,_) | VariableType::ArrayRef(id) => {
                             
-                            MemRead::ArrayRead(*id, lookup_integer_variable_id(*serialized_line, function, variable)?)
+                            MemRead::ArrayRead(*id, lookup_integer_variable_id(*serialized_line, function, globals, variable)?)
                         }
                         _ => {
                            let f = format!("invalid '%mov' statement. {} is an integer, not an array.", array);
@@ -965,25 +2185,41 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
 
             }
 
-            IRTok::Num(num) => MemRead::Number(*num),
+            IRTok::Num(num) => {
+                if matches!(lookup_variable(function, globals, ident), Some(VariableType::IntVar(_))) && i32::try_from(*num).is_err() {
+                    let f = format!("invalid '%mov' statement. {} does not fit in the 32 bit integer '{}'.", num, ident);
+                    return error(*serialized_line, f);
+                }
+                MemRead::Number(*num)
+            }
             _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov variable, 10'")),
             };
 
             bytecode = Bytecode::Mov(MemWrite::IntVar(dest), src);
         }
 
+        // writing an element of an array. a float array is written with a float, such as '%mov [a + i], 2.5'.
         IRTok::LBrace => {
-            let dest = match next_result(tokens, idx) {
+            let (dest, is_float) = match next_result(tokens, idx) {
             IRTok::Var(ident) => {
-                if let Some(id) = function.variables.get(ident) {
+                if let Some(id) = lookup_variable(function, globals, ident) {
                     match id {
-                    VariableType::IntVar(_) => {
-                        let f = format!("invalid '%mov' statement. {} is an array, not an integer.", ident);
+                    VariableType::IntVar(_) | VariableType::LongVar(_) => {
+                        let f = format!("invalid '%mov' statement. {} is an integer, not an array.", ident);
                         return error(*serialized_line, f);
                     }
          
-                    VariableType::ArrayVar(dest,_) => {
-                        *dest
+                    VariableType::ArrayVar(dest,_) | VariableType::ArrayRef(dest) => {
+                        (*dest, false)
+                    }
+
+                    VariableType::FloatArrayVar(dest,_) | VariableType::FloatArrayRef(dest) => {
+                        (*dest, true)
+                    }
+
+                    VariableType::StringVar(_) | VariableType::FloatVar(_) => {
+                        let f = format!("invalid '%mov' statement. {} is not an array.", ident);
+                        return error(*serialized_line, f);
                     }
 
                     }
@@ -1000,7 +2236,7 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
             }
 
             let index = match next_result(tokens, idx) {
-            IRTok::Var(id) => lookup_integer_variable_id(*serialized_line, function, id)?,
+            IRTok::Var(id) => lookup_integer_variable_id(*serialized_line, function, globals, id)?,
 
             IRTok::Num(num) => Op::Num(*num),
             _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'")),
@@ -1014,33 +2250,47 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
                 return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
             }
 
-            let src = match next_result(tokens, idx) {
-            IRTok::Var(ident) => {
-                if let Some(id) = function.variables.get(ident) {
-                     match id {
-                     VariableType::IntVar(id) => MemRead::IntVar(*id),
+            if is_float {
+                bytecode = Bytecode::FloatArrayWrite(dest, index, parse_float_operand(*serialized_line, function, globals, tokens, idx)?);
+            } else {
+                let src = match next_result(tokens, idx) {
+                IRTok::Var(ident) => {
+                    if let Some(id) = lookup_variable(function, globals, ident) {
+                         match id {
+                         VariableType::IntVar(id) | VariableType::LongVar(id) => MemRead::IntVar(*id),
          
-                     VariableType::ArrayVar(_,_) => {
-                         let f = format!("invalid '%mov' statement. {} is an array, not an integer.", ident);
-                         return error(*serialized_line, f);
-                     }
+                         VariableType::ArrayVar(_,_) | VariableType::ArrayRef(_) | VariableType::FloatArrayVar(_,_) | VariableType::FloatArrayRef(_) => {
+                             let f = format!("invalid '%mov' statement. {} is an array, not an integer.", ident);
+                             return error(*serialized_line, f);
+                         }
+
+                         VariableType::StringVar(_) => {
+                             let f = format!("invalid '%mov' statement. {} is a string, not an integer.", ident);
+                             return error(*serialized_line, f);
+                         }
+
+                         VariableType::FloatVar(_) => {
+                             let f = format!("invalid '%mov' statement. {} is a float, not an integer. convert it with '%ftoi'.", ident);
+                             return error(*serialized_line, f);
+                         }
          
-                     }
-                } else {
-                     let f = format!("invalid instruction. identifier '{}' has not been defined.", ident);
-                     return error(*serialized_line, f);
+                         }
+                    } else {
+                         let f = format!("invalid instruction. identifier '{}' has not been defined.", ident);
+                         return error(*serialized_line, f);
+                    }
                 }
-            }
 
-            IRTok::LBrace => {
-                return error(*serialized_line, String::from("invalid instruction. cannot perform write and read at the same time. i.e. operations such as %mov [array + 0], [array + 1] are illegal. split the instruction into multiple lines to get the desired bytecode.\n"));
-            }
+                IRTok::LBrace => {
+                    return error(*serialized_line, String::from("invalid instruction. cannot perform write and read at the same time. i.e. operations such as %mov [array + 0], [array + 1] are illegal. split the instruction into multiple lines to get the desired bytecode.\n"));
+                }
 
-            IRTok::Num(num) => MemRead::Number(*num),
-            _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov variable, 10'")),
-            };
+                IRTok::Num(num) => MemRead::Number(*num),
+                _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov variable, 10'")),
+                };
 
-            bytecode = Bytecode::Mov(MemWrite::ArrayWrite(dest, index), src);
+                bytecode = Bytecode::Mov(MemWrite::ArrayWrite(dest, index), src);
+            }
         }
 
         _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov variable, 10'")),
@@ -1050,68 +2300,105 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
 
     IRTok::Add => {
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
         bytecode = Bytecode::Add(dest, src1, src2);
     }
 
     IRTok::Sub => {
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
         bytecode = Bytecode::Sub(dest, src1, src2);
     }
 
     IRTok::Mult => {
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
         bytecode = Bytecode::Mult(dest, src1, src2);
     }
 
     IRTok::Div => {
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
         bytecode = Bytecode::Div(dest, src1, src2);
     }
 
     IRTok::Mod => {
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
         bytecode = Bytecode::Mod(dest, src1, src2);
     }
 
+    // bitwise operators.
+    IRTok::And => {
+        *idx += 1;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
+        bytecode = Bytecode::And(dest, src1, src2);
+    }
+
+    IRTok::Or => {
+        *idx += 1;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
+        bytecode = Bytecode::Or(dest, src1, src2);
+    }
+
+    IRTok::Xor => {
+        *idx += 1;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
+        bytecode = Bytecode::Xor(dest, src1, src2);
+    }
+
+    IRTok::Not => {
+        *idx += 1;
+        let (dest, src) = addr_code2(*serialized_line, function, globals, tokens, idx)?;
+        bytecode = Bytecode::Not(dest, src);
+    }
+
+    IRTok::ShiftLeft => {
+        *idx += 1;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
+        bytecode = Bytecode::Shl(dest, src1, src2);
+    }
+
+    IRTok::ShiftRight => {
+        *idx += 1;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
+        bytecode = Bytecode::Shr(dest, src1, src2);
+    }
+
     // comparison operators.
     IRTok::LessThan => {
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
         bytecode = Bytecode::LessThan(dest, src1, src2);
     }
 
     IRTok::LessEqual => {
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
         bytecode = Bytecode::LessEqual(dest, src1, src2);
     }
 
     IRTok::NotEqual => {
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
         bytecode = Bytecode::NotEqual(dest, src1, src2);
     }
 
     IRTok::Equal => {
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
         bytecode = Bytecode::Equal(dest, src1, src2);
     }
 
     IRTok::GreaterEqual => {
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
         bytecode = Bytecode::GreaterEqual(dest, src1, src2);
     }
 
     IRTok::GreaterThan => {
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        let (dest, src1, src2) = addr_code3(*serialized_line, function, globals, tokens, idx)?;
         bytecode = Bytecode::GreaterThan(dest, src1, src2);
     }
 
@@ -1142,10 +2429,37 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         }
     }
 
+    // jump tables, such as '%switch value, :default, :case0, :case1'.
+    IRTok::Switch => {
+        *idx += 1;
+        let src = match next_result(tokens, idx) {
+        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, globals, ident)?,
+        IRTok::Num(num) => Op::Num(*num),
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%switch value, :default, :case0, :case1'")),
+        };
+
+        let mut labels = vec![];
+        while matches!(tokens[*idx], IRTok::Comma) {
+            *idx += 1;
+            match &tokens[*idx] {
+            IRTok::Label(_) => {
+                labels.push(*idx);
+                *idx += 1;
+            }
+            _ => return error(*serialized_line, String::from("%switch requires labels such as '%switch value, :default, :case0, :case1'")),
+            }
+        }
+
+        if labels.len() < 2 {
+            return error(*serialized_line, String::from("%switch requires a default label and at least one case label, such as '%switch value, :default, :case0'"));
+        }
+        bytecode = Bytecode::Switch(src, labels[0], labels[1..].to_vec());
+    }
+
     IRTok::BranchIf => {
         *idx += 1;
         let dest = match next_result(tokens, idx) {
-        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
+        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, globals, ident)?,
         IRTok::Num(num) => Op::Num(*num),
         _ => return error(*serialized_line, String::from("%branch_if requires an identifier 'TF'")),
         };
@@ -1171,7 +2485,7 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
     IRTok::BranchIfNot => {
         *idx += 1;
         let dest = match next_result(tokens, idx) {
-        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
+        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, globals, ident)?,
         IRTok::Num(num) => Op::Num(*num),
         _ => return error(*serialized_line, String::from("%branch_ifn requires an identifier 'TF'")),
         };
@@ -1194,6 +2508,133 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         }
     }
 
+    // strings, such as '%concat dest, string1, string2'.
+    IRTok::Concat | IRTok::StrCmp => {
+        let is_concat = matches!(tokens[*idx], IRTok::Concat);
+        let format = if is_concat { "'%concat dest, string1, string2'" } else { "'%strcmp dest, string1, string2'" };
+        *idx += 1;
+        let dest = match next_result(tokens, idx) {
+        IRTok::Var(ident) if is_concat => lookup_string_variable_id(*serialized_line, function, globals, ident)?,
+        IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, globals, ident)?,
+        _ => return error(*serialized_line, format!("invalid instruction. expected format like {format}")),
+        };
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(*serialized_line, format!("invalid instruction. expected format like {format}"));
+        }
+        let src1 = parse_string_operand(*serialized_line, function, globals, tokens, idx)?;
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(*serialized_line, format!("invalid instruction. expected format like {format}"));
+        }
+        let src2 = parse_string_operand(*serialized_line, function, globals, tokens, idx)?;
+        bytecode = if is_concat { Bytecode::Concat(dest, src1, src2) } else { Bytecode::StrCmp(dest, src1, src2) };
+    }
+
+    IRTok::CharAt => {
+        *idx += 1;
+        let dest = match next_result(tokens, idx) {
+        IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, globals, ident)?,
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%charat dest, string, index'")),
+        };
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(*serialized_line, String::from("invalid instruction. expected format like '%charat dest, string, index'"));
+        }
+        let src = parse_string_operand(*serialized_line, function, globals, tokens, idx)?;
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(*serialized_line, String::from("invalid instruction. expected format like '%charat dest, string, index'"));
+        }
+        let index = match next_result(tokens, idx) {
+        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, globals, ident)?,
+        IRTok::Num(num) => Op::Num(*num),
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%charat dest, string, index'")),
+        };
+        bytecode = Bytecode::CharAt(dest, src, index);
+    }
+
+    IRTok::Chr => {
+        *idx += 1;
+        let dest = match next_result(tokens, idx) {
+        IRTok::Var(ident) => lookup_string_variable_id(*serialized_line, function, globals, ident)?,
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%chr string, character'")),
+        };
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(*serialized_line, String::from("invalid instruction. expected format like '%chr string, character'"));
+        }
+        let src = match next_result(tokens, idx) {
+        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, globals, ident)?,
+        IRTok::Num(num) => Op::Num(*num),
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%chr string, character'")),
+        };
+        bytecode = Bytecode::Chr(dest, src);
+    }
+
+    // floats, such as '%fadd dest, x, 2.5'. arithmetic goes into a float, and comparisons go into an integer.
+    IRTok::FloatAdd | IRTok::FloatSub | IRTok::FloatMult | IRTok::FloatDiv
+    | IRTok::FloatLessThan | IRTok::FloatLessEqual | IRTok::FloatNotEqual | IRTok::FloatEqual | IRTok::FloatGreaterEqual | IRTok::FloatGreaterThan => {
+        let opcode = &tokens[*idx];
+        let is_arithmetic = matches!(opcode, IRTok::FloatAdd | IRTok::FloatSub | IRTok::FloatMult | IRTok::FloatDiv);
+        *idx += 1;
+        let dest = match next_result(tokens, idx) {
+        IRTok::Var(ident) if is_arithmetic => lookup_float_variable_id(*serialized_line, function, globals, ident)?,
+        IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, globals, ident)?,
+        _ => return error(*serialized_line, String::from("invalid dest.")),
+        };
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(*serialized_line, String::from("invalid instruction. expected comma between dest and src1."));
+        }
+        let src1 = parse_float_operand(*serialized_line, function, globals, tokens, idx)?;
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(*serialized_line, String::from("invalid instruction. expected comma between src1 and src2."));
+        }
+        let src2 = parse_float_operand(*serialized_line, function, globals, tokens, idx)?;
+        bytecode = match opcode {
+        IRTok::FloatAdd => Bytecode::FloatAdd(dest, src1, src2),
+        IRTok::FloatSub => Bytecode::FloatSub(dest, src1, src2),
+        IRTok::FloatMult => Bytecode::FloatMult(dest, src1, src2),
+        IRTok::FloatDiv => Bytecode::FloatDiv(dest, src1, src2),
+        IRTok::FloatLessThan => Bytecode::FloatLessThan(dest, src1, src2),
+        IRTok::FloatLessEqual => Bytecode::FloatLessEqual(dest, src1, src2),
+        IRTok::FloatNotEqual => Bytecode::FloatNotEqual(dest, src1, src2),
+        IRTok::FloatEqual => Bytecode::FloatEqual(dest, src1, src2),
+        IRTok::FloatGreaterEqual => Bytecode::FloatGreaterEqual(dest, src1, src2),
+        _ => Bytecode::FloatGreaterThan(dest, src1, src2),
+        };
+    }
+
+    IRTok::OutFloat => {
+        *idx += 1;
+        bytecode = Bytecode::OutFloat(parse_float_operand(*serialized_line, function, globals, tokens, idx)?);
+    }
+
+    // conversions between integers and floats, such as '%itof x, n' and '%ftoi n, x'.
+    IRTok::IntToFloat => {
+        *idx += 1;
+        let dest = match next_result(tokens, idx) {
+        IRTok::Var(ident) => lookup_float_variable_id(*serialized_line, function, globals, ident)?,
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%itof float, integer'")),
+        };
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(*serialized_line, String::from("invalid instruction. expected format like '%itof float, integer'"));
+        }
+        let src = match next_result(tokens, idx) {
+        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, globals, ident)?,
+        IRTok::Num(num) => Op::Num(*num),
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%itof float, integer'")),
+        };
+        bytecode = Bytecode::IntToFloat(dest, src);
+    }
+
+    IRTok::FloatToInt => {
+        *idx += 1;
+        let dest = match next_result(tokens, idx) {
+        IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, globals, ident)?,
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%ftoi integer, float'")),
+        };
+        if !matches!(next_result(tokens, idx), IRTok::Comma) {
+            return error(*serialized_line, String::from("invalid instruction. expected format like '%ftoi integer, float'"));
+        }
+        bytecode = Bytecode::FloatToInt(dest, parse_float_operand(*serialized_line, function, globals, tokens, idx)?);
+    }
+
     IRTok::EndFunc => {
         bytecode = Bytecode::End;
         return Ok(bytecode);
@@ -1214,9 +2655,9 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
     return Ok(bytecode);
 }
 
-fn addr_code3(serialized_line: usize, function: &FunctionBytecode, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<(i32, Op, Op), IRError> {
+fn addr_code3(serialized_line: usize, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<(i32, Op, Op), IRError> {
     let dest = match next_result(tokens, idx) {
-    IRTok::Var(ident) => lookup_variable_dest_id(serialized_line, function, ident)?,
+    IRTok::Var(ident) => lookup_variable_dest_id(serialized_line, function, globals, ident)?,
     _ => return error(serialized_line, String::from("invalid dest.")),
     };
 
@@ -1225,7 +2666,7 @@ fn addr_code3(serialized_line: usize, function: &FunctionBytecode, tokens: &Vec<
     }
     
     let src1 = match next_result(tokens, idx) {
-    IRTok::Var(ident) => lookup_integer_variable_id(serialized_line, function, ident)?,
+    IRTok::Var(ident) => lookup_integer_variable_id(serialized_line, function, globals, ident)?,
     IRTok::Num(num) => Op::Num(*num),
     _ => return error(serialized_line, String::from("invalid src1.")),
     };
@@ -1235,7 +2676,7 @@ fn addr_code3(serialized_line: usize, function: &FunctionBytecode, tokens: &Vec<
     }
 
     let src2 = match next_result(tokens, idx) {
-    IRTok::Var(ident) => lookup_integer_variable_id(serialized_line, function, ident)?,
+    IRTok::Var(ident) => lookup_integer_variable_id(serialized_line, function, globals, ident)?,
     IRTok::Num(num) => Op::Num(*num),
     _ => return error(serialized_line, String::from("invalid src2.")),
     };
@@ -1243,6 +2684,25 @@ fn addr_code3(serialized_line: usize, function: &FunctionBytecode, tokens: &Vec<
     return Ok((dest, src1, src2));
 }
 
+fn addr_code2(serialized_line: usize, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<(i32, Op), IRError> {
+    let dest = match next_result(tokens, idx) {
+    IRTok::Var(ident) => lookup_variable_dest_id(serialized_line, function, globals, ident)?,
+    _ => return error(serialized_line, String::from("invalid dest.")),
+    };
+
+    if !matches!(next_result(tokens, idx), IRTok::Comma) {
+        return error(serialized_line, String::from("invalid instruction. expected comma between dest and src."));
+    }
+
+    let src = match next_result(tokens, idx) {
+    IRTok::Var(ident) => lookup_integer_variable_id(serialized_line, function, globals, ident)?,
+    IRTok::Num(num) => Op::Num(*num),
+    _ => return error(serialized_line, String::from("invalid src.")),
+    };
+
+    Ok((dest, src))
+}
+
 fn next_result<'a>(tokens: &'a Vec<IRTok>, index: &mut usize) -> &'a IRTok {
     let ret = *index;
     *index += 1;
@@ -1257,9 +2717,20 @@ fn opcode(s: &str) -> Result<IRTok, String> {
     "%endfunc" => Ok(EndFunc),
     "%int" => Ok(Int),
     "%int[]" => Ok(IntArray),
+    "%long" => Ok(Long),
+    "%string" => Ok(Str),
+    "%global" => Ok(Global),
+    "%global[]" => Ok(GlobalArray),
+    "%global_long" => Ok(GlobalLong),
+    "%global_string" => Ok(GlobalStr),
+    "%data" => Ok(Data),
     "%call" => Ok(Call),
     "%ret" => Ok(Return),
+    "%bounds" => Ok(Bounds),
+    "%len" => Ok(Len),
     "%out" => Ok(Out),
+    "%outc" => Ok(OutChar),
+    "%outs" => Ok(OutStr),
     "%input" => Ok(In),
     "%mov" => Ok(Mov),
     "%add" => Ok(Add),
@@ -1267,6 +2738,12 @@ fn opcode(s: &str) -> Result<IRTok, String> {
     "%mult" => Ok(Mult),
     "%div" => Ok(Div),
     "%mod" => Ok(Mod),
+    "%and" => Ok(And),
+    "%or" => Ok(Or),
+    "%xor" => Ok(Xor),
+    "%not" => Ok(Not),
+    "%shl" => Ok(ShiftLeft),
+    "%shr" => Ok(ShiftRight),
     "%lt" => Ok(LessThan),
     "%le" => Ok(LessEqual),
     "%neq" => Ok(NotEqual),
@@ -1276,94 +2753,2082 @@ fn opcode(s: &str) -> Result<IRTok, String> {
     "%jmp" => Ok(Jump),
     "%branch_if" => Ok(BranchIf),
     "%branch_ifn" => Ok(BranchIfNot),
+    "%switch" => Ok(Switch),
+    "%concat" => Ok(Concat),
+    "%charat" => Ok(CharAt),
+    "%strcmp" => Ok(StrCmp),
+    "%chr" => Ok(Chr),
+    "%float" => Ok(Float),
+    "%float[]" => Ok(FloatArray),
+    "%global_float" => Ok(GlobalFloat),
+    "%global_float[]" => Ok(GlobalFloatArray),
+    "%outf" => Ok(OutFloat),
+    "%fadd" => Ok(FloatAdd),
+    "%fsub" => Ok(FloatSub),
+    "%fmult" => Ok(FloatMult),
+    "%fdiv" => Ok(FloatDiv),
+    "%flt" => Ok(FloatLessThan),
+    "%fle" => Ok(FloatLessEqual),
+    "%fneq" => Ok(FloatNotEqual),
+    "%feq" => Ok(FloatEqual),
+    "%fge" => Ok(FloatGreaterEqual),
+    "%fgt" => Ok(FloatGreaterThan),
+    "%itof" => Ok(IntToFloat),
+    "%ftoi" => Ok(FloatToInt),
     _ => Err(format!("Invalid Instruction '{}'", s)),
     }
 }
 
-#[cfg(test)]
-mod ir_tests {
-    use crate::interpreter::*;
+// turns the resolved bytecode back into IR text. variable names are recovered from
+// the variable table, and since label names are erased to instruction indices,
+// every label is renamed ':label1', ':label2', ... in order of appearance.
+fn disassemble(program: &Program) -> String {
+    let functions = &program.functions;
+    let mut code = String::new();
+
+    // globals are printed in the order they were declared.
+    let mut globals: Vec<(&String, &VariableType)> = program.globals.iter().collect();
+    globals.sort_by_key(|(_, vartype)| {
+        match vartype {
+        VariableType::IntVar(id) => -*id,
+        VariableType::ArrayVar(id, _) => -*id,
+        VariableType::ArrayRef(id) => -*id,
+        VariableType::StringVar(id) => -*id,
+        VariableType::LongVar(id) => -*id,
+        VariableType::FloatVar(id) => -*id,
+        VariableType::FloatArrayVar(id, _) => -*id,
+        VariableType::FloatArrayRef(id) => -*id,
+        }
+    });
+    for (name, vartype) in globals {
+        match vartype {
+        VariableType::IntVar(_) => code += &format!("%global {}\n", name),
+        VariableType::ArrayVar(_, len) => code += &format!("%global[] {}, {}\n", name, len),
+        VariableType::ArrayRef(_) | VariableType::FloatArrayRef(_) => {} // only parameters refer to other arrays.
+        VariableType::StringVar(_) => code += &format!("%global_string {}\n", name),
+        VariableType::LongVar(_) => code += &format!("%global_long {}\n", name),
+        VariableType::FloatVar(_) => code += &format!("%global_float {}\n", name),
+        VariableType::FloatArrayVar(_, len) => code += &format!("%global_float[] {}, {}\n", name, len),
+        }
+    }
 
-   #[test]
-    fn ir_lex() {
-        {
-            let toks = lex_ir("%add a, b, c\n");
-            assert!(toks.len() == 6);
-            assert!(matches!(toks[0], IRTok::Add));
-            assert!(matches!(toks[1], IRTok::Var(_)));
-            assert!(matches!(toks[2], IRTok::Comma));
-            assert!(matches!(toks[3], IRTok::Var(_)));
-            assert!(matches!(toks[4], IRTok::Comma));
-            assert!(matches!(toks[5], IRTok::Var(_)));
+    // string constants are renamed '_str1', '_str2', ... like labels.
+    for (i, value) in program.strings.iter().enumerate() {
+        code += &format!("%data _str{}, \"{}\"\n", i + 1, escape(value));
+    }
+
+    for function in functions {
+        let mut names: HashMap<i32, &str> = HashMap::new();
+        for (name, vartype) in program.globals.iter().chain(function.variables.iter()) {
+            match vartype {
+            VariableType::IntVar(id) => { names.insert(*id, name); }
+            VariableType::ArrayVar(id, _) => { names.insert(*id, name); }
+            VariableType::ArrayRef(id) => { names.insert(*id, name); }
+            VariableType::StringVar(id) => { names.insert(*id, name); }
+            VariableType::LongVar(id) => { names.insert(*id, name); }
+            VariableType::FloatVar(id) => { names.insert(*id, name); }
+            VariableType::FloatArrayVar(id, _) => { names.insert(*id, name); }
+            VariableType::FloatArrayRef(id) => { names.insert(*id, name); }
+            }
         }
 
-        {
-            let toks = lex_ir("%func main,,,\n");
-            assert!(toks.len() == 5);
-            assert!(matches!(toks[0], IRTok::Func));
-            assert!(matches!(toks[1], IRTok::Var(_)));
-            assert!(matches!(toks[2], IRTok::Comma));
-            assert!(matches!(toks[3], IRTok::Comma));
-            assert!(matches!(toks[4], IRTok::Comma));
+        let mut labels: HashMap<usize, String> = HashMap::new();
+        for (i, bytecode) in function.body.iter().enumerate() {
+            if matches!(bytecode, Bytecode::Label) {
+                labels.insert(i, format!(":label{}", labels.len() + 1));
+            }
         }
 
-        {
-            let toks = lex_ir("%func,main,,,\n");
-            assert!(toks.len() == 6);
-            assert!(matches!(toks[0], IRTok::Func));
-            assert!(matches!(toks[1], IRTok::Comma));
-            assert!(matches!(toks[2], IRTok::Var(_)));
-            assert!(matches!(toks[3], IRTok::Comma));
-            assert!(matches!(toks[4], IRTok::Comma));
-            assert!(matches!(toks[5], IRTok::Comma));
+        let mut parameters: Vec<String> = vec![];
+        for id in 0..function.parameters {
+            let id = id as i32;
+            if function.variables.values().any(|v| matches!(v, VariableType::ArrayRef(i) if *i == id)) {
+                parameters.push(format!("%int[] {}", names[&id]));
+            } else if function.variables.values().any(|v| matches!(v, VariableType::StringVar(i) if *i == id)) {
+                parameters.push(format!("%string {}", names[&id]));
+            } else if function.variables.values().any(|v| matches!(v, VariableType::LongVar(i) if *i == id)) {
+                parameters.push(format!("%long {}", names[&id]));
+            } else if function.variables.values().any(|v| matches!(v, VariableType::FloatVar(i) if *i == id)) {
+                parameters.push(format!("%float {}", names[&id]));
+            } else if function.variables.values().any(|v| matches!(v, VariableType::FloatArrayRef(i) if *i == id)) {
+                parameters.push(format!("%float[] {}", names[&id]));
+            } else {
+                parameters.push(format!("%int {}", names[&id]));
+            }
         }
+        code += &format!("%func {}({})\n", function.name, parameters.join(", "));
 
-        {
-            let toks = lex_ir("%mov [arr+0], 100\n");
-            assert!(toks.len() == 8);
-            assert!(matches!(toks[0], IRTok::Mov));
-            assert!(matches!(toks[1], IRTok::LBrace));
-            assert!(matches!(toks[2], IRTok::Var(_)));
-            assert!(matches!(toks[3], IRTok::Plus));
-            assert!(matches!(toks[4], IRTok::Num(0)));
-            assert!(matches!(toks[5], IRTok::RBrace));
-            assert!(matches!(toks[6], IRTok::Comma));
-            assert!(matches!(toks[7], IRTok::Num(100)));
+        for (i, bytecode) in function.body.iter().enumerate() {
+            let op = |op: &Op| -> String {
+                match op {
+                Op::Num(num) => num.to_string(),
+                Op::Var(id) => String::from(names[id]),
+                }
+            };
+
+            // string constants are renamed '_str1', '_str2', ... like labels.
+            let string = |op: &StrOp| -> String {
+                match op {
+                StrOp::Var(id) => String::from(names[id]),
+                StrOp::Data(id) => format!("_str{}", id + 1),
+                }
+            };
+
+            let float = |op: &FloatOp| -> String {
+                match op {
+                FloatOp::Num(num) => format_float(*num),
+                FloatOp::Var(id) => String::from(names[id]),
+                }
+            };
+
+            let read = |read: &MemRead| -> String {
+                match read {
+                MemRead::IntVar(id) => String::from(names[id]),
+                MemRead::Number(num) => num.to_string(),
+                MemRead::ArrayRead(id, index) => format!("[{} + {}]", names[id], op(index)),
+                }
+            };
+
+            let line = match bytecode {
+            Bytecode::End => String::from("%endfunc"),
+            Bytecode::Label => labels[&i].clone(),
+            Bytecode::Int(id) => format!("%int {}", names[id]),
+            Bytecode::Long(id) => format!("%long {}", names[id]),
+            Bytecode::IntArray(id, len) => format!("%int[] {}, {}", names[id], len),
+            Bytecode::FloatArray(id, len) => format!("%float[] {}, {}", names[id], len),
+            Bytecode::Out(src) => format!("%out {}", op(src)),
+            Bytecode::OutChar(src) => format!("%outc {}", op(src)),
+            Bytecode::OutStr(src) => format!("%outs {}", string(src)),
+            Bytecode::In(dest) => format!("%input {}", names[dest]),
+            Bytecode::InStr(dest) => format!("%input {}", names[dest]),
+            Bytecode::Mov(MemWrite::IntVar(dest), src) => format!("%mov {}, {}", names[dest], read(src)),
+            Bytecode::Mov(MemWrite::ArrayWrite(dest, index), src) => format!("%mov [{} + {}], {}", names[dest], op(index), read(src)),
+            Bytecode::Add(dest, src1, src2) => format!("%add {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::Sub(dest, src1, src2) => format!("%sub {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::Mult(dest, src1, src2) => format!("%mult {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::Div(dest, src1, src2) => format!("%div {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::Mod(dest, src1, src2) => format!("%mod {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::And(dest, src1, src2) => format!("%and {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::Or(dest, src1, src2) => format!("%or {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::Xor(dest, src1, src2) => format!("%xor {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::Not(dest, src) => format!("%not {}, {}", names[dest], op(src)),
+            Bytecode::Shl(dest, src1, src2) => format!("%shl {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::Shr(dest, src1, src2) => format!("%shr {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::LessThan(dest, src1, src2) => format!("%lt {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::LessEqual(dest, src1, src2) => format!("%le {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::NotEqual(dest, src1, src2) => format!("%neq {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::Equal(dest, src1, src2) => format!("%eq {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::GreaterEqual(dest, src1, src2) => format!("%ge {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::GreaterThan(dest, src1, src2) => format!("%gt {}, {}, {}", names[dest], op(src1), op(src2)),
+            Bytecode::Call(dests, func, params) => {
+                let dests: String = dests.iter().map(|dest| format!("{}, ", names[dest])).collect();
+                let params: Vec<String> = params.iter().map(|p| op(p)).collect();
+                format!("%call {dests}{}({})", functions[*func].name, params.join(", "))
+            }
+            Bytecode::Builtin(dests, builtin, params) => {
+                let dests: String = dests.iter().map(|dest| format!("{}, ", names[dest])).collect();
+                let params: Vec<String> = params.iter().map(|p| op(p)).collect();
+                format!("%call {dests}{}({})", BUILTINS[builtin_index(*builtin)].0, params.join(", "))
+            }
+            Bytecode::Return(srcs) if srcs.is_empty() => String::from("%ret"),
+            Bytecode::Return(srcs) => {
+                let srcs: Vec<String> = srcs.iter().map(|src| op(src)).collect();
+                format!("%ret {}", srcs.join(", "))
+            }
+            Bytecode::Bounds(index, length) => format!("%bounds {}, {}", op(index), op(length)),
+            Bytecode::Len(dest, array) => format!("%len {}, {}", names[dest], names[array]),
+            Bytecode::Jmp(jump) => format!("%jmp {}", labels[jump]),
+            Bytecode::BranchIf(src, jump) => format!("%branch_if {}, {}", op(src), labels[jump]),
+            Bytecode::BranchIfn(src, jump) => format!("%branch_ifn {}, {}", op(src), labels[jump]),
+            Bytecode::Switch(src, default, table) => {
+                let table: Vec<String> = table.iter().map(|jump| labels[jump].clone()).collect();
+                format!("%switch {}, {}, {}", op(src), labels[default], table.join(", "))
+            }
+            Bytecode::Str(id) => format!("%string {}", names[id]),
+            Bytecode::StrMov(dest, src) => format!("%mov {}, {}", names[dest], string(src)),
+            Bytecode::Concat(dest, src1, src2) => format!("%concat {}, {}, {}", names[dest], string(src1), string(src2)),
+            Bytecode::StrLen(dest, src) => format!("%len {}, {}", names[dest], string(src)),
+            Bytecode::CharAt(dest, src, index) => format!("%charat {}, {}, {}", names[dest], string(src), op(index)),
+            Bytecode::StrCmp(dest, src1, src2) => format!("%strcmp {}, {}, {}", names[dest], string(src1), string(src2)),
+            Bytecode::Chr(dest, src) => format!("%chr {}, {}", names[dest], op(src)),
+            Bytecode::Float(id) => format!("%float {}", names[id]),
+            Bytecode::FloatMov(dest, src) => format!("%mov {}, {}", names[dest], float(src)),
+            Bytecode::FloatArrayRead(dest, array, index) => format!("%mov {}, [{} + {}]", names[dest], names[array], op(index)),
+            Bytecode::FloatArrayWrite(array, index, src) => format!("%mov [{} + {}], {}", names[array], op(index), float(src)),
+            Bytecode::OutFloat(src) => format!("%outf {}", float(src)),
+            Bytecode::InFloat(dest) => format!("%input {}", names[dest]),
+            Bytecode::FloatAdd(dest, src1, src2) => format!("%fadd {}, {}, {}", names[dest], float(src1), float(src2)),
+            Bytecode::FloatSub(dest, src1, src2) => format!("%fsub {}, {}, {}", names[dest], float(src1), float(src2)),
+            Bytecode::FloatMult(dest, src1, src2) => format!("%fmult {}, {}, {}", names[dest], float(src1), float(src2)),
+            Bytecode::FloatDiv(dest, src1, src2) => format!("%fdiv {}, {}, {}", names[dest], float(src1), float(src2)),
+            Bytecode::FloatLessThan(dest, src1, src2) => format!("%flt {}, {}, {}", names[dest], float(src1), float(src2)),
+            Bytecode::FloatLessEqual(dest, src1, src2) => format!("%fle {}, {}, {}", names[dest], float(src1), float(src2)),
+            Bytecode::FloatNotEqual(dest, src1, src2) => format!("%fneq {}, {}, {}", names[dest], float(src1), float(src2)),
+            Bytecode::FloatEqual(dest, src1, src2) => format!("%feq {}, {}, {}", names[dest], float(src1), float(src2)),
+            Bytecode::FloatGreaterEqual(dest, src1, src2) => format!("%fge {}, {}, {}", names[dest], float(src1), float(src2)),
+            Bytecode::FloatGreaterThan(dest, src1, src2) => format!("%fgt {}, {}, {}", names[dest], float(src1), float(src2)),
+            Bytecode::IntToFloat(dest, src) => format!("%itof {}, {}", names[dest], op(src)),
+            Bytecode::FloatToInt(dest, src) => format!("%ftoi {}, {}", names[dest], float(src)),
+            };
+
+            code += &line;
+            code += "\n";
+        }
+    }
+    return code;
+
+    // the inverse of how the lexer reads string constants.
+    fn escape(value: &str) -> String {
+        let mut escaped = String::new();
+        for c in value.chars() {
+            match c {
+            '\n' => escaped += "\\n",
+            '\t' => escaped += "\\t",
+            '\r' => escaped += "\\r",
+            '\0' => escaped += "\\0",
+            '\\' => escaped += "\\\\",
+            '"' => escaped += "\\\"",
+            _ => escaped.push(c),
+            }
         }
+        escaped
     }
 }
 
+// binary bytecode files (.ttb). the layout is:
+//
+//   header:             magic "TTBC", version (u16), payload length (u32), checksum (u32)
+//   global variables:   the variable table of the global variables
+//   string constants:   string count (u32), then every string constant
+//   function table:     function count (u32), then for every function its name,
+//                       parameter count, variable table and instruction count
+//   constant operands:  constant count (u32), then every constant as an i64.
+//                       float constants are stored as the bits of the f64
+//   instruction stream: the instructions of every function, in function table order
+//
+// all integers are little endian, and strings are a u32 length followed by utf-8 bytes.
+// the checksum is the 32 bit FNV-1a hash of the payload, i.e. everything after the header.
+const BYTECODE_MAGIC: &[u8; 4] = b"TTBC";
+const BYTECODE_VERSION: u16 = 9;
+const BYTECODE_HEADER_LEN: usize = 14;
+
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    for byte in bytes {
+        hash ^= *byte as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    hash
+}
 
-enum IRTok {
-    // func
-    Func,
-    EndFunc,
+fn write_bytecode(program: &Program) -> Vec<u8> {
+    let functions = &program.functions;
+    let mut constants: Vec<i64> = vec![];
+    let mut constant_ids: HashMap<i64, u32> = HashMap::new();
+    let mut instructions: Vec<u8> = vec![];
+    for function in functions {
+        for bytecode in &function.body {
+            write_instruction(&mut instructions, &mut constants, &mut constant_ids, bytecode);
+        }
+    }
 
-    // declarations.
-    Int,
-    IntArray,
+    let mut payload: Vec<u8> = vec![];
+    write_variables(&mut payload, &program.globals);
+    write_u32(&mut payload, program.strings.len() as u32);
+    for value in &program.strings {
+        write_string(&mut payload, value);
+    }
+    write_u32(&mut payload, functions.len() as u32);
+    for function in functions {
+        write_string(&mut payload, &function.name);
+        write_u32(&mut payload, function.parameters as u32);
+        write_variables(&mut payload, &function.variables);
+        write_u32(&mut payload, function.body.len() as u32);
+    }
 
-    // function calling routines.
-    Call,
-    Return,
+    write_u32(&mut payload, constants.len() as u32);
+    for constant in &constants {
+        write_i64(&mut payload, *constant);
+    }
+    payload.extend(instructions);
+
+    let mut file: Vec<u8> = vec![];
+    file.extend(BYTECODE_MAGIC);
+    file.extend(BYTECODE_VERSION.to_le_bytes());
+    write_u32(&mut file, payload.len() as u32);
+    write_u32(&mut file, checksum(&payload));
+    file.extend(payload);
+    return file;
+
+    fn write_u32(bytes: &mut Vec<u8>, value: u32) {
+        bytes.extend(value.to_le_bytes());
+    }
 
-    // input/output routines.
-    Out,
-    In,
+    fn write_i32(bytes: &mut Vec<u8>, value: i32) {
+        bytes.extend(value.to_le_bytes());
+    }
 
-    // mathematical operators.
-    Mov,
-    Add,
-    Sub,
-    Mult,
-    Div,
-    Mod,
+    fn write_i64(bytes: &mut Vec<u8>, value: i64) {
+        bytes.extend(value.to_le_bytes());
+    }
 
-    // comparison operators.
-    LessThan,
-    LessEqual,
-    NotEqual,
-    Equal,
+    fn write_variables(bytes: &mut Vec<u8>, variables: &HashMap<String, VariableType>) {
+        // sort the variable table by id so the same program always gives the same file.
+        let mut variables: Vec<(&String, &VariableType)> = variables.iter().collect();
+        variables.sort_by_key(|(_, vartype)| {
+            match vartype {
+            VariableType::IntVar(id) => *id,
+            VariableType::ArrayVar(id, _) => *id,
+            VariableType::ArrayRef(id) => *id,
+            VariableType::StringVar(id) => *id,
+            VariableType::LongVar(id) => *id,
+            VariableType::FloatVar(id) => *id,
+            VariableType::FloatArrayVar(id, _) => *id,
+            VariableType::FloatArrayRef(id) => *id,
+            }
+        });
+        write_u32(bytes, variables.len() as u32);
+        for (name, vartype) in variables {
+            write_string(bytes, name);
+            match vartype {
+            VariableType::IntVar(id) => {
+                bytes.push(0);
+                write_i32(bytes, *id);
+            }
+            VariableType::ArrayVar(id, len) => {
+                bytes.push(1);
+                write_i32(bytes, *id);
+                write_i32(bytes, *len);
+            }
+            VariableType::ArrayRef(id) => {
+                bytes.push(2);
+                write_i32(bytes, *id);
+            }
+            VariableType::StringVar(id) => {
+                bytes.push(3);
+                write_i32(bytes, *id);
+            }
+            VariableType::LongVar(id) => {
+                bytes.push(4);
+                write_i32(bytes, *id);
+            }
+            VariableType::FloatVar(id) => {
+                bytes.push(5);
+                write_i32(bytes, *id);
+            }
+            VariableType::FloatArrayVar(id, len) => {
+                bytes.push(6);
+                write_i32(bytes, *id);
+                write_i32(bytes, *len);
+            }
+            VariableType::FloatArrayRef(id) => {
+                bytes.push(7);
+                write_i32(bytes, *id);
+            }
+            }
+        }
+    }
+
+    fn write_string(bytes: &mut Vec<u8>, value: &str) {
+        write_u32(bytes, value.len() as u32);
+        bytes.extend(value.as_bytes());
+    }
+
+    fn write_constant(bytes: &mut Vec<u8>, constants: &mut Vec<i64>, constant_ids: &mut HashMap<i64, u32>, value: i64) {
+        let id = match constant_ids.get(&value) {
+        Some(id) => *id,
+        None => {
+            let id = constants.len() as u32;
+            constants.push(value);
+            constant_ids.insert(value, id);
+            id
+        }
+        };
+        write_u32(bytes, id);
+    }
+
+    fn write_op(bytes: &mut Vec<u8>, constants: &mut Vec<i64>, constant_ids: &mut HashMap<i64, u32>, op: &Op) {
+        match op {
+        Op::Var(id) => {
+            bytes.push(0);
+            write_i32(bytes, *id);
+        }
+        Op::Num(num) => {
+            bytes.push(1);
+            write_constant(bytes, constants, constant_ids, *num);
+        }
+        }
+    }
+
+    fn write_float_op(bytes: &mut Vec<u8>, constants: &mut Vec<i64>, constant_ids: &mut HashMap<i64, u32>, op: &FloatOp) {
+        match op {
+        FloatOp::Var(id) => {
+            bytes.push(0);
+            write_i32(bytes, *id);
+        }
+        FloatOp::Num(num) => {
+            bytes.push(1);
+            write_constant(bytes, constants, constant_ids, num.to_bits() as i64);
+        }
+        }
+    }
+
+    fn write_string_op(bytes: &mut Vec<u8>, op: &StrOp) {
+        match op {
+        StrOp::Var(id) => {
+            bytes.push(0);
+            write_i32(bytes, *id);
+        }
+        StrOp::Data(id) => {
+            bytes.push(1);
+            write_u32(bytes, *id as u32);
+        }
+        }
+    }
+
+    fn write_instruction(bytes: &mut Vec<u8>, constants: &mut Vec<i64>, constant_ids: &mut HashMap<i64, u32>, bytecode: &Bytecode) {
+        let mut op3 = |opcode: u8, dest: &i32, src1: &Op, src2: &Op| {
+            bytes.push(opcode);
+            write_i32(bytes, *dest);
+            write_op(bytes, constants, constant_ids, src1);
+            write_op(bytes, constants, constant_ids, src2);
+        };
+
+        match bytecode {
+        Bytecode::Add(dest, src1, src2) => return op3(10, dest, src1, src2),
+        Bytecode::Sub(dest, src1, src2) => return op3(11, dest, src1, src2),
+        Bytecode::Mult(dest, src1, src2) => return op3(12, dest, src1, src2),
+        Bytecode::Div(dest, src1, src2) => return op3(13, dest, src1, src2),
+        Bytecode::Mod(dest, src1, src2) => return op3(14, dest, src1, src2),
+        Bytecode::And(dest, src1, src2) => return op3(15, dest, src1, src2),
+        Bytecode::Or(dest, src1, src2) => return op3(16, dest, src1, src2),
+        Bytecode::Xor(dest, src1, src2) => return op3(17, dest, src1, src2),
+        Bytecode::Shl(dest, src1, src2) => return op3(18, dest, src1, src2),
+        Bytecode::Shr(dest, src1, src2) => return op3(19, dest, src1, src2),
+        Bytecode::LessThan(dest, src1, src2) => return op3(20, dest, src1, src2),
+        Bytecode::LessEqual(dest, src1, src2) => return op3(21, dest, src1, src2),
+        Bytecode::NotEqual(dest, src1, src2) => return op3(22, dest, src1, src2),
+        Bytecode::Equal(dest, src1, src2) => return op3(23, dest, src1, src2),
+        Bytecode::GreaterEqual(dest, src1, src2) => return op3(24, dest, src1, src2),
+        Bytecode::GreaterThan(dest, src1, src2) => return op3(25, dest, src1, src2),
+        _ => {}
+        }
+
+        let mut float3 = |opcode: u8, dest: &i32, src1: &FloatOp, src2: &FloatOp| {
+            bytes.push(opcode);
+            write_i32(bytes, *dest);
+            write_float_op(bytes, constants, constant_ids, src1);
+            write_float_op(bytes, constants, constant_ids, src2);
+        };
+
+        match bytecode {
+        Bytecode::FloatAdd(dest, src1, src2) => return float3(51, dest, src1, src2),
+        Bytecode::FloatSub(dest, src1, src2) => return float3(52, dest, src1, src2),
+        Bytecode::FloatMult(dest, src1, src2) => return float3(53, dest, src1, src2),
+        Bytecode::FloatDiv(dest, src1, src2) => return float3(54, dest, src1, src2),
+        Bytecode::FloatLessThan(dest, src1, src2) => return float3(55, dest, src1, src2),
+        Bytecode::FloatLessEqual(dest, src1, src2) => return float3(56, dest, src1, src2),
+        Bytecode::FloatNotEqual(dest, src1, src2) => return float3(57, dest, src1, src2),
+        Bytecode::FloatEqual(dest, src1, src2) => return float3(58, dest, src1, src2),
+        Bytecode::FloatGreaterEqual(dest, src1, src2) => return float3(59, dest, src1, src2),
+        Bytecode::FloatGreaterThan(dest, src1, src2) => return float3(60, dest, src1, src2),
+        _ => {}
+        }
+
+        match bytecode {
+        Bytecode::End => bytes.push(0),
+        Bytecode::Label => bytes.push(1),
+        Bytecode::Int(id) => {
+            bytes.push(2);
+            write_i32(bytes, *id);
+        }
+        Bytecode::Long(id) => {
+            bytes.push(46);
+            write_i32(bytes, *id);
+        }
+        Bytecode::IntArray(id, len) => {
+            bytes.push(3);
+            write_i32(bytes, *id);
+            write_i32(bytes, *len);
+        }
+        Bytecode::FloatArray(id, len) => {
+            bytes.push(64);
+            write_i32(bytes, *id);
+            write_i32(bytes, *len);
+        }
+        Bytecode::FloatArrayRead(dest, array, index) => {
+            bytes.push(65);
+            write_i32(bytes, *dest);
+            write_i32(bytes, *array);
+            write_op(bytes, constants, constant_ids, index);
+        }
+        Bytecode::FloatArrayWrite(array, index, src) => {
+            bytes.push(66);
+            write_i32(bytes, *array);
+            write_op(bytes, constants, constant_ids, index);
+            write_float_op(bytes, constants, constant_ids, src);
+        }
+        Bytecode::Out(src) => {
+            bytes.push(4);
+            write_op(bytes, constants, constant_ids, src);
+        }
+        Bytecode::OutChar(src) => {
+            bytes.push(36);
+            write_op(bytes, constants, constant_ids, src);
+        }
+        Bytecode::OutStr(src) => {
+            bytes.push(37);
+            write_string_op(bytes, src);
+        }
+        Bytecode::InStr(dest) => {
+            bytes.push(38);
+            write_i32(bytes, *dest);
+        }
+        Bytecode::Str(id) => {
+            bytes.push(39);
+            write_i32(bytes, *id);
+        }
+        Bytecode::StrMov(dest, src) => {
+            bytes.push(40);
+            write_i32(bytes, *dest);
+            write_string_op(bytes, src);
+        }
+        Bytecode::Concat(dest, src1, src2) => {
+            bytes.push(41);
+            write_i32(bytes, *dest);
+            write_string_op(bytes, src1);
+            write_string_op(bytes, src2);
+        }
+        Bytecode::StrLen(dest, src) => {
+            bytes.push(42);
+            write_i32(bytes, *dest);
+            write_string_op(bytes, src);
+        }
+        Bytecode::CharAt(dest, src, index) => {
+            bytes.push(43);
+            write_i32(bytes, *dest);
+            write_string_op(bytes, src);
+            write_op(bytes, constants, constant_ids, index);
+        }
+        Bytecode::StrCmp(dest, src1, src2) => {
+            bytes.push(44);
+            write_i32(bytes, *dest);
+            write_string_op(bytes, src1);
+            write_string_op(bytes, src2);
+        }
+        Bytecode::Chr(dest, src) => {
+            bytes.push(45);
+            write_i32(bytes, *dest);
+            write_op(bytes, constants, constant_ids, src);
+        }
+        Bytecode::In(dest) => {
+            bytes.push(5);
+            write_i32(bytes, *dest);
+        }
+        Bytecode::Float(id) => {
+            bytes.push(47);
+            write_i32(bytes, *id);
+        }
+        Bytecode::FloatMov(dest, src) => {
+            bytes.push(48);
+            write_i32(bytes, *dest);
+            write_float_op(bytes, constants, constant_ids, src);
+        }
+        Bytecode::OutFloat(src) => {
+            bytes.push(49);
+            write_float_op(bytes, constants, constant_ids, src);
+        }
+        Bytecode::InFloat(dest) => {
+            bytes.push(50);
+            write_i32(bytes, *dest);
+        }
+        Bytecode::IntToFloat(dest, src) => {
+            bytes.push(61);
+            write_i32(bytes, *dest);
+            write_op(bytes, constants, constant_ids, src);
+        }
+        Bytecode::FloatToInt(dest, src) => {
+            bytes.push(62);
+            write_i32(bytes, *dest);
+            write_float_op(bytes, constants, constant_ids, src);
+        }
+        Bytecode::Mov(dest, src) => {
+            bytes.push(6);
+            match dest {
+            MemWrite::IntVar(id) => {
+                bytes.push(0);
+                write_i32(bytes, *id);
+            }
+            MemWrite::ArrayWrite(id, index) => {
+                bytes.push(1);
+                write_i32(bytes, *id);
+                write_op(bytes, constants, constant_ids, index);
+            }
+            }
+            match src {
+            MemRead::IntVar(id) => {
+                bytes.push(0);
+                write_i32(bytes, *id);
+            }
+            MemRead::Number(num) => {
+                bytes.push(1);
+                write_constant(bytes, constants, constant_ids, *num);
+            }
+            MemRead::ArrayRead(id, index) => {
+                bytes.push(2);
+                write_i32(bytes, *id);
+                write_op(bytes, constants, constant_ids, index);
+            }
+            }
+        }
+        Bytecode::Call(dests, func, params) => {
+            bytes.push(7);
+            write_u32(bytes, dests.len() as u32);
+            for dest in dests {
+                write_i32(bytes, *dest);
+            }
+            write_u32(bytes, *func as u32);
+            write_u32(bytes, params.len() as u32);
+            for param in params {
+                write_op(bytes, constants, constant_ids, param);
+            }
+        }
+        Bytecode::Builtin(dests, builtin, params) => {
+            bytes.push(63);
+            write_u32(bytes, dests.len() as u32);
+            for dest in dests {
+                write_i32(bytes, *dest);
+            }
+            bytes.push(builtin_index(*builtin) as u8);
+            for param in params {
+                write_op(bytes, constants, constant_ids, param);
+            }
+        }
+        Bytecode::Return(srcs) => {
+            bytes.push(8);
+            write_u32(bytes, srcs.len() as u32);
+            for src in srcs {
+                write_op(bytes, constants, constant_ids, src);
+            }
+        }
+        Bytecode::Not(dest, src) => {
+            bytes.push(9);
+            write_i32(bytes, *dest);
+            write_op(bytes, constants, constant_ids, src);
+        }
+        Bytecode::Bounds(index, length) => {
+            bytes.push(33);
+            write_op(bytes, constants, constant_ids, index);
+            write_op(bytes, constants, constant_ids, length);
+        }
+        Bytecode::Len(dest, array) => {
+            bytes.push(34);
+            write_i32(bytes, *dest);
+            write_i32(bytes, *array);
+        }
+        Bytecode::Jmp(jump) => {
+            bytes.push(30);
+            write_u32(bytes, *jump as u32);
+        }
+        Bytecode::BranchIf(src, jump) => {
+            bytes.push(31);
+            write_op(bytes, constants, constant_ids, src);
+            write_u32(bytes, *jump as u32);
+        }
+        Bytecode::BranchIfn(src, jump) => {
+            bytes.push(32);
+            write_op(bytes, constants, constant_ids, src);
+            write_u32(bytes, *jump as u32);
+        }
+        Bytecode::Switch(src, default, table) => {
+            bytes.push(35);
+            write_op(bytes, constants, constant_ids, src);
+            write_u32(bytes, *default as u32);
+            write_u32(bytes, table.len() as u32);
+            for jump in table {
+                write_u32(bytes, *jump as u32);
+            }
+        }
+        _ => unreachable!(),
+        }
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn read(&mut self, len: usize, what: &str) -> Result<&'a [u8], String> {
+        if self.bytes.len() - self.position < len {
+            return Err(format!("File is truncated. Unexpected end of file while reading {what}."));
+        }
+        let slice = &self.bytes[self.position..self.position + len];
+        self.position += len;
+        Ok(slice)
+    }
+
+    fn read_u8(&mut self, what: &str) -> Result<u8, String> {
+        Ok(self.read(1, what)?[0])
+    }
+
+    fn read_u32(&mut self, what: &str) -> Result<u32, String> {
+        let bytes = self.read(4, what)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_i32(&mut self, what: &str) -> Result<i32, String> {
+        let bytes = self.read(4, what)?;
+        Ok(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_i64(&mut self, what: &str) -> Result<i64, String> {
+        let bytes = self.read(8, what)?;
+        Ok(i64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_string(&mut self, what: &str) -> Result<String, String> {
+        let len = self.read_u32(what)? as usize;
+        match std::str::from_utf8(self.read(len, what)?) {
+        Ok(s) => Ok(String::from(s)),
+        Err(_) => Err(format!("Corrupted file. {what} is not valid utf-8.")),
+        }
+    }
+}
+
+fn read_bytecode(bytes: &[u8]) -> Result<Program, String> {
+    if bytes.len() < BYTECODE_HEADER_LEN {
+        return Err(String::from("File is truncated. The file is too short to contain a bytecode header."));
+    }
+    if &bytes[0..4] != BYTECODE_MAGIC {
+        return Err(String::from("Not a Teh Tarik bytecode file. The file does not begin with 'TTBC'."));
+    }
+
+    let mut header = ByteReader { bytes, position: 4 };
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    header.position += 2;
+    if version != BYTECODE_VERSION {
+        return Err(format!("Unsupported bytecode version {version}. This interpreter only runs version {BYTECODE_VERSION}."));
+    }
+
+    let payload_len = header.read_u32("the header")? as usize;
+    let expected_checksum = header.read_u32("the header")?;
+    let payload = &bytes[BYTECODE_HEADER_LEN..];
+    if payload.len() < payload_len {
+        return Err(format!("File is truncated. Expected {} bytes of bytecode, found {}.", payload_len, payload.len()));
+    }
+    if payload.len() > payload_len {
+        return Err(format!("Corrupted file. Found {} unexpected bytes after the end of the bytecode.", payload.len() - payload_len));
+    }
+    if checksum(payload) != expected_checksum {
+        return Err(String::from("Corrupted file. The checksum does not match the contents of the file."));
+    }
+
+    let mut reader = ByteReader { bytes: payload, position: 0 };
+    let globals = read_variables(&mut reader, "the global variables", true)?;
+    let string_count = reader.read_u32("the string table")?;
+    let mut strings: Vec<String> = vec![];
+    for _ in 0..string_count {
+        strings.push(reader.read_string("a string constant")?);
+    }
+    let function_count = reader.read_u32("the function table")? as usize;
+    let mut functions: Vec<FunctionBytecode> = vec![];
+    let mut body_lengths: Vec<usize> = vec![];
+    let mut parameter_kinds: Vec<Vec<u8>> = vec![];
+    for _ in 0..function_count {
+        let name = reader.read_string("a function name")?;
+        let parameters = reader.read_u32("the function table")? as usize;
+        let variables = read_variables(&mut reader, &format!("function '{name}'"), false)?;
+        let function = FunctionBytecode {
+            name,
+            parameters,
+            id: variables.len() as i32,
+            variables,
+            body: vec![],
+        };
+
+        // parameters are integers, arrays passed by reference, strings or floats, and only parameters refer to other arrays.
+        // the kind of every parameter is its kind in the variable table.
+        let mut kinds: Vec<u8> = vec![];
+        for param in 0..function.parameters {
+            let param = param as i32;
+            if function.variables.values().any(|v| matches!(v, VariableType::IntVar(id) | VariableType::LongVar(id) if *id == param)) {
+                kinds.push(0);
+            } else if function.variables.values().any(|v| matches!(v, VariableType::ArrayRef(id) if *id == param)) {
+                kinds.push(2);
+            } else if function.variables.values().any(|v| matches!(v, VariableType::StringVar(id) if *id == param)) {
+                kinds.push(3);
+            } else if function.variables.values().any(|v| matches!(v, VariableType::FloatVar(id) if *id == param)) {
+                kinds.push(5);
+            } else if function.variables.values().any(|v| matches!(v, VariableType::FloatArrayRef(id) if *id == param)) {
+                kinds.push(7);
+            } else {
+                return Err(format!("Corrupted file. Function '{}' declares {} parameters, but parameter {param} is not an integer, an array, a string or a float.", function.name, function.parameters));
+            }
+        }
+        if function.variables.values().any(|v| matches!(v, VariableType::ArrayRef(id) | VariableType::FloatArrayRef(id) if *id as usize >= function.parameters)) {
+            return Err(format!("Corrupted file. Function '{}' has an array reference that is not a parameter.", function.name));
+        }
+        parameter_kinds.push(kinds);
+
+        if functions.iter().any(|f: &FunctionBytecode| f.name == function.name) {
+            return Err(format!("Corrupted file. Two functions with the same name {}.", function.name));
+        }
+        body_lengths.push(reader.read_u32("the function table")? as usize);
+        functions.push(function);
+    }
+
+    if !functions.iter().any(|f| f.name == "main") {
+        return Err(String::from("Bytecode does not have a main."));
+    }
+
+    let constant_count = reader.read_u32("the constant table")?;
+    let mut constants: Vec<i64> = vec![];
+    for _ in 0..constant_count {
+        constants.push(reader.read_i64("the constant table")?);
+    }
+
+    for (function, body_len) in functions.iter_mut().zip(body_lengths) {
+        for _ in 0..body_len {
+            let bytecode = read_instruction(&mut reader, function, &globals, &strings, &constants, &parameter_kinds)?;
+            function.body.push(bytecode);
+        }
+
+        // every function ends in exactly one %endfunc, and every jump lands on a label.
+        let name = &function.name;
+        if function.body.is_empty() {
+            return Err(format!("Corrupted file. Function '{name}' must end with exactly one %endfunc."));
+        }
+        let last = function.body.len() - 1;
+        for (i, bytecode) in function.body.iter().enumerate() {
+            if matches!(bytecode, Bytecode::End) != (i == last) {
+                return Err(format!("Corrupted file. Function '{name}' must end with exactly one %endfunc."));
+            }
+            let jumps = match bytecode {
+            Bytecode::Jmp(jump) | Bytecode::BranchIf(_, jump) | Bytecode::BranchIfn(_, jump) => vec![*jump],
+            Bytecode::Switch(_, default, table) => std::iter::once(default).chain(table.iter()).copied().collect(),
+            _ => continue,
+            };
+            for jump in jumps {
+                if jump >= function.body.len() || !matches!(function.body[jump], Bytecode::Label) {
+                    return Err(format!("Corrupted file. Instruction {i} in function '{name}' jumps to {jump}, which is not a label."));
+                }
+            }
+        }
+    }
+
+    // every call stores the values the function returns in variables of the same kinds.
+    let mut returns = vec![];
+    for function in &functions {
+        returns.push(return_kinds(function, &globals).map_err(|e| format!("Corrupted file. {e}"))?);
+    }
+    for function in &functions {
+        for bytecode in &function.body {
+            if let Bytecode::Call(dests, func, _) = bytecode {
+                let name = &functions[*func].name;
+                check_call_dests(function, &globals, dests, name, &returns[*func]).map_err(|e| format!("Corrupted file. {e}"))?;
+            }
+        }
+    }
+
+    if reader.position != payload.len() {
+        return Err(format!("Corrupted file. Found {} unexpected bytes after the instruction stream.", payload.len() - reader.position));
+    }
+
+    return Ok(Program {
+        globals,
+        strings,
+        functions,
+    });
+
+    // local variables have the ids 0, 1, 2, ... and global variables have the ids -1, -2, ...
+    fn read_variables(reader: &mut ByteReader, owner: &str, global: bool) -> Result<HashMap<String, VariableType>, String> {
+        let mut variables: HashMap<String, VariableType> = HashMap::new();
+        let variable_count = reader.read_u32("a variable table")?;
+        let mut ids: Vec<i32> = vec![];
+        for _ in 0..variable_count {
+            let name = reader.read_string("a variable name")?;
+            let vartype = match reader.read_u8("a variable table")? {
+            0 => VariableType::IntVar(reader.read_i32("a variable table")?),
+            1 => {
+                let id = reader.read_i32("a variable table")?;
+                let len = reader.read_i32("a variable table")?;
+                if len <= 0 {
+                    return Err(format!("Corrupted file. Array '{name}' in {owner} has length {len}."));
+                }
+                VariableType::ArrayVar(id, len)
+            }
+            2 if !global => VariableType::ArrayRef(reader.read_i32("a variable table")?),
+            3 => VariableType::StringVar(reader.read_i32("a variable table")?),
+            4 => VariableType::LongVar(reader.read_i32("a variable table")?),
+            5 => VariableType::FloatVar(reader.read_i32("a variable table")?),
+            6 => {
+                let id = reader.read_i32("a variable table")?;
+                let len = reader.read_i32("a variable table")?;
+                if len <= 0 {
+                    return Err(format!("Corrupted file. Array '{name}' in {owner} has length {len}."));
+                }
+                VariableType::FloatArrayVar(id, len)
+            }
+            7 if !global => VariableType::FloatArrayRef(reader.read_i32("a variable table")?),
+            kind => return Err(format!("Corrupted file. Invalid variable kind {kind} for '{name}'.")),
+            };
+
+            let id = match vartype {
+            VariableType::IntVar(id) => id,
+            VariableType::ArrayVar(id, _) => id,
+            VariableType::ArrayRef(id) => id,
+            VariableType::StringVar(id) => id,
+            VariableType::LongVar(id) => id,
+            VariableType::FloatVar(id) => id,
+            VariableType::FloatArrayVar(id, _) => id,
+            VariableType::FloatArrayRef(id) => id,
+            };
+            let index = if global { -(id as i64) - 1 } else { id as i64 };
+            if index < 0 || index >= variable_count as i64 || ids.contains(&id) {
+                return Err(format!("Corrupted file. Invalid variable id {id} for '{name}' in {owner}."));
+            }
+            ids.push(id);
+            if variables.insert(name.clone(), vartype).is_some() {
+                return Err(format!("Corrupted file. Variable '{name}' is declared twice in {owner}."));
+            }
+        }
+        Ok(variables)
+    }
+
+    fn read_instruction(reader: &mut ByteReader, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, strings: &[String], constants: &[i64], parameter_kinds: &[Vec<u8>]) -> Result<Bytecode, String> {
+        let bytecode = match reader.read_u8("an instruction")? {
+        0 => Bytecode::End,
+        1 => Bytecode::Label,
+        opcode @ (2 | 46) => {
+            let id = read_int_var(reader, function, globals)?;
+            if id < 0 {
+                return Err(format!("Corrupted file. Function '{}' declares the global variable {id}.", function.name));
+            }
+            let is_long = function.variables.values().any(|v| matches!(v, VariableType::LongVar(i) if *i == id));
+            if is_long != (opcode == 46) {
+                return Err(format!("Corrupted file. Declaration in function '{}' does not match the variable table.", function.name));
+            }
+            if opcode == 46 { Bytecode::Long(id) } else { Bytecode::Int(id) }
+        }
+        3 => {
+            let id = read_array_var(reader, function, globals)?;
+            let len = reader.read_i32("an instruction")?;
+            if !function.variables.values().any(|v| matches!(v, VariableType::ArrayVar(i, l) if *i == id && *l == len)) {
+                return Err(format!("Corrupted file. Array declaration in function '{}' does not match the variable table.", function.name));
+            }
+            Bytecode::IntArray(id, len)
+        }
+        4 => Bytecode::Out(read_op(reader, function, globals, constants)?),
+        5 => Bytecode::In(read_int_var(reader, function, globals)?),
+        6 => {
+            let dest = match reader.read_u8("an instruction")? {
+            0 => MemWrite::IntVar(read_int_var(reader, function, globals)?),
+            1 => {
+                let id = read_array_var(reader, function, globals)?;
+                MemWrite::ArrayWrite(id, read_op(reader, function, globals, constants)?)
+            }
+            kind => return Err(format!("Corrupted file. Invalid %mov destination kind {kind}.")),
+            };
+            let src = match reader.read_u8("an instruction")? {
+            0 => MemRead::IntVar(read_int_var(reader, function, globals)?),
+            1 => MemRead::Number(read_constant(reader, constants)?),
+            2 => {
+                let id = read_array_var(reader, function, globals)?;
+                MemRead::ArrayRead(id, read_op(reader, function, globals, constants)?)
+            }
+            kind => return Err(format!("Corrupted file. Invalid %mov source kind {kind}.")),
+            };
+            if matches!(dest, MemWrite::ArrayWrite(_, _)) && matches!(src, MemRead::ArrayRead(_, _)) {
+                return Err(String::from("Corrupted file. %mov cannot both read and write an array."));
+            }
+            Bytecode::Mov(dest, src)
+        }
+        7 => {
+            let count = reader.read_u32("an instruction")? as usize;
+            let mut dests = vec![];
+            for _ in 0..count {
+                dests.push(read_value_var(reader, function, globals)?);
+            }
+            let func = reader.read_u32("an instruction")? as usize;
+            if func >= parameter_kinds.len() {
+                return Err(format!("Corrupted file. Call to function {func}, but there are only {} functions.", parameter_kinds.len()));
+            }
+            let count = reader.read_u32("an instruction")? as usize;
+            if count != parameter_kinds[func].len() {
+                return Err(format!("Corrupted file. Call passes {count} parameters to a function taking {}.", parameter_kinds[func].len()));
+            }
+            let mut params = vec![];
+            for kind in &parameter_kinds[func] {
+                if *kind == 0 {
+                    params.push(read_op(reader, function, globals, constants)?);
+                    continue;
+                }
+                if reader.read_u8("an instruction")? != 0 {
+                    return Err(String::from("Corrupted file. Call passes a number to an array, string or float parameter."));
+                }
+                if *kind == 2 {
+                    params.push(Op::Var(read_array_var(reader, function, globals)?));
+                } else if *kind == 5 {
+                    params.push(Op::Var(read_float_var(reader, function, globals)?));
+                } else if *kind == 7 {
+                    params.push(Op::Var(read_float_array_var(reader, function, globals)?));
+                } else {
+                    params.push(Op::Var(read_string_var(reader, function, globals)?));
+                }
+            }
+            Bytecode::Call(dests, func, params)
+        }
+        8 => {
+            let count = reader.read_u32("an instruction")? as usize;
+            let mut srcs = vec![];
+            for _ in 0..count {
+                srcs.push(read_value_op(reader, function, globals, constants)?);
+            }
+            Bytecode::Return(srcs)
+        }
+        9 => {
+            let dest = read_int_var(reader, function, globals)?;
+            Bytecode::Not(dest, read_op(reader, function, globals, constants)?)
+        }
+        opcode @ 10..=25 => {
+            let dest = read_int_var(reader, function, globals)?;
+            let src1 = read_op(reader, function, globals, constants)?;
+            let src2 = read_op(reader, function, globals, constants)?;
+            match opcode {
+            10 => Bytecode::Add(dest, src1, src2),
+            11 => Bytecode::Sub(dest, src1, src2),
+            12 => Bytecode::Mult(dest, src1, src2),
+            13 => Bytecode::Div(dest, src1, src2),
+            14 => Bytecode::Mod(dest, src1, src2),
+            15 => Bytecode::And(dest, src1, src2),
+            16 => Bytecode::Or(dest, src1, src2),
+            17 => Bytecode::Xor(dest, src1, src2),
+            18 => Bytecode::Shl(dest, src1, src2),
+            19 => Bytecode::Shr(dest, src1, src2),
+            20 => Bytecode::LessThan(dest, src1, src2),
+            21 => Bytecode::LessEqual(dest, src1, src2),
+            22 => Bytecode::NotEqual(dest, src1, src2),
+            23 => Bytecode::Equal(dest, src1, src2),
+            24 => Bytecode::GreaterEqual(dest, src1, src2),
+            _ => Bytecode::GreaterThan(dest, src1, src2),
+            }
+        }
+        30 => Bytecode::Jmp(reader.read_u32("an instruction")? as usize),
+        31 => {
+            let src = read_op(reader, function, globals, constants)?;
+            Bytecode::BranchIf(src, reader.read_u32("an instruction")? as usize)
+        }
+        32 => {
+            let src = read_op(reader, function, globals, constants)?;
+            Bytecode::BranchIfn(src, reader.read_u32("an instruction")? as usize)
+        }
+        33 => {
+            let index = read_op(reader, function, globals, constants)?;
+            Bytecode::Bounds(index, read_op(reader, function, globals, constants)?)
+        }
+        34 => {
+            let dest = read_int_var(reader, function, globals)?;
+            let id = reader.read_i32("an instruction")?;
+            if !function.variables.values().chain(globals.values()).any(|v| matches!(v, VariableType::ArrayVar(i, _) | VariableType::ArrayRef(i)
+                | VariableType::FloatArrayVar(i, _) | VariableType::FloatArrayRef(i) if *i == id)) {
+                return Err(format!("Corrupted file. Instruction in function '{}' uses {id}, which is not an array.", function.name));
+            }
+            Bytecode::Len(dest, id)
+        }
+        35 => {
+            let src = read_op(reader, function, globals, constants)?;
+            let default = reader.read_u32("an instruction")? as usize;
+            let count = reader.read_u32("an instruction")?;
+            if count == 0 {
+                return Err(String::from("Corrupted file. %switch has an empty jump table."));
+            }
+            let mut table = vec![];
+            for _ in 0..count {
+                table.push(reader.read_u32("an instruction")? as usize);
+            }
+            Bytecode::Switch(src, default, table)
+        }
+        36 => Bytecode::OutChar(read_op(reader, function, globals, constants)?),
+        37 => Bytecode::OutStr(read_string_op(reader, function, globals, strings)?),
+        38 => Bytecode::InStr(read_string_var(reader, function, globals)?),
+        39 => {
+            let id = read_string_var(reader, function, globals)?;
+            if id < 0 {
+                return Err(format!("Corrupted file. Function '{}' declares the global variable {id}.", function.name));
+            }
+            Bytecode::Str(id)
+        }
+        40 => {
+            let dest = read_string_var(reader, function, globals)?;
+            Bytecode::StrMov(dest, read_string_op(reader, function, globals, strings)?)
+        }
+        opcode @ (41 | 44) => {
+            let dest = if opcode == 41 { read_string_var(reader, function, globals)? } else { read_int_var(reader, function, globals)? };
+            let src1 = read_string_op(reader, function, globals, strings)?;
+            let src2 = read_string_op(reader, function, globals, strings)?;
+            if opcode == 41 { Bytecode::Concat(dest, src1, src2) } else { Bytecode::StrCmp(dest, src1, src2) }
+        }
+        42 => {
+            let dest = read_int_var(reader, function, globals)?;
+            Bytecode::StrLen(dest, read_string_op(reader, function, globals, strings)?)
+        }
+        43 => {
+            let dest = read_int_var(reader, function, globals)?;
+            let src = read_string_op(reader, function, globals, strings)?;
+            Bytecode::CharAt(dest, src, read_op(reader, function, globals, constants)?)
+        }
+        45 => {
+            let dest = read_string_var(reader, function, globals)?;
+            Bytecode::Chr(dest, read_op(reader, function, globals, constants)?)
+        }
+        47 => {
+            let id = read_float_var(reader, function, globals)?;
+            if id < 0 {
+                return Err(format!("Corrupted file. Function '{}' declares the global variable {id}.", function.name));
+            }
+            Bytecode::Float(id)
+        }
+        48 => {
+            let dest = read_float_var(reader, function, globals)?;
+            Bytecode::FloatMov(dest, read_float_op(reader, function, globals, constants)?)
+        }
+        49 => Bytecode::OutFloat(read_float_op(reader, function, globals, constants)?),
+        50 => Bytecode::InFloat(read_float_var(reader, function, globals)?),
+        opcode @ 51..=60 => {
+            let dest = if opcode <= 54 { read_float_var(reader, function, globals)? } else { read_int_var(reader, function, globals)? };
+            let src1 = read_float_op(reader, function, globals, constants)?;
+            let src2 = read_float_op(reader, function, globals, constants)?;
+            match opcode {
+            51 => Bytecode::FloatAdd(dest, src1, src2),
+            52 => Bytecode::FloatSub(dest, src1, src2),
+            53 => Bytecode::FloatMult(dest, src1, src2),
+            54 => Bytecode::FloatDiv(dest, src1, src2),
+            55 => Bytecode::FloatLessThan(dest, src1, src2),
+            56 => Bytecode::FloatLessEqual(dest, src1, src2),
+            57 => Bytecode::FloatNotEqual(dest, src1, src2),
+            58 => Bytecode::FloatEqual(dest, src1, src2),
+            59 => Bytecode::FloatGreaterEqual(dest, src1, src2),
+            _ => Bytecode::FloatGreaterThan(dest, src1, src2),
+            }
+        }
+        61 => {
+            let dest = read_float_var(reader, function, globals)?;
+            Bytecode::IntToFloat(dest, read_op(reader, function, globals, constants)?)
+        }
+        62 => {
+            let dest = read_int_var(reader, function, globals)?;
+            Bytecode::FloatToInt(dest, read_float_op(reader, function, globals, constants)?)
+        }
+        64 => {
+            let id = read_float_array_var(reader, function, globals)?;
+            let len = reader.read_i32("an instruction")?;
+            if !function.variables.values().any(|v| matches!(v, VariableType::FloatArrayVar(i, l) if *i == id && *l == len)) {
+                return Err(format!("Corrupted file. Array declaration in function '{}' does not match the variable table.", function.name));
+            }
+            Bytecode::FloatArray(id, len)
+        }
+        65 => {
+            let dest = read_float_var(reader, function, globals)?;
+            let array = read_float_array_var(reader, function, globals)?;
+            Bytecode::FloatArrayRead(dest, array, read_op(reader, function, globals, constants)?)
+        }
+        66 => {
+            let array = read_float_array_var(reader, function, globals)?;
+            let index = read_op(reader, function, globals, constants)?;
+            Bytecode::FloatArrayWrite(array, index, read_float_op(reader, function, globals, constants)?)
+        }
+        63 => {
+            let count = reader.read_u32("an instruction")? as usize;
+            let mut dests = vec![];
+            for _ in 0..count {
+                dests.push(read_int_var(reader, function, globals)?);
+            }
+            let index = reader.read_u8("an instruction")? as usize;
+            if index >= BUILTINS.len() {
+                return Err(format!("Corrupted file. Call to builtin {index}, but there are only {} builtins.", BUILTINS.len()));
+            }
+            let (_, builtin, parameters, returns) = BUILTINS[index];
+            if count != returns {
+                return Err(format!("Corrupted file. Call stores {count} values from a builtin returning {returns}."));
+            }
+            let mut params = vec![];
+            for _ in 0..parameters {
+                params.push(read_op(reader, function, globals, constants)?);
+            }
+            Bytecode::Builtin(dests, builtin, params)
+        }
+        opcode => return Err(format!("Corrupted file. Invalid opcode {opcode} in function '{}'.", function.name)),
+        };
+        Ok(bytecode)
+    }
+
+    fn read_int_var(reader: &mut ByteReader, function: &FunctionBytecode, globals: &HashMap<String, VariableType>) -> Result<i32, String> {
+        let id = reader.read_i32("an instruction")?;
+        if function.variables.values().chain(globals.values()).any(|v| matches!(v, VariableType::IntVar(i) | VariableType::LongVar(i) if *i == id)) {
+            Ok(id)
+        } else {
+            Err(format!("Corrupted file. Instruction in function '{}' uses {id}, which is not an integer variable.", function.name))
+        }
+    }
+
+    fn read_array_var(reader: &mut ByteReader, function: &FunctionBytecode, globals: &HashMap<String, VariableType>) -> Result<i32, String> {
+        let id = reader.read_i32("an instruction")?;
+        if function.variables.values().chain(globals.values()).any(|v| matches!(v, VariableType::ArrayVar(i, _) | VariableType::ArrayRef(i) if *i == id)) {
+            Ok(id)
+        } else {
+            Err(format!("Corrupted file. Instruction in function '{}' uses {id}, which is not an array.", function.name))
+        }
+    }
+
+    fn read_float_array_var(reader: &mut ByteReader, function: &FunctionBytecode, globals: &HashMap<String, VariableType>) -> Result<i32, String> {
+        let id = reader.read_i32("an instruction")?;
+        if function.variables.values().chain(globals.values()).any(|v| matches!(v, VariableType::FloatArrayVar(i, _) | VariableType::FloatArrayRef(i) if *i == id)) {
+            Ok(id)
+        } else {
+            Err(format!("Corrupted file. Instruction in function '{}' uses {id}, which is not a float array.", function.name))
+        }
+    }
+
+    fn read_string_var(reader: &mut ByteReader, function: &FunctionBytecode, globals: &HashMap<String, VariableType>) -> Result<i32, String> {
+        let id = reader.read_i32("an instruction")?;
+        if function.variables.values().chain(globals.values()).any(|v| matches!(v, VariableType::StringVar(i) if *i == id)) {
+            Ok(id)
+        } else {
+            Err(format!("Corrupted file. Instruction in function '{}' uses {id}, which is not a string variable.", function.name))
+        }
+    }
+
+    fn read_float_var(reader: &mut ByteReader, function: &FunctionBytecode, globals: &HashMap<String, VariableType>) -> Result<i32, String> {
+        let id = reader.read_i32("an instruction")?;
+        if function.variables.values().chain(globals.values()).any(|v| matches!(v, VariableType::FloatVar(i) if *i == id)) {
+            Ok(id)
+        } else {
+            Err(format!("Corrupted file. Instruction in function '{}' uses {id}, which is not a float variable.", function.name))
+        }
+    }
+
+    // a variable that holds a value returned from a function.
+    fn read_value_var(reader: &mut ByteReader, function: &FunctionBytecode, globals: &HashMap<String, VariableType>) -> Result<i32, String> {
+        let id = reader.read_i32("an instruction")?;
+        if function.variables.values().chain(globals.values()).any(|v| matches!(v, VariableType::IntVar(i) | VariableType::LongVar(i) | VariableType::FloatVar(i)
+            | VariableType::StringVar(i) if *i == id)) {
+            Ok(id)
+        } else {
+            Err(format!("Corrupted file. Instruction in function '{}' uses {id}, which cannot hold a returned value.", function.name))
+        }
+    }
+
+    fn read_value_op(reader: &mut ByteReader, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, constants: &[i64]) -> Result<Op, String> {
+        match reader.read_u8("an instruction")? {
+        0 => Ok(Op::Var(read_value_var(reader, function, globals)?)),
+        1 => Ok(Op::Num(read_constant(reader, constants)?)),
+        kind => Err(format!("Corrupted file. Invalid operand kind {kind}.")),
+        }
+    }
+
+    // float constants are the bits of the f64, and can never be NaN or infinite.
+    fn read_float_op(reader: &mut ByteReader, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, constants: &[i64]) -> Result<FloatOp, String> {
+        match reader.read_u8("an instruction")? {
+        0 => Ok(FloatOp::Var(read_float_var(reader, function, globals)?)),
+        1 => {
+            let num = f64::from_bits(read_constant(reader, constants)? as u64);
+            if !num.is_finite() {
+                return Err(format!("Corrupted file. Function '{}' uses a float constant that is not a number.", function.name));
+            }
+            Ok(FloatOp::Num(num))
+        }
+        kind => Err(format!("Corrupted file. Invalid float operand kind {kind}.")),
+        }
+    }
+
+    fn read_string_op(reader: &mut ByteReader, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, strings: &[String]) -> Result<StrOp, String> {
+        match reader.read_u8("an instruction")? {
+        0 => Ok(StrOp::Var(read_string_var(reader, function, globals)?)),
+        1 => {
+            let id = reader.read_u32("an instruction")? as usize;
+            if id >= strings.len() {
+                return Err(format!("Corrupted file. Function '{}' uses the string constant {id}, which does not exist.", function.name));
+            }
+            Ok(StrOp::Data(id))
+        }
+        kind => Err(format!("Corrupted file. Invalid string operand kind {kind}.")),
+        }
+    }
+
+    fn read_constant(reader: &mut ByteReader, constants: &[i64]) -> Result<i64, String> {
+        let id = reader.read_u32("an instruction")? as usize;
+        match constants.get(id) {
+        Some(constant) => Ok(*constant),
+        None => Err(format!("Corrupted file. Constant {id} is out of range. There are only {} constants.", constants.len())),
+        }
+    }
+
+    fn read_op(reader: &mut ByteReader, function: &FunctionBytecode, globals: &HashMap<String, VariableType>, constants: &[i64]) -> Result<Op, String> {
+        match reader.read_u8("an instruction")? {
+        0 => Ok(Op::Var(read_int_var(reader, function, globals)?)),
+        1 => Ok(Op::Num(read_constant(reader, constants)?)),
+        kind => Err(format!("Corrupted file. Invalid operand kind {kind}.")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod ir_tests {
+    use crate::interpreter::*;
+
+   #[test]
+    fn ir_lex() {
+        {
+            let toks = lex_ir("%add a, b, c\n").unwrap();
+            assert!(toks.len() == 8);
+            assert!(matches!(toks[0], IRTok::Add));
+            assert!(matches!(toks[1], IRTok::Var(_)));
+            assert!(matches!(toks[2], IRTok::Comma));
+            assert!(matches!(toks[3], IRTok::Var(_)));
+            assert!(matches!(toks[4], IRTok::Comma));
+            assert!(matches!(toks[5], IRTok::Var(_)));
+        }
+
+        {
+            let toks = lex_ir("%func main,,,\n").unwrap();
+            assert!(toks.len() == 7);
+            assert!(matches!(toks[0], IRTok::Func));
+            assert!(matches!(toks[1], IRTok::Var(_)));
+            assert!(matches!(toks[2], IRTok::Comma));
+            assert!(matches!(toks[3], IRTok::Comma));
+            assert!(matches!(toks[4], IRTok::Comma));
+        }
+
+        {
+            let toks = lex_ir("%func,main,,,\n").unwrap();
+            assert!(toks.len() == 8);
+            assert!(matches!(toks[0], IRTok::Func));
+            assert!(matches!(toks[1], IRTok::Comma));
+            assert!(matches!(toks[2], IRTok::Var(_)));
+            assert!(matches!(toks[3], IRTok::Comma));
+            assert!(matches!(toks[4], IRTok::Comma));
+            assert!(matches!(toks[5], IRTok::Comma));
+        }
+
+        {
+            let toks = lex_ir("%mov [arr+0], 100\n").unwrap();
+            assert!(toks.len() == 10);
+            assert!(matches!(toks[0], IRTok::Mov));
+            assert!(matches!(toks[1], IRTok::LBrace));
+            assert!(matches!(toks[2], IRTok::Var(_)));
+            assert!(matches!(toks[3], IRTok::Plus));
+            assert!(matches!(toks[4], IRTok::Num(0)));
+            assert!(matches!(toks[5], IRTok::RBrace));
+            assert!(matches!(toks[6], IRTok::Comma));
+            assert!(matches!(toks[7], IRTok::Num(100)));
+        }
+    }
+
+    #[test]
+    fn ir_negative_numbers() {
+        let toks = lex_ir("%mov x, -2147483648\n").unwrap();
+        assert!(matches!(toks[3], IRTok::Num(-2147483648)));
+        assert!(parse_ir(&lex_ir("%func main()\n%int x\n%mov x, 2147483648\n%endfunc\n").unwrap(), &mut 0).is_err());
+        assert!(parse_ir(&lex_ir("%func main()\n%int x\n%mov x, -2147483649\n%endfunc\n").unwrap(), &mut 0).is_err());
+        assert!(lex_ir("%mov x, 9223372036854775808\n").is_err());
+        assert!(lex_ir("%mov x, -9223372036854775809\n").is_err());
+        assert!(lex_ir("%mov x, - 1\n").is_err());
+
+        let code = "%func main()\n\
+                    %int x\n\
+                    %mov x, -1\n\
+                    %out x\n\
+                    %sub x, 0, -2147483648\n\
+                    %out x\n\
+                    %add x, 2147483647, 1\n\
+                    %out x\n\
+                    %div x, -2147483648, -1\n\
+                    %out x\n\
+                    %mod x, -7, 2\n\
+                    %out x\n\
+                    %endfunc\n";
+        assert_eq!(execute_ir_output(code).unwrap(), "-1\n-2147483648\n-2147483648\n-2147483648\n-1\n");
+    }
+
+    fn parse(code: &str) -> Program {
+        let tokens = lex_ir(code).unwrap();
+        match parse_ir(&tokens, &mut 0) {
+        Ok(program) => program,
+        Err(e) => panic!("{e}"),
+        }
+    }
+
+    fn run(program: &Program) -> String {
+        let mut output: Vec<u8> = vec![];
+        run_program(&io::stdin(), &mut output, program);
+        String::from_utf8(output).unwrap()
+    }
+
+    // saves the program as bytecode and loads it back. the loaded program must print the same
+    // output as the original, and its canonical IR is returned to compare with the original's.
+    fn round_trip(program: &Program) -> String {
+        let loaded = match read_bytecode(&write_bytecode(program)) {
+        Ok(loaded) => loaded,
+        Err(e) => panic!("{e}"),
+        };
+        assert_eq!(run(&loaded), run(program));
+        disassemble(&loaded)
+    }
+
+    #[test]
+    fn ir_disassemble() {
+        let code = "%func add(%int a, %int b) ; adds two numbers\n\
+                    %int c\n\
+                    %add c, a, b\n\
+                    %ret c\n\
+                    %endfunc\n\
+                    \n\
+                    %func main\n\
+                    %int[] array, 4\n\
+                    %int i\n\
+                    %int x\n\
+                    :loop\n\
+                    %lt x, i, 4\n\
+                    %branch_ifn x, :done\n\
+                    %call x, add(i, 10)\n\
+                    %mov [array + i], x\n\
+                    %mov x, [array + i]\n\
+                    %out x\n\
+                    %add i, i, 1\n\
+                    %jmp :loop\n\
+                    :done\n\
+                    %endfunc\n";
+
+        let canonical = disassemble(&parse(code));
+        assert_eq!(canonical, "%func add(%int a, %int b)\n\
+                               %int c\n\
+                               %add c, a, b\n\
+                               %ret c\n\
+                               %endfunc\n\
+                               %func main()\n\
+                               %int[] array, 4\n\
+                               %int i\n\
+                               %int x\n\
+                               :label1\n\
+                               %lt x, i, 4\n\
+                               %branch_ifn x, :label2\n\
+                               %call x, add(i, 10)\n\
+                               %mov [array + i], x\n\
+                               %mov x, [array + i]\n\
+                               %out x\n\
+                               %add i, i, 1\n\
+                               %jmp :label1\n\
+                               :label2\n\
+                               %endfunc\n");
+
+        // the canonical IR is a fixed point, and runs the same as the original IR.
+        assert_eq!(disassemble(&parse(&canonical)), canonical);
+        assert_eq!(run(&parse(&canonical)), run(&parse(code)));
+        assert_eq!(run(&parse(code)), "10\n11\n12\n13\n");
+    }
+
+    #[test]
+    fn ir_bytecode_file() {
+        let code = "%func twice(%int a)\n\
+                    %int b\n\
+                    %mult b, a, 2\n\
+                    %ret b\n\
+                    %endfunc\n\
+                    %func main()\n\
+                    %int[] array, 3\n\
+                    %int i\n\
+                    %int x\n\
+                    :loop\n\
+                    %lt x, i, 3\n\
+                    %branch_ifn x, :done\n\
+                    %call x, twice(i)\n\
+                    %mov [array + i], x\n\
+                    %mov x, [array + i]\n\
+                    %out x\n\
+                    %add i, i, 1\n\
+                    %jmp :loop\n\
+                    :done\n\
+                    %out 1000000\n\
+                    %endfunc\n";
+
+        let program = parse(code);
+        let bytes = write_bytecode(&program);
+        let loaded = match read_bytecode(&bytes) {
+        Ok(loaded) => loaded,
+        Err(e) => panic!("{e}"),
+        };
+        assert_eq!(disassemble(&loaded), disassemble(&program));
+        assert_eq!(run(&loaded), "0\n2\n4\n1000000\n");
+
+        // every truncation of the file is rejected.
+        for len in 0..bytes.len() {
+            assert!(read_bytecode(&bytes[..len]).is_err());
+        }
+
+        // flipping any bit of the file is caught.
+        for i in 0..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[i] ^= 0x10;
+            assert!(read_bytecode(&corrupted).is_err());
+        }
+
+        // a well formed file that breaks the bytecode rules is also rejected.
+        // here '%jmp :loop' is redirected past the end of the function.
+        let mut invalid = bytes.clone();
+        let jmp = invalid.windows(5).position(|w| w == [30, 3, 0, 0, 0]).unwrap();
+        invalid[jmp + 1] = 100;
+        let sum = checksum(&invalid[BYTECODE_HEADER_LEN..]);
+        invalid[10..14].copy_from_slice(&sum.to_le_bytes());
+        match read_bytecode(&invalid) {
+        Err(e) => assert!(e.contains("jumps to 100"), "{e}"),
+        Ok(_) => panic!("invalid jump target was accepted"),
+        }
+    }
+
+    #[test]
+    fn ir_globals() {
+        let code = "%global count\n\
+                    %global[] table, 3\n\
+                    %func bump(%int n)\n\
+                    %add count, count, n\n\
+                    %mov [table + n], count\n\
+                    %ret count\n\
+                    %endfunc\n\
+                    %func main()\n\
+                    %int x\n\
+                    %call x, bump(1)\n\
+                    %call x, bump(2)\n\
+                    %out count\n\
+                    %mov x, [table + 1]\n\
+                    %out x\n\
+                    %int count\n\
+                    %mov count, 100\n\
+                    %out count\n\
+                    %call x, bump(0)\n\
+                    %out x\n\
+                    %endfunc\n";
+
+        // globals are shared by every call, and a local with the same name hides the global.
+        let program = parse(code);
+        assert_eq!(run(&program), "3\n1\n100\n3\n");
+
+        let canonical = disassemble(&program);
+        assert!(canonical.starts_with("%global count\n%global[] table, 3\n%func bump(%int n)\n"));
+        assert_eq!(disassemble(&parse(&canonical)), canonical);
+
+        assert_eq!(round_trip(&program), canonical);
+
+        // globals must be declared before the functions that use them.
+        let late = "%func main()\n%out g\n%endfunc\n%global g\n";
+        let tokens = lex_ir(late).unwrap();
+        assert!(parse_ir(&tokens, &mut 0).is_err());
+
+        let twice = "%global g\n%global g\n%func main()\n%endfunc\n";
+        let tokens = lex_ir(twice).unwrap();
+        assert!(parse_ir(&tokens, &mut 0).is_err());
+    }
+
+    #[test]
+    fn ir_array_parameters() {
+        let code = "%global[] shared, 2\n\
+                    %func fill(%int[] a, %int n, %int value)\n\
+                    %int i\n\
+                    %int done\n\
+                    :loop\n\
+                    %ge done, i, n\n\
+                    %branch_if done, :end\n\
+                    %mov [a + i], value\n\
+                    %add i, i, 1\n\
+                    %jmp :loop\n\
+                    :end\n\
+                    %ret n\n\
+                    %endfunc\n\
+                    %func main()\n\
+                    %int[] array, 3\n\
+                    %int x\n\
+                    %call x, fill(array, 3, 7)\n\
+                    %mov x, [array + 2]\n\
+                    %out x\n\
+                    %call x, fill(shared, 2, 5)\n\
+                    %mov x, [shared + 1]\n\
+                    %out x\n\
+                    %call x, fill(array, 4, 1)\n\
+                    %endfunc\n";
+
+        // writes through the parameter change the caller's array, and the
+        // bounds are checked against the caller's array.
+        let program = parse(code);
+        let output = execute_ir_output(code);
+        assert!(output.unwrap_err().contains("Array out of bounds"));
+        assert!(run(&program).starts_with("7\n5\n"));
+
+        let canonical = disassemble(&program);
+        assert!(canonical.contains("%func fill(%int[] a, %int n, %int value)\n"));
+        assert_eq!(round_trip(&program), canonical);
+
+        // arrays and integers cannot be mixed up.
+        let wrong = "%func f(%int[] a)\n%ret 0\n%endfunc\n%func main()\n%int x\n%call x, f(x)\n%endfunc\n";
+        let tokens = lex_ir(wrong).unwrap();
+        assert!(parse_ir(&tokens, &mut 0).is_err());
+
+        let wrong = "%func f(%int a)\n%ret a\n%endfunc\n%func main()\n%int x\n%int[] y, 2\n%call x, f(y)\n%endfunc\n";
+        let tokens = lex_ir(wrong).unwrap();
+        assert!(parse_ir(&tokens, &mut 0).is_err());
+    }
+
+    #[test]
+    fn ir_switch() {
+        let code = "%func main()\n\
+                    %int x\n\
+                    :loop\n\
+                    %switch x, :other, :zero, :one\n\
+                    :zero\n\
+                    %out 10\n\
+                    %add x, x, 1\n\
+                    %jmp :loop\n\
+                    :one\n\
+                    %out 20\n\
+                    %add x, x, 1\n\
+                    %jmp :loop\n\
+                    :other\n\
+                    %out 30\n\
+                    %switch -1, :done, :loop\n\
+                    :done\n\
+                    %endfunc\n";
+
+        // values outside of the jump table go to the default label.
+        let program = parse(code);
+        assert_eq!(run(&program), "10\n20\n30\n");
+
+        let canonical = disassemble(&program);
+        assert!(canonical.contains("%switch x, :label4, :label2, :label3\n"));
+        assert_eq!(round_trip(&program), canonical);
+
+        // every entry of the jump table must land on a label.
+        let mut invalid = write_bytecode(&program);
+        let switch = invalid.windows(6).position(|w| w == [35, 0, 0, 0, 0, 0]).unwrap();
+        invalid[switch + 14] = 100;
+        let sum = checksum(&invalid[BYTECODE_HEADER_LEN..]);
+        invalid[10..14].copy_from_slice(&sum.to_le_bytes());
+        match read_bytecode(&invalid) {
+        Err(e) => assert!(e.contains("jumps to 100"), "{e}"),
+        Ok(_) => panic!("invalid jump table was accepted"),
+        }
+
+        let wrong = "%func main()\n%int x\n%switch x, :missing, :missing\n%endfunc\n";
+        let tokens = lex_ir(wrong).unwrap();
+        assert!(parse_ir(&tokens, &mut 0).is_err());
+
+        let wrong = "%func main()\n%int x\n:a\n%switch x, :a\n%endfunc\n";
+        let tokens = lex_ir(wrong).unwrap();
+        assert!(parse_ir(&tokens, &mut 0).is_err());
+    }
+
+    #[test]
+    fn ir_strings() {
+        let code = "%data greeting, \"x = \\\"\\t\\\\\"\n\
+                    %data newline, \"\\n\"\n\
+                    %func main()\n\
+                    %int c\n\
+                    %outs greeting\n\
+                    %mov c, 104\n\
+                    %outc c\n\
+                    %outc 105\n\
+                    %outs newline\n\
+                    %out 7\n\
+                    %endfunc\n";
+
+        let program = parse(code);
+        assert_eq!(run(&program), "x = \"\t\\hi\n7\n");
+
+        let canonical = disassemble(&program);
+        assert!(canonical.starts_with("%data _str1, \"x = \\\"\\t\\\\\"\n%data _str2, \"\\n\"\n"));
+        assert!(canonical.contains("%outs _str1\n%mov c, 104\n%outc c\n%outc 105\n%outs _str2\n"));
+        assert_eq!(disassemble(&parse(&canonical)), canonical);
+        assert_eq!(round_trip(&program), canonical);
+
+        // printing a number that is not a character is a runtime error.
+        let output = execute_ir_output("%func main()\n%outc -1\n%endfunc\n");
+        assert!(output.unwrap_err().contains("is not a character"));
+
+        let wrong = "%func main()\n%outs missing\n%endfunc\n";
+        let tokens = lex_ir(wrong).unwrap();
+        assert!(parse_ir(&tokens, &mut 0).is_err());
+        assert!(lex_ir("%data s, \"no end\n").is_err());
+        assert!(lex_ir("%data s, \"\\q\"\n").is_err());
+    }
+
+    #[test]
+    fn ir_string_variables() {
+        let code = "%global_string last\n\
+                    %data hello, \"héllo\"\n\
+                    %func shout(%string s, %int c)\n\
+                    %string t\n\
+                    %chr t, c\n\
+                    %concat last, s, t\n\
+                    %len c, last\n\
+                    %ret c\n\
+                    %endfunc\n\
+                    %func main()\n\
+                    %string s\n\
+                    %int n\n\
+                    %mov s, hello\n\
+                    %call n, shout(s, 33)\n\
+                    %outs last\n\
+                    %out n\n\
+                    %charat n, s, 1\n\
+                    %outc n\n\
+                    %strcmp n, s, hello\n\
+                    %out n\n\
+                    %strcmp n, s, last\n\
+                    %out n\n\
+                    %len n, hello\n\
+                    %out n\n\
+                    %endfunc\n";
+
+        let program = parse(code);
+        assert_eq!(run(&program), "héllo!6\né0\n-1\n5\n");
+
+        let canonical = disassemble(&program);
+        assert!(canonical.starts_with("%global_string last\n%data _str1, \"héllo\"\n%func shout(%string s, %int c)\n"));
+        assert!(canonical.contains("%mov s, _str1\n%call n, shout(s, 33)\n%outs last\n"));
+        assert_eq!(disassemble(&parse(&canonical)), canonical);
+        assert_eq!(round_trip(&program), canonical);
+
+        // characters past the end of the string are a runtime error.
+        let output = execute_ir_output("%func main()\n%int c\n%string s\n%charat c, s, 0\n%endfunc\n");
+        assert!(output.unwrap_err().contains("String index out of bounds"));
+
+        for wrong in ["%func main()\n%int n\n%concat n, n, n\n%endfunc\n",
+                      "%func main()\n%string s\n%add s, 1, 2\n%endfunc\n",
+                      "%func main()\n%string s\n%mov s, missing\n%endfunc\n",
+                      "%func f(%int a)\n%ret a\n%endfunc\n%func main()\n%int n\n%string s\n%call n, f(s)\n%endfunc\n"] {
+            let tokens = lex_ir(wrong).unwrap();
+            assert!(parse_ir(&tokens, &mut 0).is_err());
+        }
+    }
+
+    #[test]
+    fn ir_long() {
+        let code = "%global_long total\n\
+                    %func square(%long n)\n\
+                    %mult n, n, n\n\
+                    %ret n\n\
+                    %endfunc\n\
+                    %func main()\n\
+                    %long x\n\
+                    %int i\n\
+                    %mov x, 3000000000\n\
+                    %add x, x, x\n\
+                    %out x\n\
+                    %mov i, x\n\
+                    %out i\n\
+                    %call total, square(100000)\n\
+                    %out total\n\
+                    %mov x, 9223372036854775807\n\
+                    %add x, x, 1\n\
+                    %out x\n\
+                    %endfunc\n";
+
+        let program = parse(code);
+        assert_eq!(run(&program), "6000000000\n1705032704\n10000000000\n-9223372036854775808\n");
+
+        let canonical = disassemble(&program);
+        assert!(canonical.starts_with("%global_long total\n%func square(%long n)\n"));
+        assert!(canonical.contains("%long x\n%int i\n"));
+        assert_eq!(disassemble(&parse(&canonical)), canonical);
+        assert_eq!(round_trip(&program), canonical);
+    }
+
+    #[test]
+    fn ir_float() {
+        let code = "%global_float half\n\
+                    %func average(%float a, %float b)\n\
+                    %float sum\n\
+                    %fadd sum, a, b\n\
+                    %fdiv sum, sum, 2\n\
+                    %int whole\n\
+                    %ftoi whole, sum\n\
+                    %ret whole\n\
+                    %endfunc\n\
+                    %func main()\n\
+                    %float x\n\
+                    %float y\n\
+                    %int n\n\
+                    %mov half, 0.5\n\
+                    %outf half\n\
+                    %mov x, 1e-7\n\
+                    %outf x\n\
+                    %itof y, 7\n\
+                    %fmult y, y, half\n\
+                    %outf y\n\
+                    %fsub x, 0.0, 2.75\n\
+                    %ftoi n, x\n\
+                    %out n\n\
+                    %flt n, x, y\n\
+                    %out n\n\
+                    %feq n, y, 3.5\n\
+                    %out n\n\
+                    %call n, average(y, half)\n\
+                    %out n\n\
+                    %fdiv x, 1.0, 3.0\n\
+                    %outf x\n\
+                    %endfunc\n";
+
+        let program = parse(code);
+        assert_eq!(run(&program), "0.5\n1e-7\n3.5\n-2\n1\n1\n2\n0.3333333333333333\n");
+
+        let canonical = disassemble(&program);
+        assert!(canonical.starts_with("%global_float half\n%func average(%float a, %float b)\n"));
+        assert!(canonical.contains("%fdiv sum, sum, 2.0\n"));
+        assert_eq!(disassemble(&parse(&canonical)), canonical);
+        assert_eq!(round_trip(&program), canonical);
+
+        // floats and integers are not mixed up, and out of range conversions are runtime errors.
+        assert!(execute_ir_output("%func main()\n%float x\n%int n\n%add n, n, x\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%float x\n%int n\n%fadd n, x, x\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%float x\n%int n\n%mov n, x\n%endfunc\n").is_err());
+        assert!(lex_ir("%mov x, 1e999\n").is_err());
+        let e = execute_ir_output("%func main()\n%float x\n%int n\n%mov x, 3e9\n%ftoi n, x\n%endfunc\n").unwrap_err();
+        assert!(e.contains("3000000000.0 does not fit in an int"));
+        assert_eq!(execute_ir_output("%func main()\n%float x\n%long n\n%mov x, -3e9\n%ftoi n, x\n%out n\n%endfunc\n").unwrap(), "-3000000000\n");
+        let e = execute_ir_output("%func main()\n%float x\n%long n\n%mov x, 1e19\n%ftoi n, x\n%endfunc\n").unwrap_err();
+        assert!(e.contains("1e19 does not fit in a long"));
+
+    }
+
+    #[test]
+    fn ir_float_arrays() {
+        let code = "%global_float[] weights, 2\n\
+                    %func total(%float[] values)\n\
+                    %float sum\n\
+                    %float value\n\
+                    %mov value, [values + 0]\n\
+                    %fadd sum, sum, value\n\
+                    %mov value, [values + 1]\n\
+                    %fadd sum, sum, value\n\
+                    %ret sum\n\
+                    %endfunc\n\
+                    %func main()\n\
+                    %float[] a, 2\n\
+                    %float x\n\
+                    %int n\n\
+                    %mov [a + 0], 1.5\n\
+                    %mov x, 0.25\n\
+                    %mov n, 1\n\
+                    %mov [a + n], x\n\
+                    %call x, total(a)\n\
+                    %outf x\n\
+                    %mov [weights + 1], 2\n\
+                    %mov x, [weights + 1]\n\
+                    %outf x\n\
+                    %len n, a\n\
+                    %out n\n\
+                    %call x, total(weights)\n\
+                    %outf x\n\
+                    %endfunc\n";
+
+        let program = parse(code);
+        assert_eq!(run(&program), "1.75\n2.0\n2\n2.0\n");
+
+        let canonical = disassemble(&program);
+        assert!(canonical.starts_with("%global_float[] weights, 2\n%func total(%float[] values)\n"));
+        assert!(canonical.contains("%float[] a, 2\n"));
+        assert!(canonical.contains("%mov [a + n], x\n"));
+        assert!(canonical.contains("%mov [weights + 1], 2.0\n"));
+        assert!(canonical.contains("%mov x, [weights + 1]\n"));
+        assert_eq!(disassemble(&parse(&canonical)), canonical);
+        assert_eq!(round_trip(&program), canonical);
+
+        // float arrays hold floats, and are not mixed up with int arrays.
+        assert!(execute_ir_output("%func main()\n%float[] a, 2\n%int n\n%mov n, [a + 0]\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%float[] a, 2\n%int n\n%mov [a + 0], n\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%int[] a, 2\n%float x\n%mov x, [a + 0]\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func f(%int[] a)\n%endfunc\n%func main()\n%float[] a, 2\n%call f(a)\n%endfunc\n").is_err());
+        let e = execute_ir_output("%func main()\n%float[] a, 2\n%float x\n%mov x, [a + 2]\n%endfunc\n").unwrap_err();
+        assert!(e.contains("Array out of bounds"));
+        let e = execute_ir_output("%func main()\n%float[] a, 2\n%mov [a + -1], 1.0\n%endfunc\n").unwrap_err();
+        assert!(e.contains("Array out of bounds"));
+    }
+
+
+    #[test]
+    fn ir_multiple_returns() {
+        let code = "%func divmod(%int a, %int b)\n\
+                    %int q\n\
+                    %int r\n\
+                    %div q, a, b\n\
+                    %mod r, a, b\n\
+                    %ret q, r\n\
+                    %endfunc\n\
+                    %func tick(%int n)\n\
+                    %out n\n\
+                    %ret\n\
+                    %endfunc\n\
+                    %func main()\n\
+                    %int q\n\
+                    %long r\n\
+                    %call q, r, divmod(17, 5)\n\
+                    %out q\n\
+                    %out r\n\
+                    %call tick(9)\n\
+                    %endfunc\n";
+
+        let program = parse(code);
+        assert_eq!(run(&program), "3\n2\n9\n");
+
+        let canonical = disassemble(&program);
+        assert!(canonical.contains("%ret q, r\n"));
+        assert!(canonical.contains("%ret\n"));
+        assert!(canonical.contains("%call q, r, divmod(17, 5)\n"));
+        assert!(canonical.contains("%call tick(9)\n"));
+        assert_eq!(disassemble(&parse(&canonical)), canonical);
+        assert_eq!(round_trip(&program), canonical);
+
+        // a call stores exactly as many values as the function returns, and every '%ret' returns the same number.
+        let divmod = "%func divmod(%int a, %int b)\n%ret a, b\n%endfunc\n";
+        assert!(execute_ir_output(&format!("{divmod}%func main()\n%int q\n%call q, divmod(1, 2)\n%endfunc\n")).is_err());
+        assert!(execute_ir_output(&format!("{divmod}%func main()\n%int q\n%call q, q, q, divmod(1, 2)\n%endfunc\n")).is_err());
+        assert!(execute_ir_output("%func f(%int a)\n%ret a\n%ret a, a\n%endfunc\n%func main()\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%string s\n%ret s\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%int x\n%call x,").is_err());
+        assert!(execute_ir_output("%func main()\n%int x\n%call x, x,").is_err());
+
+        // a function that reaches '%endfunc' stores zeros.
+        assert_eq!(execute_ir_output("%func f()\n%endfunc\n%func main()\n%int a\n%int b\n%mov a, 4\n%call a, b, f()\n%out a\n%endfunc\n").unwrap(), "0\n");
+
+        // floats and strings are returned like integers, into variables of the same kind.
+        let half = "%func half(%float x)\n%float h\n%fdiv h, x, 2.0\n%ret h, 1\n%endfunc\n";
+        let program = parse(&format!("{half}%func main()\n%float h\n%int n\n%mov h, 5.0\n%call h, n, half(h)\n%outf h\n%out n\n%endfunc\n"));
+        assert_eq!(run(&program), "2.5\n1\n");
+        assert_eq!(round_trip(&program), disassemble(&program));
+        assert!(execute_ir_output(&format!("{half}%func main()\n%float x\n%int h\n%int n\n%call h, n, half(x)\n%endfunc\n")).is_err());
+        assert!(execute_ir_output("%func f(%float x, %int n)\n%ret x\n%ret n\n%endfunc\n%func main()\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%float x\n%ret x\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%float x\n%call x, abs(1)\n%endfunc\n").is_err());
+        assert_eq!(execute_ir_output("%func f()\n%endfunc\n%func main()\n%float x\n%mov x, 4.0\n%call x, f()\n%outf x\n%endfunc\n").unwrap(), "0.0\n");
+        let greet = "%data _str1, \"!\"\n%func greet(%string s)\n%string t\n%concat t, s, _str1\n%ret t\n%endfunc\n";
+        let program = parse(&format!("{greet}%func main()\n%string s\n%mov s, _str1\n%call s, greet(s)\n%outs s\n%endfunc\n"));
+        assert_eq!(run(&program), "!!");
+        assert_eq!(round_trip(&program), disassemble(&program));
+        assert!(execute_ir_output(&format!("{greet}%func main()\n%string s\n%int n\n%call n, greet(s)\n%endfunc\n")).is_err());
+        assert!(execute_ir_output(&format!("{greet}%func main()\n%string s\n%float x\n%call x, greet(s)\n%endfunc\n")).is_err());
+        assert_eq!(execute_ir_output("%data _str1, \"a\"\n%func f()\n%endfunc\n%func main()\n%string s\n%mov s, _str1\n%call s, f()\n%outs s\n%out 1\n%endfunc\n").unwrap(), "1\n");
+    }
+
+    #[test]
+    fn ir_builtins() {
+        let code = "%func stop(%int code)\n\
+                    %call exit(code)\n\
+                    %out 1\n\
+                    %endfunc\n\
+                    %func main()\n\
+                    %int a\n\
+                    %long b\n\
+                    %call a, abs(-7)\n\
+                    %out a\n\
+                    %call a, min(3, -9)\n\
+                    %out a\n\
+                    %call a, max(3, -9)\n\
+                    %out a\n\
+                    %call b, pow(3, 13)\n\
+                    %out b\n\
+                    %call a, gcd(-84, 36)\n\
+                    %out a\n\
+                    %call a, sqrt_int(99)\n\
+                    %out a\n\
+                    %call a, sqrt_int(100)\n\
+                    %out a\n\
+                    %call assert(a)\n\
+                    %call stop(4)\n\
+                    %out 2\n\
+                    %endfunc\n";
+
+        let program = parse(code);
+        assert_eq!(run(&program), "7\n-9\n3\n1594323\n12\n9\n10\n");
+        assert_eq!(execute_ir_output(code).unwrap(), "7\n-9\n3\n1594323\n12\n9\n10\n");
+
+        let canonical = disassemble(&program);
+        assert!(canonical.contains("%call a, abs(-7)\n"));
+        assert!(canonical.contains("%call assert(a)\n"));
+        assert!(canonical.contains("%call exit(code)\n"));
+        assert_eq!(disassemble(&parse(&canonical)), canonical);
+        assert_eq!(round_trip(&program), canonical);
+
+        // a function of the program with the same name as a builtin replaces it.
+        let max = "%func max(%int a, %int b)\n%ret a\n%endfunc\n";
+        assert_eq!(execute_ir_output(&format!("{max}%func main()\n%int a\n%call a, max(1, 2)\n%out a\n%endfunc\n")).unwrap(), "1\n");
+
+        // builtins are checked like any other call, and stop the program on invalid values.
+        assert!(execute_ir_output("%func main()\n%int a\n%call a, min(1)\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%call abs(1)\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%int a\n%call a, exit(1)\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%string s\n%call assert(s)\n%endfunc\n").is_err());
+        assert!(execute_ir_output("%func main()\n%call assert(0)\n%endfunc\n").unwrap_err().contains("Assertion failed"));
+        assert!(execute_ir_output("%func main()\n%int a\n%call a, pow(2, -1)\n%endfunc\n").unwrap_err().contains("negative exponent"));
+        assert!(execute_ir_output("%func main()\n%int a\n%call a, sqrt_int(-4)\n%endfunc\n").unwrap_err().contains("negative number"));
+    }
+
+}
+
+
+enum IRTok {
+    // func
+    Func,
+    EndFunc,
+
+    // declarations.
+    Int,
+    Long,
+    IntArray,
+    Str,
+    Float,
+    FloatArray,
+    Global,
+    GlobalLong,
+    GlobalArray,
+    GlobalStr,
+    GlobalFloat,
+    GlobalFloatArray,
+    Data,
+
+    // function calling routines.
+    Call,
+    Return,
+
+    // arrays.
+    Bounds,
+    Len,
+
+    // input/output routines.
+    Out,
+    OutChar,
+    OutStr,
+    In,
+
+    // mathematical operators.
+    Mov,
+    Add,
+    Sub,
+    Mult,
+    Div,
+    Mod,
+
+    // bitwise operators.
+    And,
+    Or,
+    Xor,
+    Not,
+    ShiftLeft,
+    ShiftRight,
+
+    // comparison operators.
+    LessThan,
+    LessEqual,
+    NotEqual,
+    Equal,
     GreaterEqual,
     GreaterThan,
 
@@ -1372,6 +4837,28 @@ enum IRTok {
     Jump,
     BranchIf,
     BranchIfNot,
+    Switch,
+
+    // strings.
+    Concat,
+    CharAt,
+    StrCmp,
+    Chr,
+
+    // floats.
+    OutFloat,
+    FloatAdd,
+    FloatSub,
+    FloatMult,
+    FloatDiv,
+    FloatLessThan,
+    FloatLessEqual,
+    FloatNotEqual,
+    FloatEqual,
+    FloatGreaterEqual,
+    FloatGreaterThan,
+    IntToFloat,
+    FloatToInt,
 
     Comma,
     LBrace,
@@ -1382,20 +4869,43 @@ enum IRTok {
 
     EndInstr,
 
-    Num(i32),
+    Num(i64),
+    Decimal(f64),
     Var(String),
+    Text(String),
     End,
 }
 
 #[derive(Clone)]
 enum Op {
-    Num(i32),
+    Num(i64),
+    Var(i32),
+}
+
+// a string operand. either a string variable, or a string constant declared with '%data'.
+#[derive(Clone)]
+enum StrOp {
+    Var(i32),
+    Data(usize),
+}
+
+// a float operand. either a float variable, or a number such as '2.5'.
+#[derive(Clone)]
+enum FloatOp {
     Var(i32),
+    Num(f64),
 }
 
 enum VariableType {
     IntVar(i32),
     ArrayVar(i32, i32),
+    // an array parameter, which refers to the array passed in by the caller.
+    ArrayRef(i32),
+    StringVar(i32),
+    LongVar(i32),
+    FloatVar(i32),
+    FloatArrayVar(i32, i32),
+    FloatArrayRef(i32),
 }
 
 enum MemWrite {
@@ -1405,18 +4915,18 @@ enum MemWrite {
 
 enum MemRead {
     IntVar(i32),
-    Number(i32),
+    Number(i64),
     ArrayRead(i32, Op),
 }
 
 // TODO: array bounds check.
-fn read_memory(variables: &HashMap<i32, i32>, arrays: &HashMap<i32, Vec<i32>>, read: &MemRead) -> Result<i32, IRError> {
+fn read_memory(frame: &Frame, read: &MemRead) -> Result<i64, IRError> {
     match read {
-    MemRead::IntVar(id) => Ok(*variables.get(&id).unwrap()),
+    MemRead::IntVar(id) => Ok(read_integer_value(frame, &Op::Var(*id))),
     MemRead::Number(number) => Ok(*number),
     MemRead::ArrayRead(id, index) => {
-        let array = &arrays[&id];
-        let variable = read_integer_value(&variables, &index);
+        let array = &frame.heap[array_index(frame, id)];
+        let variable = read_integer_value(frame, index);
         if variable >= 0 && (variable as usize) < array.len() {
             Ok(array[variable as usize])
         } else {
@@ -1435,11 +4945,16 @@ enum Bytecode {
 
     // declarations.
     Int(i32),
+    Long(i32),
     IntArray(i32, i32),
+    FloatArray(i32, i32),
 
     // input/output routines.
     Out(Op),
+    OutChar(Op),
+    OutStr(StrOp),
     In(i32),
+    InStr(i32),
 
     // mathematical operators.
     Mov(MemWrite, MemRead),
@@ -1449,8 +4964,18 @@ enum Bytecode {
     Div(i32, Op, Op),
     Mod(i32, Op, Op),
 
-    // calling functions.
-    Call(i32, usize, Vec<Op>),
+    // bitwise operators.
+    And(i32, Op, Op),
+    Or(i32, Op, Op),
+    Xor(i32, Op, Op),
+    Not(i32, Op),
+    Shl(i32, Op, Op),
+    Shr(i32, Op, Op),
+
+    // calling functions. a call stores every value the function returns, and '%ret' returns any number of values.
+    // a call to a builtin, such as '%call m, max(a, b)', is run by the interpreter itself.
+    Call(Vec<i32>, usize, Vec<Op>),
+    Builtin(Vec<i32>, Builtin, Vec<Op>),
 
     // comparison operators.
     LessThan(i32, Op, Op),
@@ -1459,10 +4984,41 @@ enum Bytecode {
     Equal(i32, Op, Op),
     GreaterEqual(i32, Op, Op),
     GreaterThan(i32, Op, Op),
-    Return(Op),
+    Return(Vec<Op>),
+    Bounds(Op, Op),
+    Len(i32, i32),
     Jmp(usize),
     BranchIf(Op, usize),
     BranchIfn(Op, usize),
+    Switch(Op, usize, Vec<usize>),
+
+    // strings.
+    Str(i32),
+    StrMov(i32, StrOp),
+    Concat(i32, StrOp, StrOp),
+    StrLen(i32, StrOp),
+    CharAt(i32, StrOp, Op),
+    StrCmp(i32, StrOp, StrOp),
+    Chr(i32, Op),
+
+    // floats.
+    Float(i32),
+    FloatMov(i32, FloatOp),
+    // an element of a float array, such as '%mov x, [a + i]' and '%mov [a + i], x'.
+    FloatArrayRead(i32, i32, Op),
+    FloatArrayWrite(i32, Op, FloatOp),
+    OutFloat(FloatOp),
+    InFloat(i32),
+    FloatAdd(i32, FloatOp, FloatOp),
+    FloatSub(i32, FloatOp, FloatOp),
+    FloatMult(i32, FloatOp, FloatOp),
+    FloatDiv(i32, FloatOp, FloatOp),
+    FloatLessThan(i32, FloatOp, FloatOp),
+    FloatLessEqual(i32, FloatOp, FloatOp),
+    FloatNotEqual(i32, FloatOp, FloatOp),
+    FloatEqual(i32, FloatOp, FloatOp),
+    FloatGreaterEqual(i32, FloatOp, FloatOp),
+    FloatGreaterThan(i32, FloatOp, FloatOp),
+    IntToFloat(i32, Op),
+    FloatToInt(i32, FloatOp),
 }
-
-
@@ -1,5 +1,9 @@
 use std::env;
 use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::collections::HashMap;
+use std::collections::HashSet;
 mod interpreter;
 
 fn main() {
@@ -10,27 +14,42 @@ fn main() {
         return;
     }
 
-    if args.len() > 2 {
-        println!("Too many commandline arguments.");
+    // 'cargo run -- --disassemble file.tt' prints the IR as the interpreter
+    // understood it instead of running the program.
+    // 'cargo run -- --compile file.tt' saves the program as bytecode in 'file.ttb',
+    // and 'cargo run file.ttb' runs the saved bytecode directly.
+    let mode = args[1].as_str();
+    let expected_args = match mode {
+    "--disassemble" | "--compile" => 3,
+    _ => 2,
+    };
+    if args.len() < expected_args {
+        println!("Please provide an input file.");
         return;
     }
 
-    // read the entire file.
-    let filename = &args[1];
-    let result = fs::read_to_string(filename);
-    let code = match result {
-    Err(error) => {
-        println!("**Error. File \"{}\": {}", filename, error);
+    if args.len() > expected_args {
+        println!("Too many commandline arguments.");
         return;
     }
 
-    Ok(code) => {
-      code
-    } 
+    let filename = &args[expected_args - 1];
+    if filename.ends_with(".ttb") {
+        if mode == "--compile" {
+            println!("**Error. File \"{}\" is already compiled bytecode.", filename);
+            return;
+        }
 
-    };
+        match fs::read(filename) {
+        Ok(bytes) if mode == "--disassemble" => interpreter::disassemble_bytecode_file(&bytes),
+        Ok(bytes) => interpreter::execute_bytecode_file(&bytes),
+        Err(error) => println!("**Error. File \"{}\": {}", filename, error),
+        }
+        return;
+    }
 
-    let tokens = match lex(&code) {
+    // read the file and every file it imports.
+    let source = match load_program(filename) {
     Err(error_message) => {
         println!("**Error**");
         println!("----------------------");
@@ -39,14 +58,34 @@ fn main() {
         return;
     }
 
-    Ok(tokens) => tokens,
+    Ok(source) => source,
     
     };
 
     let mut index: usize = 0;
-    match parse_program(&tokens, &mut index) {
+    match parse_program_with_warnings(&source.tokens, &mut index) {
+
+    Ok((code, warnings)) => {
+        for warning in &warnings {
+            println!("Warning: {warning}");
+        }
+
+        if mode == "--disassemble" {
+            interpreter::disassemble_ir(&code);
+            return;
+        }
+
+        if mode == "--compile" {
+            if let Some(bytes) = interpreter::compile_ir(&code) {
+                let output = Path::new(filename).with_extension("ttb");
+                match fs::write(&output, bytes) {
+                Ok(_) => println!("Bytecode written to \"{}\".", output.display()),
+                Err(error) => println!("**Error. File \"{}\": {}", output.display(), error),
+                }
+            }
+            return;
+        }
 
-    Ok(code) => {
         println!("Program Parsed Successfully.");
         println!("--------------------------------------------");
         println!("{code}");
@@ -57,14 +96,106 @@ fn main() {
     Err(message) => {
         println!("**Error**");
         println!("----------------------");
-        if tokens.len() == 0 {
-            println!("No code has been provided.");
-        } else {
-            println!("Error: {message}");
-            println!("----------------------");
+        println!("Error in \"{}\": {message}", source_file(&source, index));
+        println!("----------------------");
+    }
+
+    }
+}
+
+// the tokens of a program and the files they come from. 'files' holds the index of the first token
+// of every file, so that an error can name the file it comes from.
+struct Source {
+    tokens: Vec<Token>,
+    files: Vec<(usize, String)>,
+}
+
+// reads a file and every file it imports, such as 'import "math.tt";'. the tokens of an imported file
+// come before the tokens of the file importing it, and a file imported more than once is only read once.
+fn load_program(filename: &str) -> Result<Source, String> {
+    let mut source = Source {
+        tokens: vec![],
+        files: vec![],
+    };
+    let mut functions: HashMap<String, String> = HashMap::new();
+    load_file(Path::new(filename), &mut vec![], &mut HashSet::new(), &mut functions, &mut source)?;
+    source.tokens.push(Token::End);
+    Ok(source)
+}
+
+// 'importing' is the chain of files being imported, so that a file importing itself is found, and
+// 'functions' is the file declaring every function read so far.
+fn load_file(path: &Path, importing: &mut Vec<(PathBuf, String)>, loaded: &mut HashSet<PathBuf>, functions: &mut HashMap<String, String>, source: &mut Source) -> Result<(), String> {
+    // a file that cannot be read is reported by the file importing it.
+    let name = path.display().to_string();
+    let cannot_read = |error: std::io::Error| match importing.last() {
+    Some((_, importer)) => format!("Error in \"{importer}\": Cannot import \"{name}\": {error}"),
+    None => format!("Error in \"{name}\": {error}"),
+    };
+    let canonical = match fs::canonicalize(path) {
+    Ok(canonical) => canonical,
+    Err(error) => {return Err(cannot_read(error));}
+    };
+    if let Some(position) = importing.iter().position(|(file, _)| *file == canonical) {
+        let mut cycle: Vec<String> = importing[position..].iter().map(|(_, name)| format!("\"{name}\"")).collect();
+        cycle.push(format!("\"{name}\""));
+        return Err(format!("Error in \"{}\": The imports form a cycle: {}", importing[importing.len() - 1].1, cycle.join(" imports ")));
+    }
+    if loaded.contains(&canonical) {
+        return Ok(());
+    }
+
+    let code = match fs::read_to_string(path) {
+    Ok(code) => code,
+    Err(error) => {return Err(cannot_read(error));}
+    };
+    let mut tokens = match lex(&code) {
+    Ok(tokens) => tokens,
+    Err(error) => {return Err(format!("Error in \"{name}\": {error}"));}
+    };
+    tokens.pop();
+
+    // imports come first, and are found next to the file importing them.
+    let mut index = 0;
+    importing.push((canonical.clone(), name.clone()));
+    while matches!(tokens.get(index), Some(Token::Import)) {
+        let import = match (tokens.get(index + 1), tokens.get(index + 2)) {
+        (Some(Token::StringLiteral(import)), Some(Token::Semicolon)) => import,
+        _ => {return Err(format!("Error in \"{name}\": Imports must name a file, such as 'import \"math.tt\";'"));}
+        };
+        let directory = path.parent().unwrap_or(Path::new(""));
+        load_file(&directory.join(import), importing, loaded, functions, source)?;
+        index += 3;
+    }
+    importing.pop();
+    loaded.insert(canonical);
+
+    let tokens = tokens.split_off(index);
+    for i in 0..tokens.len() {
+        match (&tokens[i], tokens.get(i + 1)) {
+        (Token::Import, _) => {return Err(format!("Error in \"{name}\": Imports must come before everything else in the file"));}
+        (Token::Func, Some(Token::Ident(function))) => {
+            match functions.get(function) {
+            Some(other) if *other != name => {
+                return Err(format!("Error in \"{name}\": Function '{function}' is already declared in \"{other}\""));
+            }
+            _ => {functions.insert(function.clone(), name.clone());}
+            }
+        }
+        _ => {}
         }
     }
 
+    source.files.push((source.tokens.len(), name));
+    source.tokens.extend(tokens);
+    Ok(())
+}
+
+// the name of the file that the token at 'index' comes from.
+fn source_file(source: &Source, index: usize) -> &str {
+    match source.files.iter().rev().find(|(start, _)| *start <= index) {
+    Some((_, name)) => name,
+    None => source.files.first().map(|(_, name)| name.as_str()).unwrap_or(""),
     }
 }
 
@@ -74,17 +205,43 @@ enum Token {
   Func,
   Return,
   Int,
+  Long,
+  Float,
+  Bool,
+  Char,
+  String,
+  True,
+  False,
+  Const,
   Print,
   While,
+  For,
+  Do,
+  Break,
+  Continue,
   Read,
+  Len,
   If,
+  Else,
+  Switch,
+  Case,
+  Default,
+  Struct,
+  Enum,
+  Import,
 
   LeftParen,
   RightParen,
   LeftCurly,
   RightCurly,
+  LeftBracket,
+  RightBracket,
   Comma,
   Semicolon,
+  Colon,
+  Question,
+  Dot,
+  Arrow,
 
   // mathematical operators.
   Plus,
@@ -94,11 +251,41 @@ enum Token {
   Modulus,
   Assign,
 
+  // compound assignment operators.
+  PlusAssign,
+  SubtractAssign,
+  MultiplyAssign,
+  DivideAssign,
+  ModulusAssign,
+  Increment,
+  Decrement,
+
   // comparison operators.
   Less,
+  LessEqual,
+  Greater,
+  GreaterEqual,
+  Equality,
+  NotEqual,
+
+  // logical operators.
+  LogicalAnd,
+  LogicalOr,
+  Not,
+
+  // bitwise operators.
+  BitAnd,
+  BitOr,
+  BitXor,
+  BitNot,
+  ShiftLeft,
+  ShiftRight,
 
   Ident(String),
-  Num(i32),
+  Num(i64),
+  FloatLiteral(f64),
+  CharLiteral(u32),
+  StringLiteral(String),
 
   End,
 }
@@ -106,6 +293,93 @@ enum Token {
 struct Expression {
   code: String,
   name: String,
+  ty: Type,
+}
+
+// the type of a value. bools are stored as 0 or 1 in an '%int', and the type checker
+// makes sure that only bools are used as conditions, so a branch is always on 0 or 1.
+// longs are 64 bit integers, stored in a '%long', and floats are 64 bit floating point numbers,
+// stored in a '%float'. structs and enums are the position of their declaration in 'codegen.structs'
+// or 'codegen.enums', and 'type_name' gives their name, such as 'Point' or 'Color'.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+  Int,
+  Long,
+  Float,
+  Bool,
+  Char,
+  Str,
+  Struct(usize),
+  Enum(usize),
+}
+
+fn type_name(codegen: &CodeGenerator, ty: Type) -> &str {
+  match ty {
+  Type::Int => "int",
+  Type::Long => "long",
+  Type::Float => "float",
+  Type::Bool => "bool",
+  Type::Char => "char",
+  Type::Str => "string",
+  Type::Struct(index) => &codegen.structs[index].name,
+  Type::Enum(index) => &codegen.enums[index].name,
+  }
+}
+
+// how a variable of the type is declared in the IR. bools, chars and enums are stored in integers.
+// a struct is never declared as a whole, only its fields are.
+fn ir_declaration(ty: Type) -> &'static str {
+  match ty {
+  Type::Str => "%string",
+  Type::Long => "%long",
+  Type::Float => "%float",
+  Type::Struct(_) => "",
+  _ => "%int",
+  }
+}
+
+// how an array of the type is declared in the IR. floats have their own arrays, and every other
+// type is stored in an int.
+fn array_declaration(ty: Type) -> &'static str {
+  match ty {
+  Type::Float => "%float[]",
+  _ => "%int[]",
+  }
+}
+
+// how a global variable of the type is declared in the IR, such as '%global_long' for a long.
+fn global_declaration(ty: Type) -> &'static str {
+  match ty {
+  Type::Str => "%global_string",
+  Type::Long => "%global_long",
+  Type::Float => "%global_float",
+  Type::Struct(_) => "",
+  _ => "%global",
+  }
+}
+
+// the type named by a keyword such as 'int', for declarations and parameters.
+fn type_token(token: &Token) -> Option<Type> {
+  match token {
+  Token::Int => Some(Type::Int),
+  Token::Long => Some(Type::Long),
+  Token::Float => Some(Type::Float),
+  Token::Bool => Some(Type::Bool),
+  Token::Char => Some(Type::Char),
+  Token::String => Some(Type::Str),
+  _ => None,
+  }
+}
+
+// the type named by a keyword such as 'int', or by the name of a struct or enum such as 'Point'.
+fn find_type(codegen: &CodeGenerator, token: &Token) -> Option<Type> {
+  match token {
+  Token::Ident(ident) => match codegen.structs.iter().position(|declaration| declaration.name == *ident) {
+    Some(index) => Some(Type::Struct(index)),
+    None => codegen.enums.iter().position(|declaration| declaration.name == *ident).map(Type::Enum),
+  },
+  _ => type_token(token),
+  }
 }
 
 // This is a lexer that parses numbers/identifiers and math operations
@@ -130,9 +404,41 @@ fn lex(code: &str) -> Result<Vec<Token>, String> {
           break;
         }
       }
+
+      // a number with a fraction or an exponent is a float, such as '2.5' or '1e-3'.
+      let mut is_float = false;
+      if i + 1 < bytes.len() && bytes[i] == b'.' && bytes[i + 1].is_ascii_digit() {
+        is_float = true;
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+          i += 1;
+        }
+      }
+      if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+        let sign = if i + 1 < bytes.len() && (bytes[i + 1] == b'-' || bytes[i + 1] == b'+') { 1 } else { 0 };
+        if i + 1 + sign < bytes.len() && bytes[i + 1 + sign].is_ascii_digit() {
+          is_float = true;
+          i += 1 + sign;
+          while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+          }
+        }
+      }
+
       let end = i;
       let string_token = &code[start..end];
-      let number_value = string_token.parse::<i32>().unwrap();
+      if is_float {
+        match string_token.parse::<f64>() {
+        Ok(number) if number.is_finite() => tokens.push(Token::FloatLiteral(number)),
+        _ => return Err(format!("Number '{}' is too large", string_token)),
+        }
+        continue;
+      }
+      // numbers that do not fit in an int are longs, such as 3000000000.
+      let number_value = match string_token.parse::<i64>() {
+      Ok(number) => number,
+      _ => return Err(format!("Number '{}' is too large", string_token)),
+      };
       let token = Token::Num(number_value);
       tokens.push(token);
     }
@@ -142,7 +448,7 @@ fn lex(code: &str) -> Result<Vec<Token>, String> {
       i += 1;
       while i < bytes.len() {
         let letter = bytes[i] as char;
-        if (letter >= 'a' && letter <= 'z') || (letter >= 'A' && letter <= 'Z') || (letter >= '0' && letter <= '9') {
+        if (letter >= 'a' && letter <= 'z') || (letter >= 'A' && letter <= 'Z') || (letter >= '0' && letter <= '9') || letter == '_' {
           i += 1;
         } else {
           break;
@@ -155,38 +461,138 @@ fn lex(code: &str) -> Result<Vec<Token>, String> {
     }
 
     '<' => {
-      tokens.push(Token::Less);
+      i += 1;
+      if i < bytes.len() && bytes[i] as char == '<' {
+        tokens.push(Token::ShiftLeft);
+        i += 1;
+      } else if i < bytes.len() && bytes[i] as char == '=' {
+        tokens.push(Token::LessEqual);
+        i += 1;
+      } else {
+        tokens.push(Token::Less);
+      }
+    }
+
+    '>' => {
+      i += 1;
+      if i < bytes.len() && bytes[i] as char == '>' {
+        tokens.push(Token::ShiftRight);
+        i += 1;
+      } else if i < bytes.len() && bytes[i] as char == '=' {
+        tokens.push(Token::GreaterEqual);
+        i += 1;
+      } else {
+        tokens.push(Token::Greater);
+      }
+    }
+
+    '!' => {
+      i += 1;
+      if i < bytes.len() && bytes[i] as char == '=' {
+        tokens.push(Token::NotEqual);
+        i += 1;
+      } else {
+        tokens.push(Token::Not);
+      }
+    }
+
+    '&' => {
+      i += 1;
+      if i < bytes.len() && bytes[i] as char == '&' {
+        tokens.push(Token::LogicalAnd);
+        i += 1;
+      } else {
+        tokens.push(Token::BitAnd);
+      }
+    }
+
+    '|' => {
+      i += 1;
+      if i < bytes.len() && bytes[i] as char == '|' {
+        tokens.push(Token::LogicalOr);
+        i += 1;
+      } else {
+        tokens.push(Token::BitOr);
+      }
+    }
+
+    '^' => {
+      tokens.push(Token::BitXor);
+      i += 1;
+    }
+
+    '~' => {
+      tokens.push(Token::BitNot);
       i += 1;
     }
 
     '+' => {
-      tokens.push(Token::Plus);
       i += 1;
+      if i < bytes.len() && bytes[i] as char == '+' {
+        tokens.push(Token::Increment);
+        i += 1;
+      } else if i < bytes.len() && bytes[i] as char == '=' {
+        tokens.push(Token::PlusAssign);
+        i += 1;
+      } else {
+        tokens.push(Token::Plus);
+      }
     }
 
     '-' => {
-      tokens.push(Token::Subtract);
       i += 1;
+      if i < bytes.len() && bytes[i] as char == '-' {
+        tokens.push(Token::Decrement);
+        i += 1;
+      } else if i < bytes.len() && bytes[i] as char == '=' {
+        tokens.push(Token::SubtractAssign);
+        i += 1;
+      } else if i < bytes.len() && bytes[i] as char == '>' {
+        tokens.push(Token::Arrow);
+        i += 1;
+      } else {
+        tokens.push(Token::Subtract);
+      }
     }
 
     '*' => {
-      tokens.push(Token::Multiply);
       i += 1;
+      if i < bytes.len() && bytes[i] as char == '=' {
+        tokens.push(Token::MultiplyAssign);
+        i += 1;
+      } else {
+        tokens.push(Token::Multiply);
+      }
     }
 
     '/' => {
-      tokens.push(Token::Divide);
       i += 1;
+      if i < bytes.len() && bytes[i] as char == '=' {
+        tokens.push(Token::DivideAssign);
+        i += 1;
+      } else {
+        tokens.push(Token::Divide);
+      }
     }
 
     '%' => {
-      tokens.push(Token::Modulus);
       i += 1;
+      if i < bytes.len() && bytes[i] as char == '=' {
+        tokens.push(Token::ModulusAssign);
+        i += 1;
+      } else {
+        tokens.push(Token::Modulus);
+      }
     }
 
     '=' => {
-      tokens.push(Token::Assign);
       i += 1;
+      if i < bytes.len() && bytes[i] as char == '=' {
+        tokens.push(Token::Equality);
+        i += 1;
+      } else {
+        tokens.push(Token::Assign);
+      }
     }
 
     ';' => {
@@ -214,11 +620,61 @@ fn lex(code: &str) -> Result<Vec<Token>, String> {
       i += 1;
     }
 
+    '[' => {
+      tokens.push(Token::LeftBracket);
+      i += 1;
+    }
+
+    ']' => {
+      tokens.push(Token::RightBracket);
+      i += 1;
+    }
+
     ',' => {
       tokens.push(Token::Comma);
       i += 1;
     }
 
+    ':' => {
+      tokens.push(Token::Colon);
+      i += 1;
+    }
+
+    '?' => {
+      tokens.push(Token::Question);
+      i += 1;
+    }
+
+    '.' => {
+      tokens.push(Token::Dot);
+      i += 1;
+    }
+
+    // a character such as 'a' or '\n'. characters are stored as their unicode value.
+    '\'' => {
+      i += 1;
+      let (value, len) = lex_character(&code[i..], '\'')?;
+      i += len;
+      if i >= bytes.len() || bytes[i] as char != '\'' {
+        return Err(String::from("Character literals must be a single character, such as 'a'"));
+      }
+      i += 1;
+      tokens.push(Token::CharLiteral(value as u32));
+    }
+
+    // a string such as "hello\n".
+    '"' => {
+      i += 1;
+      let mut value = String::new();
+      while i >= bytes.len() || bytes[i] as char != '"' {
+        let (c, len) = lex_character(&code[i..], '"')?;
+        value.push(c);
+        i += len;
+      }
+      i += 1;
+      tokens.push(Token::StringLiteral(value));
+    }
+
     '#' => {
       i += 1;
       while i < bytes.len() {
@@ -251,476 +707,3151 @@ fn create_identifier(code: &str) -> Token {
   "func" => Token::Func,
   "return" => Token::Return,
   "int" => Token::Int,
+  "long" => Token::Long,
+  "float" => Token::Float,
+  "bool" => Token::Bool,
+  "char" => Token::Char,
+  "string" => Token::String,
+  "true" => Token::True,
+  "false" => Token::False,
+  "const" => Token::Const,
   "print" => Token::Print,
   "read" => Token::Read,
+  "len" => Token::Len,
   "while" => Token::While,
+  "for" => Token::For,
+  "do" => Token::Do,
+  "break" => Token::Break,
+  "continue" => Token::Continue,
   "if" => Token::If,
+  "else" => Token::Else,
+  "switch" => Token::Switch,
+  "case" => Token::Case,
+  "default" => Token::Default,
+  "struct" => Token::Struct,
+  "enum" => Token::Enum,
+  "import" => Token::Import,
   _ => Token::Ident(String::from(code)),
   }
 }
 
+// reads one character of a character or string literal, and returns the character and how many
+// bytes it takes up. the escapes are \n, \t, \r, \0, \\, \' and \".
+fn lex_character(code: &str, quote: char) -> Result<(char, usize), String> {
+  let mut chars = code.chars();
+  match chars.next() {
+  None | Some('\n') => Err(format!("{quote} is missing its closing {quote}")),
+  Some(c) if c == quote => Err(String::from("Character literals cannot be empty")),
+  Some('\\') => {
+    let c = match chars.next() {
+    Some('n') => '\n',
+    Some('t') => '\t',
+    Some('r') => '\r',
+    Some('0') => '\0',
+    Some('\\') => '\\',
+    Some('\'') => '\'',
+    Some('"') => '"',
+    Some(c) => { return Err(format!("Unknown escape '\\{c}'")); }
+    None => { return Err(format!("{quote} is missing its closing {quote}")); }
+    };
+    Ok((c, 2))
+  }
+  Some(c) => Ok((c, c.len_utf8())),
+  }
+}
+
 // parse programs with multiple functions
-// loop over everything, outputting generated code.
+// loop over everything, outputting generated code. used to test the compiler.
+#[cfg(test)]
 fn parse_program(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+    let (code, _) = parse_program_with_warnings(tokens, index)?;
+    return Ok(code);
+}
+
+// loop over everything, outputting generated code and the warnings found while generating it.
+fn parse_program_with_warnings(tokens: &Vec<Token>, index: &mut usize) -> Result<(String, Vec<String>), String> {
     assert!(tokens.len() >= 1 && matches!(tokens[tokens.len() - 1], Token::End));
 
+    let mut codegen = CodeGenerator::new();
+    collect_functions(tokens, index, &mut codegen)?;
+
     let mut code = String::new();
     while !at_end(tokens, *index) {
-      match parse_function(tokens, index) {
+      if find_type(&codegen, &tokens[*index]).is_some() {
+        code += &parse_global_declaration(tokens, index, &mut codegen)?;
+        continue;
+      }
+
+      // structs and enums were already declared by 'collect_functions'.
+      if matches!(tokens[*index], Token::Struct | Token::Enum) {
+        while !matches!(tokens[*index], Token::RightCurly) {
+          *index += 1;
+        }
+        *index += 1;
+        continue;
+      }
+
+      if matches!(tokens[*index], Token::Const) {
+        let (ident, value) = parse_constant_declaration(tokens, index, &mut codegen)?;
+        codegen.global_constants.insert(ident, value);
+        continue;
+      }
+
+      match parse_function(tokens, index, &mut codegen) {
       Ok(function_code) => {
         code += &function_code;
       }
       Err(e) => { return Err(e); }
       }
     }
-    return Ok(code);
+
+    // the string constants go before the functions that print them.
+    let mut data = String::new();
+    for (i, text) in codegen.strings.iter().enumerate() {
+      data += &format!("%data _str{}, \"{}\"\n", i + 1, escape_string(text));
+    }
+    Ok((data + &code, codegen.warnings))
 }
 
-fn at_end(tokens: &Vec<Token>, index: usize) -> bool {
-  match tokens[index] {
-  Token::End => { true }
-  _ => { false }
-  }
+// writes a string the way it is written in the IR, with escapes for quotes, backslashes
+// and control characters.
+fn escape_string(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+        '\n' => escaped += "\\n",
+        '\t' => escaped += "\\t",
+        '\r' => escaped += "\\r",
+        '\0' => escaped += "\\0",
+        '\\' => escaped += "\\\\",
+        '"' => escaped += "\\\"",
+        _ => escaped.push(c),
+        }
+    }
+    escaped
 }
 
-static mut VAR_NUM: i64 = 0;
+fn is_string_constant(codegen: &CodeGenerator, name: &str) -> bool {
+    codegen.string_names.values().any(|constant| constant == name)
+}
 
-fn create_temp() -> String {
-    unsafe {
-        VAR_NUM += 1;
-        format!("_temp{}", VAR_NUM)
+// the name of the '%data' constant holding 'text'.
+fn string_constant(codegen: &mut CodeGenerator, text: &str) -> String {
+    if let Some(name) = codegen.string_names.get(text) {
+        return name.clone();
     }
+    codegen.strings.push(String::from(text));
+    let name = format!("_str{}", codegen.strings.len());
+    codegen.string_names.insert(String::from(text), name.clone());
+    name
 }
 
+// collects the structs, the enums and the parameters of every function before any code is generated,
+// so that functions, structs and enums can be used before they are declared. 'index' is left where
+// it started, unless there is an error.
+fn collect_functions(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<(), String> {
+    let start = *index;
+    while !at_end(tokens, *index) {
+        match tokens[*index] {
+        Token::Struct => parse_struct_definition(tokens, index, codegen)?,
+        Token::Enum => parse_enum_definition(tokens, index, codegen)?,
+        _ => {*index += 1;}
+        }
+    }
 
-// parse function such as:
-// func main(int a, int b) {
-//    # ... statements here...
-//    # ...
-// }
-// a loop is done to handle statements.
+    *index = start;
+    while !at_end(tokens, *index) {
+        if matches!(tokens[*index], Token::Struct | Token::Enum) {
+            while !matches!(tokens[*index], Token::RightCurly) {
+                *index += 1;
+            }
+            *index += 1;
+            continue;
+        }
 
-fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
-    
-    match tokens[*index] {
-    Token::Func => { *index += 1; }
-    _ => { return Err(String::from("functions must begin with func")); }
+        if !matches!(tokens[*index], Token::Func) {
+            // global declarations and constants end with a semicolon.
+            while !matches!(tokens[*index], Token::Semicolon | Token::End) {
+                *index += 1;
+            }
+            if matches!(tokens[*index], Token::Semicolon) {
+                *index += 1;
+            }
+            continue;
+        }
+
+        *index += 1;
+        let ident = match &tokens[*index] {
+        Token::Ident(ident) => ident.clone(),
+        _  => { return Err(String::from("functions must have a function identifier"));}
+        };
+        *index += 1;
+
+        let parameters = parse_parameters(tokens, index, codegen)?;
+        let returns = parse_return_types(tokens, index, codegen)?;
+        if BUILTINS.iter().any(|(name, _, _)| *name == ident) {
+            return Err(format!("'{ident}' is a builtin function, so a function with the same name cannot be declared. Rename it"));
+        }
+        if codegen.functions.contains_key(&ident) {
+            return Err(format!("Function '{ident}' is declared more than once"));
+        }
+        codegen.functions.insert(ident, Function { parameters, returns });
+
+        // skip over the body of the function.
+        let mut depth = 0;
+        while !matches!(tokens[*index], Token::End) {
+            match tokens[*index] {
+            Token::LeftCurly => { depth += 1; }
+            Token::RightCurly => { depth -= 1; }
+            _ => {}
+            }
+            *index += 1;
+            if depth == 0 {
+                break;
+            }
+        }
     }
+    *index = start;
+    Ok(())
+}
 
-    let mut function_code: String;
+// parses a struct such as 'struct Point { int x; int y; }'. the fields can be ints, longs, floats, bools,
+// chars or strings, but not arrays or other structs.
+fn parse_struct_definition(tokens: &[Token], index: &mut usize, codegen: &mut CodeGenerator) -> Result<(), String> {
+    match tokens[*index] {
+    Token::Struct => {*index += 1;}
+    _ => {return Err(String::from("Structs must begin with the 'struct' keyword"));}
+    }
 
-    match &tokens[*index] {
+    let ident = match &tokens[*index] {
     Token::Ident(ident) => {
         *index += 1;
-        function_code = format!("%func {ident}\n");
+        ident.clone()
     }
-    _  => { return Err(String::from("functions must have a function identifier"));}
+    _ => {return Err(String::from("Structs must have a name, such as 'struct Point { int x; int y; }'"));}
+    };
+    if is_type_name(codegen, &ident) {
+        return Err(format!("Struct '{ident}' is declared more than once"));
+    }
+
+    match tokens[*index] {
+    Token::LeftCurly => {*index += 1;}
+    _ => {return Err(format!("expected '{{' after 'struct {ident}'"));}
     }
 
+    let mut fields: Vec<Variable> = vec![];
+    while !matches!(tokens[*index], Token::RightCurly | Token::End) {
+        let ty = match type_token(&tokens[*index]) {
+        Some(ty) => ty,
+        None => {return Err(format!("The fields of struct '{ident}' must be ints, longs, floats, bools, chars or strings"));}
+        };
+        *index += 1;
+
+        let field = match &tokens[*index] {
+        Token::Ident(field) => {
+            *index += 1;
+            field.clone()
+        }
+        _ => {return Err(format!("The fields of struct '{ident}' must have a name, such as 'int x;'"));}
+        };
+        if fields.iter().any(|other| other.name == field) {
+            return Err(format!("Field '{field}' is declared more than once in struct '{ident}'"));
+        }
+
+        match tokens[*index] {
+        Token::Semicolon => {*index += 1;}
+        _ => {return Err(String::from("Fields must end with a semicolon"));}
+        }
+        fields.push(Variable {
+            name: field,
+            ty,
+            dimensions: vec![],
+        });
+    }
 
     match tokens[*index] {
-    Token::LeftParen => { *index += 1; }
-    _ => { return Err(String::from("expected '('"));}
+    Token::RightCurly => {*index += 1;}
+    _ => {return Err(format!("expected '}}' at the end of struct '{ident}'"));}
     }
+    if fields.is_empty() {
+        return Err(format!("Struct '{ident}' must have at least one field"));
+    }
+
+    codegen.structs.push(Struct {
+        name: ident,
+        fields,
+    });
+    Ok(())
+}
 
+// parses an enum such as 'enum Color { Red, Green, Blue }'. the variants are named constants, numbered
+// from zero in the order they are declared, and can only be compared with variants of the same enum.
+fn parse_enum_definition(tokens: &[Token], index: &mut usize, codegen: &mut CodeGenerator) -> Result<(), String> {
     match tokens[*index] {
-    Token::RightParen => { *index += 1; }
-    _ => { return Err(String::from("expected ')'"));}
+    Token::Enum => {*index += 1;}
+    _ => {return Err(String::from("Enums must begin with the 'enum' keyword"));}
+    }
+
+    let ident = match &tokens[*index] {
+    Token::Ident(ident) => {
+        *index += 1;
+        ident.clone()
+    }
+    _ => {return Err(String::from("Enums must have a name, such as 'enum Color { Red, Green, Blue }'"));}
+    };
+    if is_type_name(codegen, &ident) {
+        return Err(format!("Enum '{ident}' is declared more than once"));
     }
 
     match tokens[*index] {
-    Token::LeftCurly => { *index += 1; }
-    _ => { return Err(String::from("expected '{'"));}
+    Token::LeftCurly => {*index += 1;}
+    _ => {return Err(format!("expected '{{' after 'enum {ident}'"));}
     }
 
-    while !matches!(tokens[*index], Token::RightCurly) {
-        match parse_statement(tokens, index) {
-        Ok(statement_code) => {
-            function_code += &statement_code;
+    let mut variants: Vec<String> = vec![];
+    while !matches!(tokens[*index], Token::RightCurly | Token::End) {
+        if !variants.is_empty() {
+            match tokens[*index] {
+            Token::Comma => {*index += 1;}
+            _ => {return Err(format!("expected ',' in between the variants of enum '{ident}'"));}
+            }
         }
-        Err(e) => {return Err(e);}
+
+        let variant = match &tokens[*index] {
+        Token::Ident(variant) => {
+            *index += 1;
+            variant.clone()
         }
-    }
+        _ => {return Err(format!("The variants of enum '{ident}' must be names, such as 'Red'"));}
+        };
 
+        // the variants share the table of global constants, so they cannot reuse the name of a constant.
+        if codegen.global_constants.contains_key(&variant) {
+            return Err(format!("'{variant}' is declared more than once"));
+        }
+        codegen.global_constants.insert(variant.clone(), variants.len() as i32);
+        variants.push(variant);
+    }
 
     match tokens[*index] {
-    Token::RightCurly => { *index += 1; }
-    _ => { return Err(String::from("expected '}'"));}
+    Token::RightCurly => {*index += 1;}
+    _ => {return Err(format!("expected '}}' at the end of enum '{ident}'"));}
+    }
+    if variants.is_empty() {
+        return Err(format!("Enum '{ident}' must have at least one variant"));
     }
 
-    function_code += "%endfunc\n";
-    return Ok(function_code);
+    codegen.enums.push(Enum {
+        name: ident,
+        variants,
+    });
+    Ok(())
 }
 
-// parsing a statement such as:
-// int a;
-// a = a + b;
-// a = a % b;
-// print(a)
-// read(a)
-// returns epsilon if '}'
-fn parse_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
-    match tokens[*index] {
-    Token::Int => parse_declaration_statement(tokens, index),
-    Token::Ident(_) => parse_assignment_statement(tokens, index),
-    Token::Return => parse_return_statement(tokens, index),
-    Token::Print => parse_print_statement(tokens, index),
-    Token::Read => parse_read_statement(tokens, index),
-    Token::While => parse_while_loop(tokens, index),
-    Token::If => parse_if_statement(tokens, index),
-    _ => Err(String::from("invalid statement"))
-    }
+// whether a name is already used by a struct or an enum.
+fn is_type_name(codegen: &CodeGenerator, ident: &str) -> bool {
+    codegen.structs.iter().any(|declaration| declaration.name == ident) || codegen.enums.iter().any(|declaration| declaration.name == ident)
 }
 
-fn parse_while_loop(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
-
-    match tokens[*index] {
-    Token::While => {*index += 1;}
-    _ => {return Err(String::from("While statements must being with 'while' keyword"));}
+// parses a global declaration such as 'int g;' or 'int [10] table;'.
+// globals are shared by every function, and start out as zero.
+fn parse_global_declaration(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    let mut i = *index;
+    while !matches!(tokens[i], Token::Semicolon | Token::End) {
+        if matches!(tokens[i], Token::Assign) {
+            return Err(String::from("Global variables cannot be initialized. Assign to them inside of a function instead"));
+        }
+        i += 1;
     }
 
-    let boolean_expression = parse_boolean(tokens, index)?;
+    // the declaration is parsed like a local one to declare the variable, but the globals are
+    // declared from its type instead of from the IR of a local declaration.
+    codegen.scopes.push(Scope::new());
+    parse_declaration(tokens, index, codegen)?;
+    let scope = codegen.scopes.pop().unwrap();
+    codegen.ir_names.clear();
 
     match tokens[*index] {
-    Token::LeftCurly => { *index += 1; }
-    _ => { return Err(String::from("expected '{'"));}
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statements must end with a semicolon"));}
     }
 
-    let mut while_loop_body = String::from("");
-    while !matches!(tokens[*index], Token::RightCurly) {
-        match parse_statement(tokens, index) {
-        Ok(statement_code) => {
-            while_loop_body += &statement_code;
+    let mut code = String::new();
+    for (ident, variable) in scope.variables {
+        if codegen.global_variables.contains_key(&ident) {
+            return Err(format!("Global variable '{ident}' is declared more than once"));
         }
-        Err(e) => {return Err(e);}
+
+        // a struct declares one global for every field.
+        let globals = match variable.ty {
+        Type::Struct(_) => struct_fields(codegen, &variable.name, variable.ty),
+        _ => vec![(variable.name.clone(), variable.ty)],
+        };
+        let size: i32 = variable.dimensions.iter().product();
+        for (name, ty) in globals {
+            if variable.dimensions.is_empty() {
+                code += &format!("{} {name}\n", global_declaration(ty));
+            } else if ty == Type::Float {
+                code += &format!("%global_float[] {name}, {size}\n");
+            } else {
+                code += &format!("%global[] {name}, {size}\n");
+            }
         }
+        codegen.global_variables.insert(ident, variable);
     }
+    Ok(code)
+}
+
+fn at_end(tokens: &Vec<Token>, index: usize) -> bool {
+  match tokens[index] {
+  Token::End => { true }
+  _ => { false }
+  }
+}
 
+static mut VAR_NUM: i64 = 0;
 
-    match tokens[*index] {
-    Token::RightCurly => { *index += 1; }
-    _ => { return Err(String::from("expected '}'"));}
+fn create_temp() -> String {
+    unsafe {
+        VAR_NUM += 1;
+        format!("_temp{}", VAR_NUM)
     }
+}
 
-    // todo: this example does not handle nested loops. 
-    // this is a relatively 'simple' demonstration of how to do simple loops.
-    let mut loop_code = String::from("");
-    loop_code += ":loop_begin\n";
-    loop_code += &boolean_expression.code;
-    loop_code += &format!("%branch_ifn {}, :endloop1\n", boolean_expression.name);
-    loop_code += &while_loop_body;
-    loop_code += "%jmp :loop_begin\n";
-    loop_code += ":endloop1\n";
+static mut LABEL_NUM: i64 = 0;
 
-    return Ok(loop_code);
+// every loop and if statement gets its own number to make its labels
+// unique, such as ':loopbegin1' and ':endloop1'.
+fn create_label_num() -> i64 {
+    unsafe {
+        LABEL_NUM += 1;
+        LABEL_NUM
+    }
 }
 
-fn parse_if_statement(_tokens: &Vec<Token>, _index: &mut usize) -> Result<String, String> {
-    todo!()
+// state kept by the code generator while it parses the program.
+struct CodeGenerator {
+    // the loops surrounding the current statement, innermost loop last.
+    loops: Vec<Loop>,
+
+    // named constants and variables declared outside of any function.
+    global_constants: HashMap<String, i32>,
+    global_variables: HashMap<String, Variable>,
+
+    // every function, struct and enum in the program, and the function being parsed.
+    functions: HashMap<String, Function>,
+    function: String,
+    structs: Vec<Struct>,
+    enums: Vec<Enum>,
+
+    // the string literals of the program, emitted as '%data' constants. the same
+    // text is only stored once.
+    strings: Vec<String>,
+    string_names: HashMap<String, String>,
+
+    // the blocks surrounding the current statement, innermost block last. the first
+    // scope of a function holds its parameters.
+    scopes: Vec<Scope>,
+
+    // every variable name used in the IR of the current function, and the integers
+    // whose block has ended. a new integer reuses one of those before declaring another.
+    ir_names: HashSet<String>,
+    free_slots: Vec<String>,
+
+    // problems that do not stop the program from compiling, such as a switch that misses a variant.
+    warnings: Vec<String>,
 }
 
-fn parse_boolean(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
-    let expr1 = parse_expression(tokens, index)?;
-    let opcode: &str;
-    match tokens[*index] {
-    Token::Less => {
-        opcode = "%lt";
-        *index += 1;
-    }
-    _ => {
-        return Err(String::from("Invalid boolean expression. Must have a '<', '<=', '>', or any other comparsion operator."));
-    }
-    }
-    let expr2 = parse_expression(tokens, index)?;
+// the parameters of a function and the types of the values it returns, so that calls can be checked.
+struct Function {
+    parameters: Vec<Variable>,
+    returns: Vec<Type>,
+}
 
-    let dest = create_temp();
-    let src1 = expr1.name;
-    let src2 = expr2.name;
-    let mut statement = String::from("");
-    statement += &expr1.code;
-    statement += &expr2.code;
-    statement += &format!("%int {dest}\n");
-    statement += &format!("{opcode} {dest}, {src1}, {src2}\n");
+// the functions that the interpreter runs natively, with the types of their parameters and of
+// the values they return. they are called like any other function.
+const BUILTINS: [(&str, &[Type], &[Type]); 8] = [
+    ("abs", &[Type::Int], &[Type::Int]),
+    ("min", &[Type::Int, Type::Int], &[Type::Int]),
+    ("max", &[Type::Int, Type::Int], &[Type::Int]),
+    ("pow", &[Type::Int, Type::Int], &[Type::Int]),
+    ("gcd", &[Type::Int, Type::Int], &[Type::Int]),
+    ("sqrt_int", &[Type::Int], &[Type::Int]),
+    ("assert", &[Type::Bool], &[]),
+    ("exit", &[Type::Int], &[]),
+];
 
-    let expression = Expression {
-        code : statement,
-        name : dest,
-    };
+// the fields of a struct, in the order they are declared.
+struct Struct {
+    name: String,
+    fields: Vec<Variable>,
+}
 
-    Ok(expression)
+// the variants of an enum, in the order they are declared. the value of a variant is its position.
+struct Enum {
+    name: String,
+    variants: Vec<String>,
 }
 
-fn parse_declaration_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+// the names declared inside of a block. they go out of scope at the end of the block.
+struct Scope {
+    constants: HashMap<String, i32>,
+    variables: HashMap<String, Variable>,
+}
 
-    let statement: String;
+// a variable in the symbol table. 'name' is the name of the variable in the IR, which is
+// only different from the name in the program when a variable is shadowed, such as 'x_1'.
+// 'ty' is the type of the variable, or of its elements. 'dimensions' is empty for single values,
+// and has the size of every dimension for arrays. the size of an array parameter is only known
+// at runtime, and is 0.
+struct Variable {
+    name: String,
+    ty: Type,
+    dimensions: Vec<i32>,
+}
 
-    match tokens[*index] {
-    Token::Int => {*index += 1;}
-    _ => {return Err(String::from("Declaration statements must being with 'int' keyword"));}
+impl CodeGenerator {
+    fn new() -> CodeGenerator {
+        let mut functions = HashMap::new();
+        for (name, parameters, returns) in BUILTINS {
+            let parameters = parameters.iter().map(|ty| Variable { name: String::from("value"), ty: *ty, dimensions: vec![] }).collect();
+            functions.insert(String::from(name), Function { parameters, returns: returns.to_vec() });
+        }
+        CodeGenerator {
+            loops: vec![],
+            global_constants: HashMap::new(),
+            global_variables: HashMap::new(),
+            functions,
+            function: String::new(),
+            structs: vec![],
+            enums: vec![],
+            strings: vec![],
+            string_names: HashMap::new(),
+            scopes: vec![],
+            ir_names: HashSet::new(),
+            free_slots: vec![],
+            warnings: vec![],
+        }
     }
+}
 
-    match &tokens[*index] {
-    Token::Ident(ident) => {
-        *index += 1;
-        statement = format!("%int {ident}\n");
-    }
-    _ => {return Err(String::from("Declarations must have an identifier"));}
+impl Scope {
+    fn new() -> Scope {
+        Scope {
+            constants: HashMap::new(),
+            variables: HashMap::new(),
+        }
     }
+}
 
-    match tokens[*index] {
-    Token::Semicolon => {*index += 1;}
-    _ => {return Err(String::from("Statements must end with a semicolon"));}
+// finds the variable that 'ident' refers to. a constant declared in a closer block hides the variable.
+fn find_variable<'a>(codegen: &'a CodeGenerator, ident: &str) -> Option<&'a Variable> {
+    for scope in codegen.scopes.iter().rev() {
+        if scope.constants.contains_key(ident) {
+            return None;
+        }
+        if let Some(variable) = scope.variables.get(ident) {
+            return Some(variable);
+        }
     }
-
-    return Ok(statement);
+    codegen.global_variables.get(ident)
 }
 
-fn parse_assignment_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
-    let mut statement: String;
-
-    let dest: String;
-    match &tokens[*index] {
-    Token::Ident(ident) => {
-        *index += 1;
-        dest = ident.clone(); // copy ident into variable.
+fn find_constant(codegen: &CodeGenerator, ident: &str) -> Option<i32> {
+    for scope in codegen.scopes.iter().rev() {
+        if let Some(value) = scope.constants.get(ident) {
+            return Some(*value);
+        }
+        if scope.variables.contains_key(ident) {
+            return None;
+        }
     }
-    _ => {return Err(String::from("Assignment statements must being with an identifier"));}
+    codegen.global_constants.get(ident).copied()
+}
+
+// parses the arguments of a call, such as '(a, 2)', and returns the code computing them and the
+// values passed in the IR.
+// only variables can be passed to and returned from functions, so string and float constants are
+// copied into one. returns the code of the copy and the variable holding the value.
+fn constant_to_variable(codegen: &CodeGenerator, expression: &Expression, ty: Type) -> (String, String) {
+    let is_constant = match ty {
+    Type::Str => is_string_constant(codegen, &expression.name),
+    Type::Float => float_value(expression).is_some(),
+    _ => false,
+    };
+    if !is_constant {
+        return (String::from(""), expression.name.clone());
     }
+    let temp = create_temp();
+    let code = format!("{} {temp}\n%mov {temp}, {}\n", ir_declaration(ty), expression.name);
+    (code, temp)
+}
 
+fn parse_arguments(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator, identifier: &str) -> Result<(String, Vec<String>), String> {
     match tokens[*index] {
-    Token::Assign => {*index += 1;}
-    _ => {return Err(String::from("Statement is missing the '=' operator"));}
+    Token::LeftParen => {*index += 1;}
+    _ => {return Err(format!("expected '(' after '{identifier}'"));}
     }
+    let parameters: Vec<(Type, bool)> = match codegen.functions.get(identifier) {
+    Some(function) => function.parameters.iter().map(|parameter| (parameter.ty, !parameter.dimensions.is_empty())).collect(),
+    None => { return Err(format!("Function '{identifier}' is not declared")); }
+    };
 
-    match parse_expression(tokens, index) {
-    Ok(expression) => {
-        let src = expression.name;
-        statement = expression.code;
-        statement += &format!("%mov {dest}, {src}\n");
-    },
-    Err(e) => {return Err(e);}
-    }
+    // a struct is passed as its fields, so there can be more arguments in the IR than in the program.
+    let mut code = String::from("");
+    let mut arguments: Vec<String> = vec![];
+    let mut count = 0;
+    while !matches!(tokens[*index], Token::RightParen | Token::End) {
+        if count > 0 {
+            match tokens[*index] {
+            Token::Comma => {*index += 1;}
+            _ => { return Err(String::from("expected ',' in between function arguments")); }
+            }
+        }
+        count += 1;
+        let number = count;
+        if number > parameters.len() {
+            return Err(format!("Function '{identifier}' takes {} arguments, but more were given", parameters.len()));
+        }
+
+        let (ty, is_array) = parameters[number - 1];
+        if is_array {
+            // arrays are passed by reference, so the argument must be the name of an array.
+            let array = match &tokens[*index] {
+            Token::Ident(ident) if matches!(tokens[*index + 1], Token::Comma | Token::RightParen) => find_variable(codegen, ident),
+            _ => None,
+            };
+            match array {
+            Some(array) if !array.dimensions.is_empty() && array.ty == ty => {
+                *index += 1;
+                match ty {
+                Type::Struct(_) => arguments.extend(struct_fields(codegen, &array.name, ty).into_iter().map(|(field, _)| field)),
+                _ => arguments.push(array.name.clone()),
+                }
+            }
+            _ => { return Err(format!("Argument {number} of '{identifier}' must be an array of {}", type_name(codegen, ty))); }
+            }
+            continue;
+        }
 
+        if let Type::Struct(_) = ty {
+            let (value_code, values) = parse_struct_value(tokens, index, codegen, ty, &format!("Argument {number} of '{identifier}'"))?;
+            code += &value_code;
+            arguments.extend(values);
+            continue;
+        }
+
+        let argument = parse_expression(tokens, index, codegen)?;
+        check_type(codegen, &argument, ty, &format!("Argument {number} of '{identifier}'"))?;
+        code += &argument.code;
+        let (copy, name) = constant_to_variable(codegen, &argument, ty);
+        code += &copy;
+        arguments.push(name);
+    }
     match tokens[*index] {
-    Token::Semicolon => {*index += 1;}
-    _ => {return Err(String::from("Statement is missing the ';' semicolon"));}
+    Token::RightParen => {*index += 1;}
+    _ => { return Err(format!("missing ')' after the arguments of '{identifier}'")); }
     }
 
-    return Ok(statement);
+    if count != parameters.len() {
+        return Err(format!("Function '{identifier}' takes {} arguments, but {} were given", parameters.len(), count));
+    }
+    Ok((code, arguments))
 }
 
-fn parse_return_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
-    match tokens[*index] {
-    Token::Return => {*index += 1;}
-    _ => {return Err(String::from("Return statements must being with a return keyword"));}
+// the type of a named constant, which is the enum it is a variant of, or an int.
+fn constant_type(codegen: &CodeGenerator, ident: &str) -> Type {
+    for (position, declaration) in codegen.enums.iter().enumerate() {
+        if declaration.variants.iter().any(|variant| variant == ident) {
+            return Type::Enum(position);
+        }
     }
+    Type::Int
+}
 
-    match parse_expression(tokens, index) {
-    Ok(_) => {},
-    Err(e) => {return Err(e);}
+// the name of a variable in the IR.
+fn variable_name(codegen: &CodeGenerator, ident: &str) -> Result<String, String> {
+    match find_variable(codegen, ident) {
+    Some(variable) => Ok(variable.name.clone()),
+    None => Err(format!("'{ident}' is not declared")),
     }
+}
 
-    match tokens[*index] {
-    Token::Semicolon => {*index += 1;}
-    _ => {return Err(String::from("Statement is missing the ';' semicolon"));}
+// declares a variable in the innermost block, and returns its name in the IR and whether it reuses
+// the variable of a block that has already ended. only '%int' variables are reused, since they are cheap to
+// clear. bools and chars are integers too. a name that the function already uses in the IR gets a number, so that an inner 'x' does
+// not overwrite the outer 'x'.
+fn declare_variable(codegen: &mut CodeGenerator, ident: &str, ty: Type, dimensions: Vec<i32>) -> Result<(String, bool), String> {
+    if find_constant(codegen, ident).is_some() {
+        return Err(format!("'{ident}' is a constant and cannot be declared as a variable"));
+    }
+    if is_type_name(codegen, ident) {
+        return Err(format!("'{ident}' is a type and cannot be declared as a variable"));
+    }
+    let scope = codegen.scopes.last().unwrap();
+    if scope.variables.contains_key(ident) || scope.constants.contains_key(ident) {
+        return Err(format!("'{ident}' is declared more than once in the same block"));
     }
 
-    todo!()
-}
+    // the fields of a struct need free names too.
+    let is_free = |name: &str| {
+        !ir_name_taken(codegen, name) && struct_fields(codegen, name, ty).iter().all(|(field, _)| !ir_name_taken(codegen, field))
+    };
 
-fn parse_print_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
-    let expression: Expression;
-    match tokens[*index] {
-    Token::Print=> {*index += 1;}
-    _ => {return Err(String::from("Print statements must being with a return keyword"));}
+    let mut reused = false;
+    let name: String;
+    if dimensions.is_empty() && ir_declaration(ty) == "%int" && !codegen.free_slots.is_empty() {
+        // prefer a slot that had the same name, so that the IR stays readable.
+        let same_name = format!("{ident}_");
+        let slot = codegen.free_slots.iter().position(|slot| slot == ident || slot.starts_with(&same_name));
+        let slot = slot.unwrap_or(codegen.free_slots.len() - 1);
+        name = codegen.free_slots.remove(slot);
+        reused = true;
+    } else if is_free(ident) {
+        name = String::from(ident);
+    } else {
+        let mut num = 1;
+        while !is_free(&format!("{ident}_{num}")) {
+            num += 1;
+        }
+        name = format!("{ident}_{num}");
     }
 
-    match parse_expression(tokens, index) {
-    Ok(expr) => {
-        expression = expr;
-    },
-    Err(e) => {return Err(e);}
+    for (field, _) in struct_fields(codegen, &name, ty) {
+        codegen.ir_names.insert(field);
     }
+    codegen.ir_names.insert(name.clone());
+    let variable = Variable {
+        name: name.clone(),
+        ty,
+        dimensions,
+    };
+    codegen.scopes.last_mut().unwrap().variables.insert(String::from(ident), variable);
+    Ok((name, reused))
+}
 
-    match tokens[*index] {
-    Token::Semicolon => {*index += 1;}
-    _ => {return Err(String::from("Statement is missing the ';' semicolon"));}
+// whether a name is already used in the IR, by the current function or by a global variable.
+fn ir_name_taken(codegen: &CodeGenerator, name: &str) -> bool {
+    if codegen.ir_names.contains(name) {
+        return true;
     }
+    codegen.global_variables.values().any(|variable| {
+        variable.name == name || struct_fields(codegen, &variable.name, variable.ty).iter().any(|(field, _)| field == name)
+    })
+}
 
-    let mut statement = expression.code;
-    statement += &format!("%out {}\n", expression.name);
-    return Ok(statement);
+// the names in the IR and the types of the fields of a struct variable. the field 'x' of 'p' is the
+// variable 'p_x', and for an array of structs, 'p_x' is an array holding the field 'x' of every element.
+fn struct_fields(codegen: &CodeGenerator, name: &str, ty: Type) -> Vec<(String, Type)> {
+    match ty {
+    Type::Struct(index) => codegen.structs[index].fields.iter().map(|field| (format!("{name}_{}", field.name), field.ty)).collect(),
+    _ => vec![],
+    }
 }
 
-fn parse_read_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
-    match tokens[*index] {
-    Token::Read => {*index += 1;}
-    _ => {return Err(String::from("Read statements must being with a 'read' keyword"));}
+// the declaration of a struct type, such as the fields of 'Point'.
+fn struct_declaration(codegen: &CodeGenerator, ty: Type) -> &Struct {
+    match ty {
+    Type::Struct(index) => &codegen.structs[index],
+    _ => panic!("{} is not a struct", type_name(codegen, ty)),
     }
+}
 
-    match parse_expression(tokens, index) {
-    Ok(_) => {},
-    Err(e) => {return Err(e);}
+// arrays only hold ints and floats, so an array of structs is an array for every field, and every field
+// must be stored in an int or a float.
+fn check_struct_array(codegen: &CodeGenerator, ty: Type) -> Result<(), String> {
+    if let Type::Struct(index) = ty {
+        for field in &codegen.structs[index].fields {
+            if ir_declaration(field.ty) != "%int" && ir_declaration(field.ty) != "%float" {
+                return Err(format!("Arrays of '{}' are not supported, because its field '{}' is a {}", type_name(codegen, ty), field.name, type_name(codegen, field.ty)));
+            }
+        }
     }
+    Ok(())
+}
+
+// parses the field after a '.', such as the 'x' in 'p.x', and returns its name in the IR and its type.
+fn parse_field(tokens: &[Token], index: &mut usize, codegen: &CodeGenerator, ident: &str, name: &str, ty: Type) -> Result<(String, Type), String> {
     match tokens[*index] {
-    Token::Semicolon => {*index += 1;}
-    _ => {return Err(String::from("Statement is missing the ';' semicolon"));}
+    Token::Dot => {*index += 1;}
+    _ => {return Err(format!("expected '.' after '{ident}'"));}
     }
 
-    todo!()
-}
-
-// parsing complex expressions such as: "a + b - (c * d) / (f + g - 8);
-fn parse_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
-    let mut expression: Expression;
-    match parse_multiply_expression(tokens, index) {
-    Ok(expr) => {
-        expression = expr;
-    },
-    Err(e) => {return Err(e);}
+    let field = match &tokens[*index] {
+    Token::Ident(field) => {
+        *index += 1;
+        field
     }
-    loop {
-       match tokens[*index] {
+    _ => {return Err(format!("expected the name of a field after '{ident}.'"));}
+    };
 
-       Token::Plus => {
-           *index += 1;
-           match parse_multiply_expression(tokens, index) {
-           Ok(expr2) => {
-               let src1 = expression.name;
-               let src2 = expr2.name;
-               let dest = create_temp();
-               expression.code += &expr2.code;
-               expression.code += &format!("%int {dest}\n");
-               expression.code += &format!("%add {dest}, {src1}, {src2}\n");
-               expression.name = dest;
-           },
-           Err(e) => {return Err(e);}
-
-           }
-       }
+    let struct_name = type_name(codegen, ty);
+    match struct_declaration(codegen, ty).fields.iter().position(|other| &other.name == field) {
+    Some(position) => Ok(struct_fields(codegen, name, ty).swap_remove(position)),
+    None => Err(format!("Struct '{struct_name}' has no field named '{field}'")),
+    }
+}
 
-       Token::Subtract => {
-           *index += 1;
-           match parse_multiply_expression(tokens, index) {
-           Ok(expr2) => {
-               let src1 = expression.name;
-               let src2 = expr2.name;
-               let dest = create_temp();
-               expression.code += &expr2.code;
-               expression.code += &format!("%int {dest}\n");
-               expression.code += &format!("%add {dest}, {src1}, {src2}\n");
-               expression.name = dest;
-           },
-           Err(e) => {return Err(e);}
-
-           }
-       }
+// parses a whole struct, such as the 'q' in 'p = q;' or the 'points[i]' in 'p = points[i];', and returns
+// the code that computes it and the value of every field.
+fn parse_struct_value(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator, ty: Type, what: &str) -> Result<(String, Vec<String>), String> {
+    let variable = match &tokens[*index] {
+    Token::Ident(ident) => find_variable(codegen, ident).map(|variable| (ident.clone(), variable.name.clone(), variable.ty, !variable.dimensions.is_empty())),
+    _ => None,
+    };
+    let (ident, name, is_array) = match variable {
+    Some((ident, name, variable_ty, is_array)) if variable_ty == ty => (ident, name, is_array),
+    Some((_, _, variable_ty @ Type::Struct(_), _)) => {return Err(format!("{what} must be {}, not {}", type_name(codegen, ty), type_name(codegen, variable_ty)));}
+    _ => {
+        let expression = parse_expression(tokens, index, codegen)?;
+        return Err(format!("{what} must be {}, not {}", type_name(codegen, ty), type_name(codegen, expression.ty)));
+    }
+    };
+    *index += 1;
 
-       _ => { 
-           break;
-       }
+    let mut code = String::from("");
+    let mut element_index: Option<String> = None;
+    if is_array {
+        if !matches!(tokens[*index], Token::LeftBracket) {
+            return Err(format!("'{ident}' is an array. Use one of its elements instead, such as '{ident}[0]'"));
+        }
+        let element = parse_element_index(tokens, index, codegen, &ident)?;
+        code += &element.code;
+        element_index = Some(element.name);
+    }
+    if matches!(tokens[*index], Token::Dot) {
+        return Err(format!("{what} must be {}, not one of its fields", type_name(codegen, ty)));
+    }
 
-       };
+    let mut values: Vec<String> = vec![];
+    for (field, field_ty) in struct_fields(codegen, &name, ty) {
+        match &element_index {
+        None => values.push(field),
+        Some(element_index) => {
+            let temp = create_temp();
+            code += &format!("{} {temp}\n", ir_declaration(field_ty));
+            code += &format!("%mov {temp}, [{field} + {element_index}]\n");
+            values.push(temp);
+        }
+        }
     }
+    Ok((code, values))
+}
 
-    return Ok(expression);
+// ends the innermost block. its integers can be reused by the blocks that come after it.
+fn end_scope(codegen: &mut CodeGenerator) {
+    let scope = codegen.scopes.pop().unwrap();
+    let mut slots: Vec<String> = scope.variables.into_values()
+        .filter(|variable| variable.dimensions.is_empty() && ir_declaration(variable.ty) == "%int")
+        .map(|variable| variable.name)
+        .collect();
+    slots.sort();
+    codegen.free_slots.extend(slots);
 }
 
-fn parse_multiply_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
-    let mut expression: Expression;
-    match parse_term(tokens, index) {
-    Ok(expr) => {
-        expression = expr;
-    },
-    Err(e) => {return Err(e);}
+// where 'break' and 'continue' jump to inside of a loop. a switch statement is also on the
+// loop stack, since 'break' leaves the switch, but it has no continue label, so 'continue'
+// inside of a switch continues the enclosing loop.
+struct Loop {
+    break_label: String,
+    continue_label: Option<String>,
+}
+
+
+// parses the parameters of a function, such as '(int a, bool b)'. arrays are passed by
+// reference, such as '(int [] array, int n)'. the names are the names in the program.
+fn parse_parameters(tokens: &[Token], index: &mut usize, codegen: &CodeGenerator) -> Result<Vec<Variable>, String> {
+    match tokens[*index] {
+    Token::LeftParen => { *index += 1; }
+    _ => { return Err(String::from("expected '('"));}
     }
-    loop {
-       match tokens[*index] {
-       Token::Multiply => {
-          *index += 1;
-          match parse_term(tokens, index) {
-          Ok(expr2) => {
-              let src1 = expression.name;
-              let src2 = expr2.name;
-              let dest = create_temp();
-              expression.code += &expr2.code;
-              expression.code += &format!("%int {dest}\n");
-              expression.code += &format!("%mult {dest}, {src1}, {src2}\n");
-              expression.name = dest;
-          },
-          Err(e) => {return Err(e);}
-          }
-       }
 
-       Token::Divide => {
-          *index += 1;
-          match parse_term(tokens, index) {
-          Ok(expr2) => {
-              let src1 = expression.name;
-              let src2 = expr2.name;
-              let dest = create_temp();
-              expression.code += &expr2.code;
-              expression.code += &format!("%int {dest}\n");
-              expression.code += &format!("%div {dest}, {src1}, {src2}\n");
-              expression.name = dest;
-          },
-          Err(e) => {return Err(e);}
-          }
-       }
+    let mut parameters: Vec<Variable> = vec![];
+    while !matches!(tokens[*index], Token::RightParen) {
+        if !parameters.is_empty() {
+            match tokens[*index] {
+            Token::Comma => { *index += 1; }
+            _ => { return Err(String::from("expected ',' in between function parameters"));}
+            }
+        }
 
-       Token::Modulus => {
-          *index += 1;
-          match parse_term(tokens, index) {
-          Ok(expr2) => {
-              let src1 = expression.name;
-              let src2 = expr2.name;
-              let dest = create_temp();
-              expression.code += &expr2.code;
-              expression.code += &format!("%int {dest}\n");
-              expression.code += &format!("%mod {dest}, {src1}, {src2}\n");
-              expression.name = dest;
-          },
-          Err(e) => {return Err(e);}
-          }
-       }
-  
-       _ => {
-           break;
-       }
+        let ty = match find_type(codegen, &tokens[*index]) {
+        Some(ty) => ty,
+        None => { return Err(String::from("function parameters must begin with a type, such as 'int a'"));}
+        };
+        *index += 1;
 
-       };
+        let mut dimensions: Vec<i32> = vec![];
+        if matches!(tokens[*index], Token::LeftBracket) {
+            *index += 1;
+            match tokens[*index] {
+            Token::RightBracket => { *index += 1; }
+            _ => { return Err(String::from("array parameters do not have a size, such as 'int [] array'"));}
+            }
+            dimensions = vec![0];
+            if ty == Type::Str {
+                return Err(String::from("Arrays of strings are not supported"));
+            }
+            if ty == Type::Long {
+                return Err(String::from("Arrays of longs are not supported"));
+            }
+            check_struct_array(codegen, ty)?;
+        }
 
+        match &tokens[*index] {
+        Token::Ident(ident) => {
+            *index += 1;
+            parameters.push(Variable {
+                name: ident.clone(),
+                ty,
+                dimensions,
+            });
+        }
+        _ => { return Err(String::from("function parameters must have an identifier"));}
+        }
     }
 
-    return Ok(expression);
+    match tokens[*index] {
+    Token::RightParen => { *index += 1; }
+    _ => { return Err(String::from("expected ')'"));}
+    }
+    Ok(parameters)
 }
 
-// a term is either a Number or an Identifier.
-fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
-    match &tokens[*index] {
+// parse function such as:
+// func main(int a, int b) {
+//    # ... statements here...
+//    # ...
+// }
+// a loop is done to handle statements.
 
-    Token::Ident(identifier) => {
-        *index += 1;
-        let expression = Expression {
-            code : String::from(""),
-            name : identifier.clone()
-        };
-        return Ok(expression);
+// parses the types a function returns, such as '-> long' or '-> (int, int)'. a function without
+// them does not return a value. every type except structs can be returned.
+fn parse_return_types(tokens: &[Token], index: &mut usize, codegen: &CodeGenerator) -> Result<Vec<Type>, String> {
+    if !matches!(tokens[*index], Token::Arrow) {
+        return Ok(vec![]);
     }
+    *index += 1;
 
-    Token::Num(number) => {
+    let is_tuple = matches!(tokens[*index], Token::LeftParen);
+    if is_tuple {
         *index += 1;
-        let expression = Expression {
-            code : String::from(""),
-            name : number.to_string()
-        };
-        return Ok(expression);
     }
-
+    let mut returns: Vec<Type> = vec![];
+    loop {
+        let ty = match find_type(codegen, &tokens[*index]) {
+        Some(ty) => ty,
+        None => {return Err(String::from("expected a type after '->', such as '-> int' or '-> (int, int)'"));}
+        };
+        if let Type::Struct(_) = ty {
+            return Err(format!("Functions cannot return structs, such as {}. Return its fields instead", type_name(codegen, ty)));
+        }
+        *index += 1;
+        returns.push(ty);
+
+        if !is_tuple || !matches!(tokens[*index], Token::Comma) {
+            break;
+        }
+        *index += 1;
+    }
+
+    if is_tuple {
+        match tokens[*index] {
+        Token::RightParen => {*index += 1;}
+        _ => {return Err(String::from("expected ')' after the return types"));}
+        }
+    }
+    Ok(returns)
+}
+
+fn parse_function(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    
+    match tokens[*index] {
+    Token::Func => { *index += 1; }
+    _ => { return Err(String::from("functions must begin with func")); }
+    }
+
+    let mut function_code: String;
+
+    let function_ident = match &tokens[*index] {
+    Token::Ident(ident) => {
+        *index += 1;
+        ident
+    }
+    _  => { return Err(String::from("functions must have a function identifier"));}
+    };
+
+
+    codegen.scopes.push(Scope::new());
+    let mut parameters: Vec<String> = vec![];
+    for parameter in parse_parameters(tokens, index, codegen)? {
+        let ident = &parameter.name;
+        if find_constant(codegen, ident).is_some() {
+            return Err(format!("'{ident}' is a constant and cannot be used as a parameter name"));
+        }
+        let is_array = !parameter.dimensions.is_empty();
+        let (name, _) = declare_variable(codegen, ident, parameter.ty, parameter.dimensions)?;
+
+        // a struct is passed as its fields, and an array of structs as the array of every field.
+        let names = match parameter.ty {
+        Type::Struct(_) => struct_fields(codegen, &name, parameter.ty),
+        ty => vec![(name, ty)],
+        };
+        for (name, ty) in names {
+            let parameter_type = if is_array { array_declaration(ty) } else { ir_declaration(ty) };
+            parameters.push(format!("{parameter_type} {name}"));
+        }
+    }
+
+    function_code = format!("%func {function_ident}({})\n", parameters.join(", "));
+    parse_return_types(tokens, index, codegen)?;
+    codegen.function = function_ident.clone();
+
+    match tokens[*index] {
+    Token::LeftCurly => { *index += 1; }
+    _ => { return Err(String::from("expected '{'"));}
+    }
+
+    let body_start = function_code.len();
+    while !matches!(tokens[*index], Token::RightCurly) {
+        match parse_statement(tokens, index, codegen) {
+        Ok(statement_code) => {
+            function_code += &statement_code;
+        }
+        Err(e) => {return Err(e);}
+        }
+    }
+
+
+    match tokens[*index] {
+    Token::RightCurly => { *index += 1; }
+    _ => { return Err(String::from("expected '}'"));}
+    }
+
+    // a function returning a value must return it on every path.
+    if !codegen.functions[function_ident].returns.is_empty() && can_reach_end(&function_code[body_start..])? {
+        return Err(format!("Function '{function_ident}' can reach its end without returning a value. Add a 'return' at the end"));
+    }
+
+    // constants and variables declared inside of the function go out of scope.
+    codegen.scopes.clear();
+    codegen.ir_names.clear();
+    codegen.free_slots.clear();
+
+    function_code += "%endfunc\n";
+    return Ok(function_code);
+}
+
+// whether running the IR of a function body can reach its end, instead of a '%ret' or a call to 'exit'.
+// a branch on a number, such as the one 'while true' becomes, only goes one way.
+fn can_reach_end(code: &str) -> Result<bool, String> {
+    let lines: Vec<&str> = code.lines().collect();
+    let labels: HashMap<&str, usize> = lines.iter().enumerate()
+        .filter(|(_, line)| line.starts_with(':'))
+        .map(|(i, line)| (*line, i))
+        .collect();
+
+    let target = |label: &str| match labels.get(label) {
+    Some(line) => Ok(*line),
+    None => Err(format!("The function jumps to the label '{label}', which is not in its body")),
+    };
+
+    let mut reached = vec![false; lines.len()];
+    let mut pending = vec![0];
+    while let Some(i) = pending.pop() {
+        if i == lines.len() {
+            return Ok(true);
+        }
+        if reached[i] {
+            continue;
+        }
+        reached[i] = true;
+
+        let (opcode, operands) = lines[i].split_once(' ').unwrap_or((lines[i], ""));
+        let operands: Vec<&str> = operands.split(", ").collect();
+        match opcode {
+        "%ret" => {}
+        "%call" if operands[operands.len() - 1].starts_with("exit(") => {}
+        "%jmp" => pending.push(target(operands[0])?),
+        "%branch_if" | "%branch_ifn" => {
+            match operands[0].parse::<i64>() {
+            Ok(value) if (value != 0) == (opcode == "%branch_if") => pending.push(target(operands[1])?),
+            Ok(_) => pending.push(i + 1),
+            Err(_) => {
+                pending.push(target(operands[1])?);
+                pending.push(i + 1);
+            }
+            }
+        }
+        "%switch" => {
+            for label in &operands[1..] {
+                pending.push(target(label)?);
+            }
+        }
+        _ => pending.push(i + 1),
+        }
+    }
+    Ok(false)
+}
+
+// parsing a statement such as:
+// int a;
+// a = a + b;
+// a = a % b;
+// print(a)
+// read(a)
+// returns epsilon if '}'
+fn parse_statement(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    match &tokens[*index] {
+    Token::Int | Token::Long | Token::Float | Token::Bool | Token::Char | Token::String => parse_declaration_statement(tokens, index, codegen),
+    Token::Ident(ident) if is_type_name(codegen, ident) && matches!(tokens[*index + 1], Token::Ident(_) | Token::LeftBracket) => {
+        parse_declaration_statement(tokens, index, codegen)
+    }
+    Token::Const => parse_constant_statement(tokens, index, codegen),
+    Token::Ident(_) if matches!(tokens[*index + 1], Token::Comma) => parse_multiple_assignment(tokens, index, codegen),
+    Token::Ident(_) if matches!(tokens[*index + 1], Token::LeftParen) => parse_call_statement(tokens, index, codegen),
+    Token::Ident(_) | Token::Increment | Token::Decrement => parse_assignment_statement(tokens, index, codegen),
+    Token::Return => parse_return_statement(tokens, index, codegen),
+    Token::Print => parse_print_statement(tokens, index, codegen),
+    Token::Read => parse_read_statement(tokens, index, codegen),
+    Token::While => parse_while_loop(tokens, index, codegen),
+    Token::For => parse_for_loop(tokens, index, codegen),
+    Token::Do => parse_do_while_loop(tokens, index, codegen),
+    Token::Break => parse_break_statement(tokens, index, codegen),
+    Token::Continue => parse_continue_statement(tokens, index, codegen),
+    Token::If => parse_if_statement(tokens, index, codegen),
+    Token::Switch => parse_switch_statement(tokens, index, codegen),
+    Token::LeftCurly => parse_block(tokens, index, codegen),
+    _ => Err(String::from("invalid statement"))
+    }
+}
+
+// parsing a block of statements such as:
+// {
+//    a = a + b;
+//    print(a);
+// }
+// variables declared inside of the block can only be used inside of the block.
+fn parse_block(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    match tokens[*index] {
+    Token::LeftCurly => { *index += 1; }
+    _ => { return Err(String::from("expected '{'"));}
+    }
+
+    codegen.scopes.push(Scope::new());
+    let mut code = String::from("");
+    while !matches!(tokens[*index], Token::RightCurly | Token::End) {
+        code += &parse_statement(tokens, index, codegen)?;
+    }
+    end_scope(codegen);
+
+    match tokens[*index] {
+    Token::RightCurly => { *index += 1; }
+    _ => { return Err(String::from("expected '}'"));}
+    }
+
+    Ok(code)
+}
+
+// parses the body of a loop. 'break' and 'continue' inside of the body
+// jump to the given labels.
+fn parse_loop_body(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator, break_label: &str, continue_label: &str) -> Result<String, String> {
+    codegen.loops.push(Loop {
+        break_label: String::from(break_label),
+        continue_label: Some(String::from(continue_label)),
+    });
+    let body = parse_block(tokens, index, codegen);
+    codegen.loops.pop();
+    body
+}
+
+// while i < 10 {
+//     ...
+// }
+fn parse_while_loop(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+
+    match tokens[*index] {
+    Token::While => {*index += 1;}
+    _ => {return Err(String::from("While statements must being with 'while' keyword"));}
+    }
+
+    let boolean_expression = parse_expression(tokens, index, codegen)?;
+    check_type(codegen, &boolean_expression, Type::Bool, "The condition of a while loop")?;
+
+    let num = create_label_num();
+    let loop_begin = format!(":loopbegin{num}");
+    let end_loop = format!(":endloop{num}");
+    let while_loop_body = parse_loop_body(tokens, index, codegen, &end_loop, &loop_begin)?;
+
+    let mut loop_code = String::from("");
+    loop_code += &format!("{loop_begin}\n");
+    loop_code += &boolean_expression.code;
+    loop_code += &format!("%branch_ifn {}, {end_loop}\n", boolean_expression.name);
+    loop_code += &while_loop_body;
+    loop_code += &format!("%jmp {loop_begin}\n");
+    loop_code += &format!("{end_loop}\n");
+
+    Ok(loop_code)
+}
+
+// for (int i = 0; i < 10; i = i + 1) {
+//     ...
+// }
+// any of the three parts may be left empty. without a condition, the loop runs until a 'break'.
+fn parse_for_loop(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+
+    match tokens[*index] {
+    Token::For => {*index += 1;}
+    _ => {return Err(String::from("For loops must being with 'for' keyword"));}
+    }
+
+    match tokens[*index] {
+    Token::LeftParen => {*index += 1;}
+    _ => {return Err(String::from("expected '(' after 'for'"));}
+    }
+
+    // a variable declared in the initialization can only be used inside of the loop.
+    codegen.scopes.push(Scope::new());
+
+    let init_code = match tokens[*index] {
+    Token::Semicolon => String::from(""),
+    _ if find_type(codegen, &tokens[*index]).is_some() => parse_declaration(tokens, index, codegen)?,
+    _ => parse_assignment(tokens, index, codegen)?,
+    };
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("expected ';' after the initialization of the for loop"));}
+    }
+
+    let condition = match tokens[*index] {
+    Token::Semicolon => None,
+    _ => Some(parse_expression(tokens, index, codegen)?),
+    };
+    if let Some(condition) = &condition {
+        check_type(codegen, condition, Type::Bool, "The condition of a for loop")?;
+    }
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("expected ';' after the condition of the for loop"));}
+    }
+
+    let step_code = match tokens[*index] {
+    Token::RightParen => String::from(""),
+    _ => parse_assignment(tokens, index, codegen)?,
+    };
+
+    match tokens[*index] {
+    Token::RightParen => {*index += 1;}
+    _ => {return Err(String::from("expected ')' after the step of the for loop"));}
+    }
+
+    // 'continue' must still run the step, so it jumps to ':loopcontinue' instead of ':loopbegin'.
+    let num = create_label_num();
+    let loop_begin = format!(":loopbegin{num}");
+    let loop_continue = format!(":loopcontinue{num}");
+    let end_loop = format!(":endloop{num}");
+    let for_loop_body = parse_loop_body(tokens, index, codegen, &end_loop, &loop_continue)?;
+    end_scope(codegen);
+
+    let mut loop_code = init_code;
+    loop_code += &format!("{loop_begin}\n");
+    if let Some(condition) = condition {
+        loop_code += &condition.code;
+        loop_code += &format!("%branch_ifn {}, {end_loop}\n", condition.name);
+    }
+    loop_code += &for_loop_body;
+    loop_code += &format!("{loop_continue}\n");
+    loop_code += &step_code;
+    loop_code += &format!("%jmp {loop_begin}\n");
+    loop_code += &format!("{end_loop}\n");
+
+    Ok(loop_code)
+}
+
+// do {
+//     ...
+// } while (i < 10);
+fn parse_do_while_loop(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+
+    match tokens[*index] {
+    Token::Do => {*index += 1;}
+    _ => {return Err(String::from("Do while loops must being with 'do' keyword"));}
+    }
+
+    // 'continue' skips the rest of the body, but the condition is still checked.
+    let num = create_label_num();
+    let loop_begin = format!(":loopbegin{num}");
+    let loop_continue = format!(":loopcontinue{num}");
+    let end_loop = format!(":endloop{num}");
+    let do_loop_body = parse_loop_body(tokens, index, codegen, &end_loop, &loop_continue)?;
+
+    match tokens[*index] {
+    Token::While => {*index += 1;}
+    _ => {return Err(String::from("expected 'while' after the body of the do while loop"));}
+    }
+
+    let boolean_expression = parse_expression(tokens, index, codegen)?;
+    check_type(codegen, &boolean_expression, Type::Bool, "The condition of a do while loop")?;
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statement is missing the ';' semicolon"));}
+    }
+
+    let mut loop_code = String::from("");
+    loop_code += &format!("{loop_begin}\n");
+    loop_code += &do_loop_body;
+    loop_code += &format!("{loop_continue}\n");
+    loop_code += &boolean_expression.code;
+    loop_code += &format!("%branch_if {}, {loop_begin}\n", boolean_expression.name);
+    loop_code += &format!("{end_loop}\n");
+
+    return Ok(loop_code);
+}
+
+fn parse_break_statement(tokens: &[Token], index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    match tokens[*index] {
+    Token::Break => {*index += 1;}
+    _ => {return Err(String::from("Break statements must being with 'break' keyword"));}
+    }
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statement is missing the ';' semicolon"));}
+    }
+
+    match codegen.loops.last() {
+    Some(inner_loop) => Ok(format!("%jmp {}\n", inner_loop.break_label)),
+    None => Err(String::from("break statement is outside a loop")),
+    }
+}
+
+fn parse_continue_statement(tokens: &[Token], index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    match tokens[*index] {
+    Token::Continue => {*index += 1;}
+    _ => {return Err(String::from("Continue statements must being with 'continue' keyword"));}
+    }
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statement is missing the ';' semicolon"));}
+    }
+
+    match codegen.loops.iter().rev().find_map(|inner_loop| inner_loop.continue_label.as_ref()) {
+    Some(continue_label) => Ok(format!("%jmp {}\n", continue_label)),
+    None => Err(String::from("continue statement is outside a loop")),
+    }
+}
+
+// if a < b {
+//     ...
+// } else if a == b {
+//     ...
+// } else {
+//     ...
+// }
+fn parse_if_statement(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+
+    match tokens[*index] {
+    Token::If => {*index += 1;}
+    _ => {return Err(String::from("If statements must being with 'if' keyword"));}
+    }
+
+    let boolean_expression = parse_expression(tokens, index, codegen)?;
+    check_type(codegen, &boolean_expression, Type::Bool, "The condition of an if statement")?;
+    let if_body = parse_block(tokens, index, codegen)?;
+
+    let else_body = match tokens[*index] {
+    Token::Else => {
+        *index += 1;
+        match tokens[*index] {
+        Token::If => parse_if_statement(tokens, index, codegen)?,
+        _ => parse_block(tokens, index, codegen)?,
+        }
+    }
+    _ => String::from(""),
+    };
+
+    let num = create_label_num();
+    let mut if_code = boolean_expression.code;
+    if_code += &format!("%branch_ifn {}, :else{num}\n", boolean_expression.name);
+    if_code += &if_body;
+    if_code += &format!("%jmp :endif{num}\n");
+    if_code += &format!(":else{num}\n");
+    if_code += &else_body;
+    if_code += &format!(":endif{num}\n");
+
+    Ok(if_code)
+}
+
+// switch (a) {
+// case 1:
+//     ...
+// case 2, 3:
+//     ...
+// default:
+//     ...
+// }
+// there is no fallthrough. after a case is done, the switch is done, and 'break' leaves the switch early.
+// the case values must be constants. a few cases, or cases spread far apart, are compared one at a time
+// with '%eq' and '%branch_if'. many cases close together use a jump table with '%switch'.
+fn parse_switch_statement(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    match tokens[*index] {
+    Token::Switch => {*index += 1;}
+    _ => {return Err(String::from("Switch statements must begin with the 'switch' keyword"));}
+    }
+
+    // switch statements can be over ints, chars or enums.
+    let value = parse_expression(tokens, index, codegen)?;
+    if !matches!(value.ty, Type::Char | Type::Enum(_)) {
+        check_type(codegen, &value, Type::Int, "The value of a switch statement")?;
+    }
+    match tokens[*index] {
+    Token::LeftCurly => {*index += 1;}
+    _ => {return Err(String::from("expected '{'"));}
+    }
+
+    let num = create_label_num();
+    let end_label = format!(":endswitch{num}");
+    codegen.loops.push(Loop {
+        break_label: end_label.clone(),
+        continue_label: None,
+    });
+
+    // every case value and the label of its case.
+    let mut cases: Vec<(i32, String)> = vec![];
+    let mut default_label: Option<String> = None;
+    let mut body = String::from("");
+    while !matches!(tokens[*index], Token::RightCurly | Token::End) {
+        let label: String;
+        match tokens[*index] {
+        Token::Case => {
+            *index += 1;
+            label = format!(":case{num}_{}", cases.len());
+            loop {
+                let case = parse_expression(tokens, index, codegen)?;
+                check_type(codegen, &case, value.ty, "A case value")?;
+                let case = match constant_value(&case) {
+                Some(case) => case as i32,
+                None => {return Err(String::from("Case values must be known at compile time, such as 'case 1:' or 'case N:'"));}
+                };
+                if cases.iter().any(|(value, _)| *value == case) {
+                    return Err(format!("Duplicate case value {case} in switch statement"));
+                }
+                cases.push((case, label.clone()));
+
+                match tokens[*index] {
+                Token::Comma => {*index += 1;}
+                _ => {break;}
+                }
+            }
+        }
+
+        Token::Default => {
+            *index += 1;
+            if default_label.is_some() {
+                return Err(String::from("A switch statement can only have one default"));
+            }
+            label = format!(":default{num}");
+            default_label = Some(label.clone());
+        }
+
+        _ => {return Err(String::from("Statements in a switch must be after a 'case' or 'default'"));}
+        }
+
+        match tokens[*index] {
+        Token::Colon => {*index += 1;}
+        _ => {return Err(String::from("expected ':' after the case"));}
+        }
+
+        body += &format!("{label}\n");
+        codegen.scopes.push(Scope::new());
+        while !matches!(tokens[*index], Token::Case | Token::Default | Token::RightCurly | Token::End) {
+            body += &parse_statement(tokens, index, codegen)?;
+        }
+        end_scope(codegen);
+        body += &format!("%jmp {end_label}\n");
+    }
+    codegen.loops.pop();
+
+    match tokens[*index] {
+    Token::RightCurly => {*index += 1;}
+    _ => {return Err(String::from("expected '}'"));}
+    }
+
+    // a switch over an enum should handle every variant, or have a default.
+    if let Type::Enum(enum_index) = value.ty {
+        if default_label.is_none() {
+            let enum_name = type_name(codegen, value.ty).to_string();
+            let missing: Vec<String> = codegen.enums[enum_index].variants.iter().enumerate()
+                .filter(|(position, _)| !cases.iter().any(|(case, _)| *case == *position as i32))
+                .map(|(_, variant)| format!("'{variant}'"))
+                .collect();
+            if !missing.is_empty() {
+                codegen.warnings.push(format!("The switch over {enum_name} has no case for {} and no default", missing.join(", ")));
+            }
+        }
+    }
+
+    let mut code = value.code;
+    let miss_label = default_label.unwrap_or(end_label.clone());
+    let min = cases.iter().map(|(value, _)| *value).min().unwrap_or(0);
+    let max = cases.iter().map(|(value, _)| *value).max().unwrap_or(0);
+    let range = max as i64 - min as i64 + 1;
+    if cases.len() >= 4 && range <= 2 * cases.len() as i64 {
+        // jump table. values without a case jump to the default.
+        let mut table: Vec<String> = vec![miss_label.clone(); range as usize];
+        for (value, label) in &cases {
+            table[(*value as i64 - min as i64) as usize] = label.clone();
+        }
+        let offset = create_temp();
+        code += &format!("%int {offset}\n");
+        code += &format!("%sub {offset}, {}, {min}\n", value.name);
+        code += &format!("%switch {offset}, {miss_label}, {}\n", table.join(", "));
+    } else {
+        for (case, label) in &cases {
+            let equal = create_temp();
+            code += &format!("%int {equal}\n");
+            code += &format!("%eq {equal}, {}, {case}\n", value.name);
+            code += &format!("%branch_if {equal}, {label}\n");
+        }
+        code += &format!("%jmp {miss_label}\n");
+    }
+    code += &body;
+    code += &format!("{end_label}\n");
+    Ok(code)
+}
+
+fn parse_constant_statement(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    let (ident, value) = parse_constant_declaration(tokens, index, codegen)?;
+    let scope = codegen.scopes.last_mut().unwrap();
+    if scope.variables.contains_key(&ident) {
+        return Err(format!("'{ident}' is declared more than once in the same block"));
+    }
+    scope.constants.insert(ident, value);
+
+    // constants are replaced by their value wherever they are used, so no code is generated.
+    Ok(String::from(""))
+}
+
+// parses a named constant such as 'const int N = 10 * 4;'. the value must be known at
+// compile time, so it may only use numbers and other constants.
+fn parse_constant_declaration(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<(String, i32), String> {
+    match tokens[*index] {
+    Token::Const => {*index += 1;}
+    _ => {return Err(String::from("Constant declarations must begin with the 'const' keyword"));}
+    }
+
+    match tokens[*index] {
+    Token::Int => {*index += 1;}
+    _ => {return Err(String::from("Constant declarations must have a type, such as 'const int N = 10'"));}
+    }
+
+    let ident = match &tokens[*index] {
+    Token::Ident(ident) => {
+        *index += 1;
+        ident.clone()
+    }
+    _ => {return Err(String::from("Constant declarations must have an identifier"));}
+    };
+
+    if find_constant(codegen, &ident).is_some() {
+        return Err(format!("Constant '{ident}' is declared more than once"));
+    }
+
+    match tokens[*index] {
+    Token::Assign => {*index += 1;}
+    _ => {return Err(format!("Constant '{ident}' must be given a value, such as 'const int {ident} = 10'"));}
+    }
+
+    let expression = parse_expression(tokens, index, codegen)?;
+    check_type(codegen, &expression, Type::Int, &format!("The value of constant '{ident}'"))?;
+    let value = match constant_value(&expression) {
+    Some(value) => value as i32,
+    None => {return Err(format!("The value of constant '{ident}' must be known at compile time"));}
+    };
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statements must end with a semicolon"));}
+    }
+
+    Ok((ident, value))
+}
+
+fn parse_declaration_statement(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    let statement = parse_declaration(tokens, index, codegen)?;
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statements must end with a semicolon"));}
+    }
+
+    Ok(statement)
+}
+
+// parses a declaration without the semicolon, such as 'int a', 'int a = 10', 'int [10] array'
+// or 'int [4][5] grid'. multi-dimensional arrays are stored row by row in a single '%int[]' or '%float[]'.
+fn parse_declaration(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+
+    let mut statement: String;
+
+    let ty = match find_type(codegen, &tokens[*index]) {
+    Some(ty) => ty,
+    None => {return Err(String::from("Declaration statements must being with a type, such as 'int' or 'bool'"));}
+    };
+    *index += 1;
+
+    let mut dimensions: Vec<i32> = vec![];
+    while matches!(tokens[*index], Token::LeftBracket) {
+        *index += 1;
+        let expression = parse_expression(tokens, index, codegen)?;
+        check_type(codegen, &expression, Type::Int, "The size of an array")?;
+        let size = match constant_value(&expression) {
+        Some(size) => size as i32,
+        None => {return Err(String::from("Array sizes must be known at compile time, such as 'int [10] array' or 'int [N] array'"));}
+        };
+        if size <= 0 {
+            return Err(format!("Array size must be greater than zero, not {size}"));
+        }
+
+        match tokens[*index] {
+        Token::RightBracket => {*index += 1;}
+        _ => {return Err(String::from("expected ']' after the size of the array"));}
+        }
+        dimensions.push(size);
+    }
+
+    if !dimensions.is_empty() {
+        if ty == Type::Str {
+            return Err(String::from("Arrays of strings are not supported"));
+        }
+        if ty == Type::Long {
+            return Err(String::from("Arrays of longs are not supported"));
+        }
+        check_struct_array(codegen, ty)?;
+        let mut size: i32 = 1;
+        for dimension in &dimensions {
+            size = match size.checked_mul(*dimension) {
+            Some(size) => size,
+            None => {return Err(String::from("Array is too large"));}
+            };
+        }
+
+        let ident = match &tokens[*index] {
+        Token::Ident(ident) => {
+            *index += 1;
+            ident
+        }
+        _ => {return Err(String::from("Declarations must have an identifier"));}
+        };
+
+        if let Type::Struct(_) = ty {
+            if matches!(tokens[*index], Token::Assign) {
+                return Err(format!("Arrays of structs cannot be initialized. Assign to the elements of '{ident}' instead"));
+            }
+            let (name, _) = declare_variable(codegen, ident, ty, dimensions)?;
+            statement = String::from("");
+            for (field, field_ty) in struct_fields(codegen, &name, ty) {
+                statement += &format!("{} {field}, {size}\n", array_declaration(field_ty));
+            }
+            return Ok(statement);
+        }
+
+        // an initializer list such as '{2, 3, 5, 7, 11}'. the elements without a value stay zero.
+        // the values are computed before the array is declared, so they cannot use the array itself.
+        let mut values: Vec<Expression> = vec![];
+        if matches!(tokens[*index], Token::Assign) {
+            *index += 1;
+            match tokens[*index] {
+            Token::LeftCurly => {*index += 1;}
+            _ => {return Err(format!("Arrays must be initialized with a list of values, such as 'int [3] {ident} = {{1, 2, 3}}'"));}
+            }
+
+            let mut count: i32 = 0;
+            while !matches!(tokens[*index], Token::RightCurly) {
+                if count > 0 {
+                    match tokens[*index] {
+                    Token::Comma => {*index += 1;}
+                    _ => {return Err(String::from("expected ',' in between the values of the array"));}
+                    }
+                }
+                if count == size {
+                    return Err(format!("Array '{ident}' has {size} elements, but its initializer has more values than that"));
+                }
+
+                let value = parse_expression(tokens, index, codegen)?;
+                check_type(codegen, &value, ty, &format!("The values of '{ident}'"))?;
+                values.push(value);
+                count += 1;
+            }
+            *index += 1;
+        }
+
+        let (name, _) = declare_variable(codegen, ident, ty, dimensions)?;
+        statement = format!("{} {name}, {size}\n", array_declaration(ty));
+        for (i, value) in values.iter().enumerate() {
+            statement += &value.code;
+            statement += &format!("%mov [{name} + {i}], {}\n", value.name);
+        }
+        return Ok(statement);
+    }
+
+    let ident = match &tokens[*index] {
+    Token::Ident(ident) => {
+        *index += 1;
+        ident
+    }
+    _ => {return Err(String::from("Declarations must have an identifier"));}
+    };
+
+    if let Type::Struct(_) = ty {
+        return parse_struct_declaration(tokens, index, codegen, ident, ty);
+    }
+
+    // like arrays, the value is computed before the variable is declared, so in
+    // 'int x = x + 1;' inside of a block, the 'x + 1' is the 'x' of the outer block.
+    let mut value: Option<Expression> = None;
+    if matches!(tokens[*index], Token::Assign) {
+        *index += 1;
+        let expression = parse_expression(tokens, index, codegen)?;
+        check_type(codegen, &expression, ty, &format!("The value of '{ident}'"))?;
+        value = Some(expression);
+    }
+
+    // a reused variable still holds the value from its old block, so it is cleared.
+    let (name, reused) = declare_variable(codegen, ident, ty, vec![])?;
+    match value {
+    Some(expression) => {
+        statement = if reused { String::from("") } else { format!("{} {name}\n", ir_declaration(ty)) };
+        statement += &expression.code;
+        statement += &format!("%mov {name}, {}\n", expression.name);
+    }
+    None if reused => {
+        statement = format!("%mov {name}, 0\n");
+    }
+    None => {
+        statement = format!("{} {name}\n", ir_declaration(ty));
+    }
+    }
+
+    Ok(statement)
+}
+
+// declares a struct variable, which is a variable for every field. the fields can be given in order,
+// such as 'Point p = {1, 2}', or copied from another struct, such as 'Point p = q'. fields without
+// a value start out as zero, or as the empty string.
+fn parse_struct_declaration(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator, ident: &str, ty: Type) -> Result<String, String> {
+    let mut code = String::from("");
+    let mut values: Vec<String> = vec![];
+    if matches!(tokens[*index], Token::Assign) {
+        *index += 1;
+        if matches!(tokens[*index], Token::LeftCurly) {
+            *index += 1;
+            let fields: Vec<(String, Type)> = struct_declaration(codegen, ty).fields.iter().map(|field| (field.name.clone(), field.ty)).collect();
+            while !matches!(tokens[*index], Token::RightCurly) {
+                if !values.is_empty() {
+                    match tokens[*index] {
+                    Token::Comma => {*index += 1;}
+                    _ => {return Err(String::from("expected ',' in between the values of the fields"));}
+                    }
+                }
+                if values.len() == fields.len() {
+                    return Err(format!("Struct '{}' has {} fields, but the value of '{ident}' has more values than that", type_name(codegen, ty), fields.len()));
+                }
+
+                let (field, field_ty) = &fields[values.len()];
+                let value = parse_expression(tokens, index, codegen)?;
+                check_type(codegen, &value, *field_ty, &format!("The value of field '{field}'"))?;
+                code += &value.code;
+                values.push(value.name);
+            }
+            *index += 1;
+        } else {
+            let (value_code, value_fields) = parse_struct_value(tokens, index, codegen, ty, &format!("The value of '{ident}'"))?;
+            code += &value_code;
+            values = value_fields;
+        }
+    }
+
+    let (name, _) = declare_variable(codegen, ident, ty, vec![])?;
+    let fields = struct_fields(codegen, &name, ty);
+    let mut statement = String::from("");
+    for (field, field_ty) in &fields {
+        statement += &format!("{} {field}\n", ir_declaration(*field_ty));
+    }
+    statement += &code;
+    for (value, (field, _)) in values.iter().zip(&fields) {
+        statement += &format!("%mov {field}, {value}\n");
+    }
+    Ok(statement)
+}
+
+// calls a function for what it does, such as 'tick(1);'. the values it returns, if any, are not used.
+fn parse_call_statement(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    let identifier = match &tokens[*index] {
+    Token::Ident(identifier) => {
+        *index += 1;
+        identifier.clone()
+    }
+    _ => {return Err(String::from("Call statements must begin with the name of a function"));}
+    };
+    let (mut statement, arguments) = parse_arguments(tokens, index, codegen, &identifier)?;
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statements must end with a semicolon"));}
+    }
+
+    let mut dests = String::from("");
+    for ty in codegen.functions[&identifier].returns.clone() {
+        let temp = create_temp();
+        statement += &format!("{} {temp}\n", ir_declaration(ty));
+        dests += &format!("{temp}, ");
+    }
+    statement += &format!("%call {dests}{identifier}({})\n", arguments.join(", "));
+    Ok(statement)
+}
+
+// stores every value a function returns, such as 'q, r = divmod(7, 2);'. the variables must have the
+// types the function returns. array elements and struct fields cannot be assigned this way.
+fn parse_multiple_assignment(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    let mut dests: Vec<(String, String, Type)> = vec![];
+    loop {
+        let ident = match &tokens[*index] {
+        Token::Ident(ident) => {
+            *index += 1;
+            ident.clone()
+        }
+        _ => {return Err(String::from("expected a variable in between the ','"));}
+        };
+        if matches!(tokens[*index], Token::LeftBracket | Token::Dot) {
+            return Err(String::from("Only variables can receive multiple return values, not array elements or struct fields"));
+        }
+        if find_constant(codegen, &ident).is_some() {
+            return Err(format!("'{ident}' is a constant and cannot be assigned to"));
+        }
+        let (name, ty) = match find_variable(codegen, &ident) {
+        Some(variable) if !variable.dimensions.is_empty() => {return Err(format!("'{ident}' is an array, and only variables can be assigned several values at once"));}
+        Some(variable) if matches!(variable.ty, Type::Struct(_)) => {
+            return Err(format!("'{ident}' is a {}, and functions cannot return structs", type_name(codegen, variable.ty)));
+        }
+        Some(variable) => (variable.name.clone(), variable.ty),
+        None => {return Err(format!("'{ident}' is not declared"));}
+        };
+        if dests.iter().any(|(other, _, _)| *other == ident) {
+            return Err(format!("'{ident}' is assigned more than once"));
+        }
+        dests.push((ident, name, ty));
+
+        match tokens[*index] {
+        Token::Comma => {*index += 1;}
+        _ => {break;}
+        }
+    }
+
+    let names: Vec<&str> = dests.iter().map(|(ident, _, _)| ident.as_str()).collect();
+    let names = names.join(", ");
+    match tokens[*index] {
+    Token::Assign => {*index += 1;}
+    _ => {return Err(format!("expected '=' after '{names}'"));}
+    }
+
+    let identifier = match &tokens[*index] {
+    Token::Ident(identifier) if matches!(tokens[*index + 1], Token::LeftParen) => {
+        *index += 1;
+        identifier.clone()
+    }
+    _ => {return Err(format!("The values of '{names}' must come from a function returning {} values, such as '{names} = f()'", dests.len()));}
+    };
+    let (mut statement, arguments) = parse_arguments(tokens, index, codegen, &identifier)?;
+
+    let returns = codegen.functions[&identifier].returns.clone();
+    if returns.len() != dests.len() {
+        return Err(format!("Function '{identifier}' returns {} values, but {} variables are assigned", returns.len(), dests.len()));
+    }
+    for (i, (ident, _, ty)) in dests.iter().enumerate() {
+        let value = Expression {
+            code : String::from(""),
+            name : String::from(""),
+            ty : returns[i],
+        };
+        check_type(codegen, &value, *ty, &format!("Return value {} of '{identifier}', assigned to '{ident}',", i + 1))?;
+    }
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statements must end with a semicolon"));}
+    }
+
+    let dests: Vec<&str> = dests.iter().map(|(_, name, _)| name.as_str()).collect();
+    statement += &format!("%call {}, {identifier}({})\n", dests.join(", "), arguments.join(", "));
+    Ok(statement)
+}
+
+fn parse_assignment_statement(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    let statement = parse_assignment(tokens, index, codegen)?;
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statement is missing the ';' semicolon"));}
+    }
+
+    return Ok(statement);
+}
+
+// parses an assignment without the semicolon, such as 'a = a + 1', 'a[i] += 2' or 'a++'.
+// compound assignments are lowered straight to the arithmetic instruction, e.g. 'a += b' is '%add a, a, b'.
+fn parse_assignment(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    let mut statement = String::from("");
+
+    // prefix '++a' and '--a'.
+    let prefix_opcode = match tokens[*index] {
+    Token::Increment => Some("%add"),
+    Token::Decrement => Some("%sub"),
+    _ => None,
+    };
+    if prefix_opcode.is_some() {
+        *index += 1;
+    }
+
+    let dest: String;
+    match &tokens[*index] {
+    Token::Ident(ident) => {
+        *index += 1;
+        dest = ident.clone(); // copy ident into variable.
+    }
+    _ => {return Err(String::from("Assignment statements must being with an identifier"));}
+    }
+
+    if find_constant(codegen, &dest).is_some() {
+        return Err(format!("Cannot assign to '{dest}' because it is a constant"));
+    }
+    let (mut name, mut ty, is_array) = match find_variable(codegen, &dest) {
+    Some(variable) => (variable.name.clone(), variable.ty, !variable.dimensions.is_empty()),
+    None => {return Err(format!("'{dest}' is not declared"));}
+    };
+
+    // 'array[index]'. the index is only computed once, even when the element is
+    // read and then written back, such as in 'array[index] += 1'.
+    let mut element_index: Option<String> = None;
+    if matches!(tokens[*index], Token::LeftBracket) {
+        let expression = parse_element_index(tokens, index, codegen, &dest)?;
+        statement += &expression.code;
+        element_index = Some(expression.name);
+    } else if is_array {
+        return Err(format!("'{dest}' is an array. Assign to its elements instead, such as '{dest}[0] = 1'"));
+    }
+
+    // a field of a struct, such as 'p.x = 1' or 'points[i].x += 2', or a whole struct, such as 'p = q'.
+    if let Type::Struct(_) = ty {
+        if matches!(tokens[*index], Token::Dot) {
+            (name, ty) = parse_field(tokens, index, codegen, &dest, &name, ty)?;
+        } else {
+            if prefix_opcode.is_some() || !matches!(tokens[*index], Token::Assign) {
+                return Err(format!("'{dest}' is a struct, so only a whole struct can be assigned to it, such as '{dest} = other'"));
+            }
+            *index += 1;
+            let (code, values) = parse_struct_value(tokens, index, codegen, ty, &format!("The value assigned to '{dest}'"))?;
+            statement += &code;
+            for (value, (field, _)) in values.iter().zip(struct_fields(codegen, &name, ty)) {
+                match &element_index {
+                None => statement += &format!("%mov {field}, {value}\n"),
+                Some(element_index) => statement += &format!("%mov [{field} + {element_index}], {value}\n"),
+                }
+            }
+            return Ok(statement);
+        }
+    }
+
+    let opcode: Option<&str>;
+    let src: String;
+    if let Some(prefix_opcode) = prefix_opcode {
+        opcode = Some(prefix_opcode);
+        src = String::from("1");
+    } else {
+        opcode = match tokens[*index] {
+        Token::Assign => None,
+        Token::PlusAssign | Token::Increment => Some("%add"),
+        Token::SubtractAssign | Token::Decrement => Some("%sub"),
+        Token::MultiplyAssign => Some("%mult"),
+        Token::DivideAssign => Some("%div"),
+        Token::ModulusAssign => Some("%mod"),
+        Token::Comma => {return Err(String::from("Only variables can receive multiple return values, not array elements or struct fields"));}
+        _ => {return Err(String::from("Statement is missing the '=' operator"));}
+        };
+
+        if matches!(tokens[*index], Token::Increment | Token::Decrement) {
+            *index += 1;
+            src = String::from("1");
+        } else {
+            *index += 1;
+            let expression = parse_expression(tokens, index, codegen)?;
+            check_type(codegen, &expression, ty, &format!("The value assigned to '{dest}'"))?;
+            statement += &expression.code;
+            src = expression.name;
+        }
+    }
+
+    // floats use the float instructions, such as '%fadd', and have no remainder.
+    let mut opcode = opcode;
+    if ty == Type::Float && opcode.is_some() {
+        opcode = match opcode.unwrap() {
+        "%add" => Some("%fadd"),
+        "%sub" => Some("%fsub"),
+        "%mult" => Some("%fmult"),
+        "%div" => Some("%fdiv"),
+        _ => {return Err(format!("The operator '%' cannot be used on floats, so '{dest}' cannot use '%='"));}
+        };
+    }
+    if opcode.is_some() && !is_integer(ty) && ty != Type::Float {
+        return Err(format!("'{dest}' is a {}, so it cannot be used in arithmetic", type_name(codegen, ty)));
+    }
+
+    match (opcode, element_index) {
+    (None, None) => {
+        statement += &format!("%mov {name}, {src}\n");
+    }
+    (None, Some(element_index)) => {
+        statement += &format!("%mov [{name} + {element_index}], {src}\n");
+    }
+    (Some(opcode), None) => {
+        statement += &format!("{opcode} {name}, {name}, {src}\n");
+    }
+    (Some(opcode), Some(element_index)) => {
+        let temp = create_temp();
+        statement += &format!("{} {temp}\n", ir_declaration(ty));
+        statement += &format!("%mov {temp}, [{name} + {element_index}]\n");
+        statement += &format!("{opcode} {temp}, {temp}, {src}\n");
+        statement += &format!("%mov [{name} + {element_index}], {temp}\n");
+    }
+    }
+
+    return Ok(statement);
+}
+
+// parses the indices of an array element such as 'array[i]' or 'grid[i][j]', and computes
+// where the element is in the '%int[]'. arrays are stored row by row, so in 'int [4][5] grid',
+// 'grid[i][j]' is element 'i * 5 + j'. every index of a multi-dimensional array is checked
+// with '%bounds', so that 'grid[0][7]' is an error instead of reading 'grid[1][2]'.
+fn parse_element_index(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator, ident: &str) -> Result<Expression, String> {
+    let dimensions = match find_variable(codegen, ident) {
+    Some(variable) if variable.ty == Type::Str => {return Err(format!("'{ident}' is a string, and the characters of a string cannot be assigned to"));}
+    Some(variable) if variable.dimensions.is_empty() && matches!(variable.ty, Type::Struct(_)) => {return Err(format!("'{ident}' is a struct, not an array"));}
+    Some(variable) => variable.dimensions.clone(),
+    None => {return Err(format!("'{ident}' is not declared"));}
+    };
+    if dimensions.is_empty() {
+        return Err(format!("'{ident}' is an integer, not an array"));
+    }
+
+    let mut element: Option<Expression> = None;
+    for (dimension, size) in dimensions.iter().enumerate() {
+        match tokens[*index] {
+        Token::LeftBracket => {*index += 1;}
+        _ => {return Err(format!("'{ident}' has {} dimensions, but only {} indices were given", dimensions.len(), dimension));}
+        }
+
+        let mut subscript = parse_expression(tokens, index, codegen)?;
+        check_type(codegen, &subscript, Type::Int, "An array index")?;
+        match tokens[*index] {
+        Token::RightBracket => {*index += 1;}
+        _ => {return Err(String::from("missing right bracket ']'"));}
+        }
+
+        if dimensions.len() > 1 {
+            if let Some(value) = constant_value(&subscript) {
+                if value < 0 || value >= *size as i64 {
+                    return Err(format!("Index {value} is out of bounds for dimension {} of '{ident}', which has length {size}", dimension + 1));
+                }
+            } else {
+                subscript.code += &format!("%bounds {}, {size}\n", subscript.name);
+            }
+        }
+
+        element = match element {
+        None => Some(subscript),
+        Some(row) => {
+            let size = Expression {
+                code : String::from(""),
+                name : size.to_string(),
+                ty : Type::Int,
+            };
+            Some(binary_expression("%add", binary_expression("%mult", row, size), subscript))
+        }
+        };
+    }
+
+    if matches!(tokens[*index], Token::LeftBracket) {
+        return Err(format!("'{ident}' has {} dimensions, but more indices were given", dimensions.len()));
+    }
+    Ok(element.unwrap())
+}
+
+fn parse_return_statement(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    match tokens[*index] {
+    Token::Return => {*index += 1;}
+    _ => {return Err(String::from("Return statements must being with a return keyword"));}
+    }
+
+    // a function returning several values returns them all, such as 'return q, r;'.
+    let function = codegen.function.clone();
+    let returns = codegen.functions[&function].returns.clone();
+    if returns.is_empty() {
+        match tokens[*index] {
+        Token::Semicolon => {*index += 1;}
+        _ => {return Err(format!("Function '{function}' does not return a value, so 'return' cannot have one. Declare it like 'func {function}() -> int'"));}
+        }
+        return Ok(String::from("%ret\n"));
+    }
+    if matches!(tokens[*index], Token::Semicolon) {
+        return Err(format!("Function '{function}' returns a value, so 'return' must have one"));
+    }
+
+    let mut statement = String::from("");
+    let mut values: Vec<String> = vec![];
+    loop {
+        let expression = parse_expression(tokens, index, codegen)?;
+        if values.len() < returns.len() {
+            let what = if returns.len() == 1 { format!("The return value of '{function}'") } else { format!("Return value {} of '{function}'", values.len() + 1) };
+            check_type(codegen, &expression, returns[values.len()], &what)?;
+        }
+        statement += &expression.code;
+        if values.len() < returns.len() {
+            let (copy, name) = constant_to_variable(codegen, &expression, returns[values.len()]);
+            statement += &copy;
+            values.push(name);
+        } else {
+            values.push(expression.name);
+        }
+
+        match tokens[*index] {
+        Token::Comma => {*index += 1;}
+        _ => {break;}
+        }
+    }
+    if values.len() != returns.len() {
+        return Err(format!("Function '{function}' returns {} values, but {} were returned", returns.len(), values.len()));
+    }
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statement is missing the ';' semicolon"));}
+    }
+
+    statement += &format!("%ret {}\n", values.join(", "));
+    Ok(statement)
+}
+
+fn parse_print_statement(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    let expression: Expression;
+    match tokens[*index] {
+    Token::Print=> {*index += 1;}
+    _ => {return Err(String::from("Print statements must being with a return keyword"));}
+    }
+
+    match parse_expression(tokens, index, codegen) {
+    Ok(expr) => {
+        expression = expr;
+    },
+    Err(e) => {return Err(e);}
+    }
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statement is missing the ';' semicolon"));}
+    }
+
+    // chars and strings are printed as they are, without a newline.
+    let mut statement = expression.code;
+    match expression.ty {
+    Type::Char => statement += &format!("%outc {}\n", expression.name),
+    Type::Str => statement += &format!("%outs {}\n", expression.name),
+    Type::Float => statement += &format!("%outf {}\n", expression.name),
+    _ => statement += &format!("%out {}\n", expression.name),
+    }
+    return Ok(statement);
+}
+
+fn parse_read_statement(tokens: &[Token], index: &mut usize, codegen: &mut CodeGenerator) -> Result<String, String> {
+    match tokens[*index] {
+    Token::Read => {*index += 1;}
+    _ => {return Err(String::from("Read statements must being with a 'read' keyword"));}
+    }
+
+    // 'read x;' reads a number into an int, a long or a float, and 'read s;' reads a whole line into a string.
+    let ident = match &tokens[*index] {
+    Token::Ident(ident) => {
+        *index += 1;
+        ident
+    }
+    _ => {return Err(String::from("Read statements read into a variable, such as 'read x;'"));}
+    };
+    if find_constant(codegen, ident).is_some() {
+        return Err(format!("Cannot read into '{ident}' because it is a constant"));
+    }
+    let name = variable_name(codegen, ident)?;
+    let variable = find_variable(codegen, ident).unwrap();
+    if !variable.dimensions.is_empty() {
+        return Err(format!("'{ident}' is an array. Read into a variable instead"));
+    }
+    if !matches!(variable.ty, Type::Int | Type::Long | Type::Float | Type::Str) {
+        return Err(format!("Only ints, longs, floats and strings can be read, not {}", type_name(codegen, variable.ty)));
+    }
+
+    match tokens[*index] {
+    Token::Semicolon => {*index += 1;}
+    _ => {return Err(String::from("Statement is missing the ';' semicolon"));}
+    }
+
+    Ok(format!("%input {name}\n"))
+}
+
+// expressions follow the C operator precedence, from lowest to highest:
+//   ?:
+//   ||
+//   &&
+//   |
+//   ^
+//   &
+//   ==  !=
+//   <  <=  >  >=
+//   <<  >>
+//   +  -
+//   *  /  %
+//   unary -  ~  !
+// comparisons, '&&', '||' and '!' are bools, and every other operator has the type of its operands.
+fn parse_expression(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    let condition = parse_logical_or_expression(tokens, index, codegen)?;
+    if !matches!(tokens[*index], Token::Question) {
+        return Ok(condition);
+    }
+    *index += 1;
+    check_type(codegen, &condition, Type::Bool, "The condition of '?:'")?;
+
+    let if_true = parse_expression(tokens, index, codegen)?;
+    match tokens[*index] {
+    Token::Colon => {*index += 1;}
+    _ => {return Err(String::from("expected ':' in the conditional expression"));}
+    }
+    let if_false = parse_expression(tokens, index, codegen)?;
+    if mixes_int_and_long(&if_true, &if_false) {
+        return Err(String::from("Both sides of '?:' must have the same type. Cast the int with '(long)' first"));
+    }
+    if mixes_float(&if_true, &if_false) {
+        return Err(format!("Both sides of '?:' must have the same type, not {} and {}. Convert with '(float)' first", type_name(codegen, if_true.ty), type_name(codegen, if_false.ty)));
+    }
+    let is_number = |ty: Type| is_integer(ty) || ty == Type::Float;
+    if if_true.ty != if_false.ty && !(is_number(if_true.ty) && is_number(if_false.ty)) {
+        return Err(format!("Both sides of '?:' must have the same type, not {} and {}", type_name(codegen, if_true.ty), type_name(codegen, if_false.ty)));
+    }
+    let ty = if if_true.ty == Type::Float || if_true.ty == Type::Long && if_false.ty != Type::Float { if_true.ty } else { if_false.ty };
+
+    // a constant condition picks the arm at compile time. the arm still has the type of the
+    // whole expression, so an int constant chosen over a long is a long, and over a float is a float.
+    if let Some(value) = constant_value(&condition) {
+        let mut chosen = if value != 0 { if_true } else { if_false };
+        if ty == Type::Float && chosen.ty != Type::Float {
+            chosen.name = format_float(float_value(&chosen).unwrap());
+        }
+        chosen.ty = ty;
+        return Ok(chosen);
+    }
+
+    // only the chosen arm is evaluated.
+    let label_num = create_label_num();
+    let false_label = format!(":ternary_false{label_num}");
+    let end_label = format!(":endternary{label_num}");
+    let dest = create_temp();
+    let mut code = condition.code;
+    code += &format!("{} {dest}\n", ir_declaration(ty));
+    code += &format!("%branch_ifn {}, {false_label}\n", condition.name);
+    code += &if_true.code;
+    code += &format!("%mov {dest}, {}\n", if_true.name);
+    code += &format!("%jmp {end_label}\n");
+    code += &format!("{false_label}\n");
+    code += &if_false.code;
+    code += &format!("%mov {dest}, {}\n", if_false.name);
+    code += &format!("{end_label}\n");
+    Ok(Expression {
+        code,
+        name : dest,
+        ty,
+    })
+}
+
+fn parse_logical_or_expression(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    let mut expression = parse_logical_and_expression(tokens, index, codegen)?;
+    while matches!(tokens[*index], Token::LogicalOr) {
+        *index += 1;
+        let expr2 = parse_logical_and_expression(tokens, index, codegen)?;
+        expression = logical_expression(codegen, "||", expression, expr2)?;
+    }
+    Ok(expression)
+}
+
+fn parse_logical_and_expression(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    let mut expression = parse_or_expression(tokens, index, codegen)?;
+    while matches!(tokens[*index], Token::LogicalAnd) {
+        *index += 1;
+        let expr2 = parse_or_expression(tokens, index, codegen)?;
+        expression = logical_expression(codegen, "&&", expression, expr2)?;
+    }
+    Ok(expression)
+}
+
+// 'a && b' and 'a || b'. the right side is only evaluated when the left side does not
+// already decide the result, so 'i < n && a[i] > 0' never reads outside of the array.
+fn logical_expression(codegen: &CodeGenerator, operator: &str, expr1: Expression, expr2: Expression) -> Result<Expression, String> {
+    if expr1.ty != Type::Bool || expr2.ty != Type::Bool {
+        return Err(format!("The operator '{operator}' needs bool operands, not {} and {}", type_name(codegen, expr1.ty), type_name(codegen, expr2.ty)));
+    }
+
+    // 'true || b' and 'false && b' are decided by the left side alone.
+    let decided_by = if operator == "&&" { 0 } else { 1 };
+    if let Some(value) = constant_value(&expr1) {
+        return Ok(if value == decided_by { expr1 } else { expr2 });
+    }
+
+    let label_num = create_label_num();
+    let end_label = if operator == "&&" { format!(":endand{label_num}") } else { format!(":endor{label_num}") };
+    let branch = if operator == "&&" { "%branch_ifn" } else { "%branch_if" };
+    let dest = create_temp();
+    let mut code = expr1.code;
+    code += &format!("%int {dest}\n");
+    code += &format!("%mov {dest}, {}\n", expr1.name);
+    code += &format!("{branch} {dest}, {end_label}\n");
+    code += &expr2.code;
+    code += &format!("%mov {dest}, {}\n", expr2.name);
+    code += &format!("{end_label}\n");
+    Ok(Expression {
+        code,
+        name : dest,
+        ty : Type::Bool,
+    })
+}
+
+fn parse_or_expression(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    let mut expression = parse_xor_expression(tokens, index, codegen)?;
+    while matches!(tokens[*index], Token::BitOr) {
+        *index += 1;
+        let expr2 = parse_xor_expression(tokens, index, codegen)?;
+        expression = operator_expression(codegen, "%or", expression, expr2)?;
+    }
+    Ok(expression)
+}
+
+fn parse_xor_expression(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    let mut expression = parse_and_expression(tokens, index, codegen)?;
+    while matches!(tokens[*index], Token::BitXor) {
+        *index += 1;
+        let expr2 = parse_and_expression(tokens, index, codegen)?;
+        expression = operator_expression(codegen, "%xor", expression, expr2)?;
+    }
+    Ok(expression)
+}
+
+fn parse_and_expression(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    let mut expression = parse_equality_expression(tokens, index, codegen)?;
+    while matches!(tokens[*index], Token::BitAnd) {
+        *index += 1;
+        let expr2 = parse_equality_expression(tokens, index, codegen)?;
+        expression = operator_expression(codegen, "%and", expression, expr2)?;
+    }
+    Ok(expression)
+}
+
+fn parse_equality_expression(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    let mut expression = parse_comparison_expression(tokens, index, codegen)?;
+    loop {
+        let opcode = match tokens[*index] {
+        Token::Equality => "%eq",
+        Token::NotEqual => "%neq",
+        _ => break,
+        };
+        *index += 1;
+        let expr2 = parse_comparison_expression(tokens, index, codegen)?;
+        expression = operator_expression(codegen, opcode, expression, expr2)?;
+    }
+    Ok(expression)
+}
+
+fn parse_comparison_expression(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    let mut expression = parse_shift_expression(tokens, index, codegen)?;
+    loop {
+        let opcode = match tokens[*index] {
+        Token::Less => "%lt",
+        Token::LessEqual => "%le",
+        Token::Greater => "%gt",
+        Token::GreaterEqual => "%ge",
+        _ => break,
+        };
+        *index += 1;
+        let expr2 = parse_shift_expression(tokens, index, codegen)?;
+        expression = operator_expression(codegen, opcode, expression, expr2)?;
+    }
+    Ok(expression)
+}
+
+fn parse_shift_expression(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    let mut expression = parse_add_expression(tokens, index, codegen)?;
+    loop {
+        let opcode = match tokens[*index] {
+        Token::ShiftLeft => "%shl",
+        Token::ShiftRight => "%shr",
+        _ => break,
+        };
+        *index += 1;
+        let expr2 = parse_add_expression(tokens, index, codegen)?;
+        expression = operator_expression(codegen, opcode, expression, expr2)?;
+    }
+    Ok(expression)
+}
+
+// type checks an operator in the program, then generates it with 'binary_expression'.
+// '==' and '!=' compare two values of the same type, and every other operator needs ints or longs.
+// the amount of a shift is always allowed to be an int.
+fn operator_expression(codegen: &CodeGenerator, opcode: &str, expr1: Expression, expr2: Expression) -> Result<Expression, String> {
+    let is_comparison = matches!(opcode, "%lt" | "%le" | "%gt" | "%ge" | "%eq" | "%neq");
+    if expr1.ty == Type::Str && expr2.ty == Type::Str && is_comparison {
+        return Ok(string_comparison(opcode, expr1, expr2));
+    }
+    if opcode == "%add" && (expr1.ty == Type::Str || expr2.ty == Type::Str) {
+        return string_concatenation(codegen, expr1, expr2);
+    }
+    if expr1.ty == Type::Float || expr2.ty == Type::Float {
+        return float_expression(codegen, opcode, expr1, expr2);
+    }
+
+    let symbol = operator_symbol(opcode);
+    let is_shift = matches!(opcode, "%shl" | "%shr");
+    if !is_shift && mixes_int_and_long(&expr1, &expr2) {
+        return Err(format!("The operator '{symbol}' cannot mix int and long. Cast the int with '(long)' first"));
+    }
+
+    if matches!(opcode, "%eq" | "%neq") {
+        if expr1.ty != expr2.ty && !(is_integer(expr1.ty) && is_integer(expr2.ty)) {
+            return Err(format!("Cannot compare {} with {}", type_name(codegen, expr1.ty), type_name(codegen, expr2.ty)));
+        }
+    } else if matches!(opcode, "%lt" | "%le" | "%gt" | "%ge") && expr1.ty == expr2.ty && matches!(expr1.ty, Type::Char | Type::Enum(_)) {
+        // chars are ordered by their unicode value, and variants by the order they are declared in.
+    } else if !is_integer(expr1.ty) || !is_integer(expr2.ty) {
+        return Err(format!("The operator '{symbol}' needs int operands, not {} and {}", type_name(codegen, expr1.ty), type_name(codegen, expr2.ty)));
+    }
+    Ok(binary_expression(opcode, expr1, expr2))
+}
+
+// how an operator is written in the program, for error messages.
+fn operator_symbol(opcode: &str) -> &'static str {
+    match opcode {
+    "%add" => "+",
+    "%sub" => "-",
+    "%mult" => "*",
+    "%div" => "/",
+    "%mod" => "%",
+    "%and" => "&",
+    "%or" => "|",
+    "%xor" => "^",
+    "%shl" => "<<",
+    "%shr" => ">>",
+    "%lt" => "<",
+    "%le" => "<=",
+    "%gt" => ">",
+    "%ge" => ">=",
+    "%eq" => "==",
+    _ => "!=",
+    }
+}
+
+fn is_integer(ty: Type) -> bool {
+    matches!(ty, Type::Int | Type::Long)
+}
+
+// an int constant can be used as a long, such as the '1' in 'n + 1', but any other int
+// must be cast with '(long)' before it is used together with a long.
+fn mixes_int_and_long(expr1: &Expression, expr2: &Expression) -> bool {
+    match (expr1.ty, expr2.ty) {
+    (Type::Int, Type::Long) => constant_value(expr1).is_none(),
+    (Type::Long, Type::Int) => constant_value(expr2).is_none(),
+    _ => false,
+    }
+}
+
+// like a long, an int or long constant can be used as a float, such as the '2' in 'x / 2',
+// but any other integer must be converted with '(float)' first.
+fn mixes_float(expr1: &Expression, expr2: &Expression) -> bool {
+    match (expr1.ty, expr2.ty) {
+    (Type::Float, ty) if is_integer(ty) => constant_value(expr2).is_none(),
+    (ty, Type::Float) if is_integer(ty) => constant_value(expr1).is_none(),
+    _ => false,
+    }
+}
+
+// arithmetic and comparisons on floats use the float instructions, such as '%fadd' and '%flt'.
+// there is no remainder, bitwise operator or shift on floats.
+fn float_expression(codegen: &CodeGenerator, opcode: &str, expr1: Expression, expr2: Expression) -> Result<Expression, String> {
+    let symbol = operator_symbol(opcode);
+    if mixes_float(&expr1, &expr2) {
+        let other = if expr1.ty == Type::Float { expr2.ty } else { expr1.ty };
+        return Err(format!("The operator '{symbol}' cannot mix float and {}. Convert it with '(float)' first", type_name(codegen, other)));
+    }
+    let is_number = |ty: Type| is_integer(ty) || ty == Type::Float;
+    if !is_number(expr1.ty) || !is_number(expr2.ty) {
+        return Err(format!("The operator '{symbol}' needs float operands, not {} and {}", type_name(codegen, expr1.ty), type_name(codegen, expr2.ty)));
+    }
+
+    let float_opcode = match opcode {
+    "%add" => "%fadd",
+    "%sub" => "%fsub",
+    "%mult" => "%fmult",
+    "%div" => "%fdiv",
+    "%lt" => "%flt",
+    "%le" => "%fle",
+    "%gt" => "%fgt",
+    "%ge" => "%fge",
+    "%eq" => "%feq",
+    "%neq" => "%fneq",
+    _ => { return Err(format!("The operator '{symbol}' cannot be used on floats")); }
+    };
+    let ty = if matches!(opcode, "%lt" | "%le" | "%gt" | "%ge" | "%eq" | "%neq") { Type::Bool } else { Type::Float };
+
+    // results that are not finite, such as '1.0 / 0.0', are left for the interpreter.
+    if let (Some(a), Some(b)) = (float_value(&expr1), float_value(&expr2)) {
+        let value = match opcode {
+        "%add" => a + b,
+        "%sub" => a - b,
+        "%mult" => a * b,
+        "%div" => a / b,
+        "%lt" => (a < b) as i64 as f64,
+        "%le" => (a <= b) as i64 as f64,
+        "%gt" => (a > b) as i64 as f64,
+        "%ge" => (a >= b) as i64 as f64,
+        "%eq" => (a == b) as i64 as f64,
+        _ => (a != b) as i64 as f64,
+        };
+        if value.is_finite() {
+            return Ok(Expression {
+                code : String::from(""),
+                name : if ty == Type::Bool { (value as i64).to_string() } else { format_float(value) },
+                ty,
+            });
+        }
+    }
+
+    let dest = create_temp();
+    let mut code = expr1.code;
+    code += &expr2.code;
+    code += &format!("{} {dest}\n", ir_declaration(ty));
+    code += &format!("{float_opcode} {dest}, {}, {}\n", expr1.name, expr2.name);
+    Ok(Expression {
+        code,
+        name : dest,
+        ty,
+    })
+}
+
+// the value of a float, int or long if it is known at compile time.
+fn float_value(expression: &Expression) -> Option<f64> {
+    if expression.ty != Type::Float {
+        return if is_integer(expression.ty) { constant_value(expression).map(|value| value as f64) } else { None };
+    }
+    // variable names are never numbers, but names such as 'inf' and 'nan' would parse as floats.
+    let is_number = expression.name.starts_with(|c: char| c.is_ascii_digit() || c == '-');
+    if expression.code.is_empty() && is_number {
+        expression.name.parse::<f64>().ok()
+    } else {
+        None
+    }
+}
+
+// writes a float the same way the interpreter prints it: the shortest form that reads back as
+// the same float, always with a '.' or an exponent, such as '2.0', '0.1' or '1e-7'.
+fn format_float(value: f64) -> String {
+    format!("{:?}", value)
+}
+
+// strings are compared one character at a time, so "apple" < "banana" and "ab" < "abc".
+// '%strcmp' gives -1, 0 or 1, which is then compared with 0.
+fn string_comparison(opcode: &str, expr1: Expression, expr2: Expression) -> Expression {
+    let order = create_temp();
+    let mut code = expr1.code;
+    code += &expr2.code;
+    code += &format!("%int {order}\n");
+    code += &format!("%strcmp {order}, {}, {}\n", expr1.name, expr2.name);
+    let order = Expression {
+        code,
+        name : order,
+        ty : Type::Int,
+    };
+    let zero = Expression {
+        code : String::from(""),
+        name : String::from("0"),
+        ty : Type::Int,
+    };
+    binary_expression(opcode, order, zero)
+}
+
+// 'a + b' joins two strings. a char can be added to either side of a string, such as 's + '!''.
+fn string_concatenation(codegen: &CodeGenerator, expr1: Expression, expr2: Expression) -> Result<Expression, String> {
+    let mut code = String::from("");
+    let mut operands: Vec<String> = vec![];
+    for expression in [&expr1, &expr2] {
+        code += &expression.code;
+        match expression.ty {
+        Type::Str => operands.push(expression.name.clone()),
+        Type::Char => {
+            let temp = create_temp();
+            code += &format!("%string {temp}\n");
+            code += &format!("%chr {temp}, {}\n", expression.name);
+            operands.push(temp);
+        }
+        _ => { return Err(format!("The operator '+' can only join a string with a string or a char, not {} and {}", type_name(codegen, expr1.ty), type_name(codegen, expr2.ty))); }
+        }
+    }
+
+    let dest = create_temp();
+    code += &format!("%string {dest}\n");
+    code += &format!("%concat {dest}, {}, {}\n", operands[0], operands[1]);
+    Ok(Expression {
+        code,
+        name : dest,
+        ty : Type::Str,
+    })
+}
+
+// generates 'dest = expr1 opcode expr2' into a new temporary. the interpreter computes in
+// 64 bits and wraps the result to the width of 'dest', so the result is a long if either
+// operand is a long. a shift has the type of the value being shifted.
+fn binary_expression(opcode: &str, expr1: Expression, expr2: Expression) -> Expression {
+    let ty = match opcode {
+    "%lt" | "%le" | "%gt" | "%ge" | "%eq" | "%neq" => Type::Bool,
+    "%shl" | "%shr" if expr1.ty == Type::Long => Type::Long,
+    "%shl" | "%shr" => Type::Int,
+    _ if expr1.ty == Type::Long || expr2.ty == Type::Long => Type::Long,
+    _ => Type::Int,
+    };
+
+    // numbers and constants are evaluated at compile time.
+    if let (Some(a), Some(b)) = (constant_value(&expr1), constant_value(&expr2)) {
+        if let Some(value) = fold_constant(opcode, a, b) {
+            return Expression {
+                code : String::from(""),
+                name : wrap_constant(value, ty).to_string(),
+                ty,
+            };
+        }
+    }
+
+    let dest = create_temp();
+    let mut code = expr1.code;
+    code += &expr2.code;
+    code += &format!("{} {dest}\n", ir_declaration(ty));
+    code += &format!("{opcode} {dest}, {}, {}\n", expr1.name, expr2.name);
+    Expression {
+        code,
+        name : dest,
+        ty,
+    }
+}
+
+// checks the type of an expression, such as the condition of an if statement.
+// an int widens to a long without a cast, since every int fits in a long. an int or long
+// constant can be used as a float, but other integers are converted with '(float)'.
+fn check_type(codegen: &CodeGenerator, expression: &Expression, ty: Type, what: &str) -> Result<(), String> {
+    let widens = (ty == Type::Long && expression.ty == Type::Int)
+        || (ty == Type::Float && is_integer(expression.ty) && constant_value(expression).is_some());
+    if expression.ty != ty && !widens {
+        if ty == Type::Int && expression.ty == Type::Long {
+            return Err(format!("{what} must be int, not long. Narrow it with '(int)' first"));
+        }
+        if ty == Type::Float && is_integer(expression.ty) {
+            return Err(format!("{what} must be float, not {}. Convert it with '(float)' first", type_name(codegen, expression.ty)));
+        }
+        if ty == Type::Int && matches!(expression.ty, Type::Enum(_)) {
+            return Err(format!("{what} must be int, not {}. Convert it with '(int)' first", type_name(codegen, expression.ty)));
+        }
+        if is_integer(ty) && expression.ty == Type::Float {
+            return Err(format!("{what} must be {}, not float. Convert it with '({})' first", type_name(codegen, ty), type_name(codegen, ty)));
+        }
+        return Err(format!("{what} must be {}, not {}", type_name(codegen, ty), type_name(codegen, expression.ty)));
+    }
+    Ok(())
+}
+
+// the value of an expression if it is known at compile time, i.e. the expression is just a number.
+fn constant_value(expression: &Expression) -> Option<i64> {
+    if expression.code.is_empty() {
+        expression.name.parse::<i64>().ok()
+    } else {
+        None
+    }
+}
+
+// evaluates an operator the same way the interpreter does at runtime, in 64 bits.
+// 'wrap_constant' then wraps the result to the width of its type.
+// division by zero and negative shifts are left for the interpreter to report.
+fn fold_constant(opcode: &str, a: i64, b: i64) -> Option<i64> {
+    match opcode {
+    "%add" => Some(a.wrapping_add(b)),
+    "%sub" => Some(a.wrapping_sub(b)),
+    "%mult" => Some(a.wrapping_mul(b)),
+    "%div" if b != 0 => Some(a.wrapping_div(b)),
+    "%mod" if b != 0 => Some(a.wrapping_rem(b)),
+    "%and" => Some(a & b),
+    "%or" => Some(a | b),
+    "%xor" => Some(a ^ b),
+    "%shl" if b >= 0 => Some(if b >= 64 { 0 } else { a << b }),
+    "%shr" if b >= 0 => Some(a >> b.min(63)),
+    "%lt" => Some((a < b) as i64),
+    "%le" => Some((a <= b) as i64),
+    "%gt" => Some((a > b) as i64),
+    "%ge" => Some((a >= b) as i64),
+    "%eq" => Some((a == b) as i64),
+    "%neq" => Some((a != b) as i64),
+    _ => None,
+    }
+}
+
+// wraps a constant to 32 bits, unless it is a long.
+fn wrap_constant(value: i64, ty: Type) -> i64 {
+    if ty == Type::Long { value } else { value as i32 as i64 }
+}
+
+// parsing complex expressions such as: "a + b - (c * d) / (f + g - 8);
+fn parse_add_expression(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    let mut expression: Expression;
+    match parse_multiply_expression(tokens, index, codegen) {
+    Ok(expr) => {
+        expression = expr;
+    },
+    Err(e) => {return Err(e);}
+    }
+    loop {
+       match tokens[*index] {
+
+       Token::Plus => {
+           *index += 1;
+           let expr2 = parse_multiply_expression(tokens, index, codegen)?;
+           expression = operator_expression(codegen, "%add", expression, expr2)?;
+       }
+
+       Token::Subtract => {
+           *index += 1;
+           let expr2 = parse_multiply_expression(tokens, index, codegen)?;
+           expression = operator_expression(codegen, "%sub", expression, expr2)?;
+       }
+
+       _ => { 
+           break;
+       }
+
+       };
+    }
+
+    return Ok(expression);
+}
+
+fn parse_multiply_expression(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    let mut expression: Expression;
+    match parse_term(tokens, index, codegen) {
+    Ok(expr) => {
+        expression = expr;
+    },
+    Err(e) => {return Err(e);}
+    }
+    loop {
+       match tokens[*index] {
+       Token::Multiply => {
+          *index += 1;
+          let expr2 = parse_term(tokens, index, codegen)?;
+          expression = operator_expression(codegen, "%mult", expression, expr2)?;
+       }
+
+       Token::Divide => {
+          *index += 1;
+          let expr2 = parse_term(tokens, index, codegen)?;
+          expression = operator_expression(codegen, "%div", expression, expr2)?;
+       }
+
+       Token::Modulus => {
+          *index += 1;
+          let expr2 = parse_term(tokens, index, codegen)?;
+          expression = operator_expression(codegen, "%mod", expression, expr2)?;
+       }
+  
+       _ => {
+           break;
+       }
+
+       };
+
+    }
+
+    return Ok(expression);
+}
+
+// a term is either a Number, 'true' or 'false', an Identifier, an array element, a negated or
+// complemented term, or a parenthesized expression.
+fn parse_term(tokens: &Vec<Token>, index: &mut usize, codegen: &mut CodeGenerator) -> Result<Expression, String> {
+    match &tokens[*index] {
+
+    Token::Ident(identifier) => {
+        *index += 1;
+
+        // calling a function such as 'add(a, b + 1)'. every argument must have the type of its parameter.
+        if matches!(tokens[*index], Token::LeftParen) {
+            let (mut code, arguments) = parse_arguments(tokens, index, codegen, identifier)?;
+
+            // a function returning several values can only be called by assigning all of them.
+            let returns = &codegen.functions[identifier].returns;
+            if returns.is_empty() {
+                return Err(format!("Function '{identifier}' does not return a value, so it cannot be used in an expression"));
+            }
+            if returns.len() != 1 {
+                let names: Vec<String> = (1..=returns.len()).map(|i| format!("v{i}")).collect();
+                return Err(format!("Function '{identifier}' returns {} values, so it must be called like '{} = {identifier}(...)'", returns.len(), names.join(", ")));
+            }
+            let ty = returns[0];
+            let dest = create_temp();
+            code += &format!("{} {dest}\n", ir_declaration(ty));
+            code += &format!("%call {dest}, {identifier}({})\n", arguments.join(", "));
+            let expression = Expression {
+                code,
+                name : dest,
+                ty,
+            };
+            return Ok(expression);
+        }
+
+        // named constants and the variants of enums are replaced by their value.
+        if let Some(value) = find_constant(codegen, identifier) {
+            let expression = Expression {
+                code : String::from(""),
+                name : value.to_string(),
+                ty : constant_type(codegen, identifier),
+            };
+            return Ok(expression);
+        }
+
+        let (name, ty, is_array) = match find_variable(codegen, identifier) {
+        Some(variable) => (variable.name.clone(), variable.ty, !variable.dimensions.is_empty()),
+        None => { return Err(format!("'{identifier}' is not declared")); }
+        };
+
+        // a field of a struct, such as 'p.x' or 'points[i].x'.
+        if let Type::Struct(_) = ty {
+            let mut code = String::from("");
+            let mut element_index: Option<String> = None;
+            if is_array {
+                if !matches!(tokens[*index], Token::LeftBracket) {
+                    return Err(format!("'{identifier}' is an array. Use one of its elements instead, such as '{identifier}[0]'"));
+                }
+                let element = parse_element_index(tokens, index, codegen, identifier)?;
+                code += &element.code;
+                element_index = Some(element.name);
+            }
+            if !matches!(tokens[*index], Token::Dot) {
+                let first = &struct_declaration(codegen, ty).fields[0].name;
+                return Err(format!("'{identifier}' is a {}. Use one of its fields instead, such as '{identifier}.{first}'", type_name(codegen, ty)));
+            }
+            let (field, field_ty) = parse_field(tokens, index, codegen, identifier, &name, ty)?;
+
+            let name = match element_index {
+            None => field,
+            Some(element_index) => {
+                let dest = create_temp();
+                code += &format!("{} {dest}\n", ir_declaration(field_ty));
+                code += &format!("%mov {dest}, [{field} + {element_index}]\n");
+                dest
+            }
+            };
+            let expression = Expression {
+                code,
+                name,
+                ty : field_ty,
+            };
+            return Ok(expression);
+        }
+
+        // the character at an index of a string, such as 'name[0]'.
+        if ty == Type::Str && matches!(tokens[*index], Token::LeftBracket) {
+            *index += 1;
+            let subscript = parse_expression(tokens, index, codegen)?;
+            check_type(codegen, &subscript, Type::Int, "A string index")?;
+            match tokens[*index] {
+            Token::RightBracket => {*index += 1;}
+            _ => {return Err(String::from("missing right bracket ']'"));}
+            }
+
+            let dest = create_temp();
+            let mut code = subscript.code;
+            code += &format!("%int {dest}\n");
+            code += &format!("%charat {dest}, {name}, {}\n", subscript.name);
+            let expression = Expression {
+                code,
+                name : dest,
+                ty : Type::Char,
+            };
+            return Ok(expression);
+        }
+
+        // reading an element of an array such as 'array[i + 1]' or 'grid[i][j]'
+        if matches!(tokens[*index], Token::LeftBracket) {
+            let element_index = parse_element_index(tokens, index, codegen, identifier)?;
+
+            let dest = create_temp();
+            let mut code = element_index.code;
+            code += &format!("{} {dest}\n", ir_declaration(ty));
+            code += &format!("%mov {dest}, [{name} + {}]\n", element_index.name);
+            let expression = Expression {
+                code,
+                name : dest,
+                ty,
+            };
+            return Ok(expression);
+        }
+
+        if is_array {
+            return Err(format!("'{identifier}' is an array. Use one of its elements instead, such as '{identifier}[0]'"));
+        }
+        let expression = Expression {
+            code : String::from(""),
+            name,
+            ty,
+        };
+        return Ok(expression);
+    }
+
+    // the length of an array or a string. the length of an array parameter is only known at runtime.
+    Token::Len => {
+        *index += 1;
+        match tokens[*index] {
+        Token::LeftParen => {*index += 1;}
+        _ => { return Err(String::from("expected '(' after 'len', such as 'len(array)'")); }
+        }
+
+        let is_array = match &tokens[*index] {
+        Token::Ident(ident) => matches!(find_variable(codegen, ident), Some(variable) if !variable.dimensions.is_empty()),
+        _ => false,
+        };
+        if !is_array {
+            let string = parse_expression(tokens, index, codegen)?;
+            if string.ty != Type::Str {
+                return Err(format!("len() takes an array or a string, not {}", type_name(codegen, string.ty)));
+            }
+            match tokens[*index] {
+            Token::RightParen => {*index += 1;}
+            _ => { return Err(String::from("missing right parenthesis ')'")); }
+            }
+
+            let dest = create_temp();
+            let mut code = string.code;
+            code += &format!("%int {dest}\n");
+            code += &format!("%len {dest}, {}\n", string.name);
+            let expression = Expression {
+                code,
+                name : dest,
+                ty : Type::Int,
+            };
+            return Ok(expression);
+        }
+
+        let ident = match &tokens[*index] {
+        Token::Ident(ident) => {
+            *index += 1;
+            ident
+        }
+        _ => { return Err(String::from("len() takes the name of an array, such as 'len(array)'")); }
+        };
+
+        match tokens[*index] {
+        Token::RightParen => {*index += 1;}
+        _ => { return Err(String::from("missing right parenthesis ')'")); }
+        }
+
+        // an array of structs has the length of the array of its first field.
+        let (name, length) = match find_variable(codegen, ident) {
+        Some(variable) if matches!(variable.ty, Type::Struct(_)) => (struct_fields(codegen, &variable.name, variable.ty).remove(0).0, variable.dimensions[0]),
+        Some(variable) => (variable.name.clone(), variable.dimensions[0]),
+        None => { return Err(format!("len() of '{ident}', which has not been declared")); }
+        };
+
+        if length > 0 {
+            let expression = Expression {
+                code : String::from(""),
+                name : length.to_string(),
+                ty : Type::Int,
+            };
+            return Ok(expression);
+        }
+
+        let dest = create_temp();
+        let mut code = format!("%int {dest}\n");
+        code += &format!("%len {dest}, {name}\n");
+        let expression = Expression {
+            code,
+            name : dest,
+            ty : Type::Int,
+        };
+        Ok(expression)
+    }
+
+    // numbers that do not fit in an int are longs.
+    Token::Num(number) => {
+        *index += 1;
+        let expression = Expression {
+            code : String::from(""),
+            name : number.to_string(),
+            ty : if *number > i32::MAX as i64 { Type::Long } else { Type::Int },
+        };
+        Ok(expression)
+    }
+
+    Token::FloatLiteral(number) => {
+        *index += 1;
+        let expression = Expression {
+            code : String::from(""),
+            name : format_float(*number),
+            ty : Type::Float,
+        };
+        Ok(expression)
+    }
+
+    // chars are stored as their unicode value.
+    Token::CharLiteral(value) => {
+        *index += 1;
+        let expression = Expression {
+            code : String::from(""),
+            name : value.to_string(),
+            ty : Type::Char,
+        };
+        Ok(expression)
+    }
+
+    // string literals are '%data' constants, which can be used wherever a string variable can.
+    Token::StringLiteral(text) => {
+        *index += 1;
+        let expression = Expression {
+            code : String::from(""),
+            name : string_constant(codegen, text),
+            ty : Type::Str,
+        };
+        Ok(expression)
+    }
+
+    // bools are stored as 1 and 0.
+    Token::True | Token::False => {
+        let value = if matches!(tokens[*index], Token::True) { 1 } else { 0 };
+        *index += 1;
+        let expression = Expression {
+            code : String::from(""),
+            name : value.to_string(),
+            ty : Type::Bool,
+        };
+        return Ok(expression);
+    }
+
+    // unary minus. negative numbers such as '-5' are folded into the number,
+    // anything else is computed as '0 - term'.
+    Token::Subtract => {
+        *index += 1;
+        if let Token::Num(number) = &tokens[*index] {
+            *index += 1;
+            let expression = Expression {
+                code : String::from(""),
+                name : (-number).to_string(),
+                ty : if -number < i32::MIN as i64 { Type::Long } else { Type::Int },
+            };
+            return Ok(expression);
+        }
+
+        let term = parse_term(tokens, index, codegen)?;
+        if term.ty == Type::Float {
+            let zero = Expression {
+                code : String::from(""),
+                name : String::from("0.0"),
+                ty : Type::Float,
+            };
+            return float_expression(codegen, "%sub", zero, term);
+        }
+        if !is_integer(term.ty) {
+            check_type(codegen, &term, Type::Int, "The operand of '-'")?;
+        }
+        if let Some(value) = constant_value(&term) {
+            let expression = Expression {
+                code : String::from(""),
+                name : wrap_constant(value.wrapping_neg(), term.ty).to_string(),
+                ty : term.ty,
+            };
+            return Ok(expression);
+        }
+
+        let dest = create_temp();
+        let mut code = term.code;
+        code += &format!("{} {dest}\n", ir_declaration(term.ty));
+        code += &format!("%sub {dest}, 0, {}\n", term.name);
+        let expression = Expression {
+            code,
+            name : dest,
+            ty : term.ty,
+        };
+        Ok(expression)
+    }
+
+    Token::BitNot => {
+        *index += 1;
+        let term = parse_term(tokens, index, codegen)?;
+        if !is_integer(term.ty) {
+            check_type(codegen, &term, Type::Int, "The operand of '~'")?;
+        }
+        if let Some(value) = constant_value(&term) {
+            let expression = Expression {
+                code : String::from(""),
+                name : (!value).to_string(),
+                ty : term.ty,
+            };
+            return Ok(expression);
+        }
+
+        let dest = create_temp();
+        let mut code = term.code;
+        code += &format!("{} {dest}\n", ir_declaration(term.ty));
+        code += &format!("%not {dest}, {}\n", term.name);
+        let expression = Expression {
+            code,
+            name : dest,
+            ty : term.ty,
+        };
+        Ok(expression)
+    }
+
+    // logical not. '!b' is 'b == 0'.
+    Token::Not => {
+        *index += 1;
+        let term = parse_term(tokens, index, codegen)?;
+        check_type(codegen, &term, Type::Bool, "The operand of '!'")?;
+        let zero = Expression {
+            code : String::from(""),
+            name : String::from("0"),
+            ty : Type::Bool,
+        };
+        Ok(binary_expression("%eq", term, zero))
+    }
+
+    // a cast such as '(long) i' or '(int) n'.
+    Token::LeftParen if type_token(&tokens[*index + 1]).is_some() && matches!(tokens[*index + 2], Token::RightParen) => {
+        let ty = type_token(&tokens[*index + 1]).unwrap();
+        *index += 3;
+        let term = parse_term(tokens, index, codegen)?;
+        cast_expression(codegen, term, ty)
+    }
+
     Token::LeftParen => {
         *index += 1;
         let expression: Expression;
-        match parse_expression(tokens, index) {
+        match parse_expression(tokens, index, codegen) {
         Ok(e) => {expression = e;},
         Err(e) => {return Err(e);}
         }
@@ -739,3 +3870,1252 @@ fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, Stri
     }
 }
 
+// converts between ints, longs, floats and chars. a long is narrowed to an int by moving it into an
+// '%int', which keeps the lowest 32 bits. widening only changes the type, because the interpreter
+// reads every integer as 64 bits. chars are ints, so '(char) 97' is 'a'. integers become floats with
+// '%itof', and floats become integers with '%ftoi', which drops the fraction.
+fn cast_expression(codegen: &CodeGenerator, expression: Expression, ty: Type) -> Result<Expression, String> {
+    // a variant is already stored as its position, such as 1 for 'Green'.
+    if matches!(expression.ty, Type::Enum(_)) && ty == Type::Int {
+        return Ok(Expression {
+            code : expression.code,
+            name : expression.name,
+            ty,
+        });
+    }
+
+    let castable = |ty: Type| matches!(ty, Type::Int | Type::Long | Type::Float | Type::Char);
+    if !castable(expression.ty) || !castable(ty) {
+        return Err(format!("Cannot cast {} to {}", type_name(codegen, expression.ty), type_name(codegen, ty)));
+    }
+
+    if expression.ty == Type::Float || ty == Type::Float {
+        return float_cast(codegen, expression, ty);
+    }
+
+    if let Some(value) = constant_value(&expression) {
+        return Ok(Expression {
+            code : String::from(""),
+            name : wrap_constant(value, ty).to_string(),
+            ty,
+        });
+    }
+
+    if expression.ty != Type::Long || ty == Type::Long {
+        return Ok(Expression {
+            code : expression.code,
+            name : expression.name,
+            ty,
+        });
+    }
+
+    let dest = create_temp();
+    let mut code = expression.code;
+    code += &format!("%int {dest}\n");
+    code += &format!("%mov {dest}, {}\n", expression.name);
+    Ok(Expression {
+        code,
+        name : dest,
+        ty,
+    })
+}
+
+fn float_cast(codegen: &CodeGenerator, expression: Expression, ty: Type) -> Result<Expression, String> {
+    if expression.ty == ty {
+        return Ok(expression);
+    }
+
+    if let Some(value) = float_value(&expression) {
+        if ty == Type::Float {
+            return Ok(Expression {
+                code : String::from(""),
+                name : format_float(value),
+                ty,
+            });
+        }
+        // the range is checked the same way the interpreter checks '%ftoi'.
+        let min = if ty == Type::Long { i64::MIN as f64 } else { i32::MIN as f64 };
+        let value = value.trunc();
+        if !(value >= min && value < -min) {
+            return Err(format!("Cannot cast {} to {}, because it does not fit", expression.name, type_name(codegen, ty)));
+        }
+        return Ok(Expression {
+            code : String::from(""),
+            name : (value as i64).to_string(),
+            ty,
+        });
+    }
+
+    let dest = create_temp();
+    let opcode = if ty == Type::Float { "%itof" } else { "%ftoi" };
+    let mut code = expression.code;
+    code += &format!("{} {dest}\n", ir_declaration(ty));
+    code += &format!("{opcode} {dest}, {}\n", expression.name);
+    Ok(Expression {
+        code,
+        name : dest,
+        ty,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lex;
+    use crate::parse_program;
+    use crate::parse_program_with_warnings;
+    use crate::parse_assignment;
+    use crate::parse_statement;
+    use crate::can_reach_end;
+    use crate::CodeGenerator;
+    use crate::Scope;
+    use crate::interpreter::execute_ir_output;
+
+    fn run(code: &str) -> Result<String, String> {
+        let tokens = lex(code)?;
+        let ir = parse_program(&tokens, &mut 0)?;
+        execute_ir_output(&ir)
+    }
+
+    #[test]
+    fn negative_numbers() {
+        let output = run("func main() {
+            int a;
+            int b;
+            a = -5;
+            print(a);
+            b = -a * 3 - -2;
+            print(b);
+            b = -(a + 1);
+            print(b);
+            a = -2147483648;
+            print(a);
+            print(a - 1);
+        }");
+        assert_eq!(output.unwrap(), "-5\n17\n4\n-2147483648\n2147483647\n");
+
+        assert!(run("func main() { int a; a = 2147483648; }").is_err());
+        assert!(run("func main() { int a; a = -2147483649; }").is_err());
+    }
+
+    #[test]
+    fn bitwise_operators() {
+        let output = run("func main() {
+            int a;
+            a = 12;
+            print(a & 10);
+            print(a | 3);
+            print(a ^ 5);
+            print(~a);
+            print(1 << 4);
+            print(-16 >> 2);
+            print(1 << 32);
+            print(-1 >> 40);
+
+            # C precedence: shifts bind tighter than comparisons,
+            # comparisons tighter than '&', then '^', then '|'.
+            print(1 << 2 + 1);
+            print(1 | 6 ^ 3 & 5);
+            print((a & 4) == 4);
+            print(3 < 1 << 2);
+        }");
+        assert_eq!(output.unwrap(), "8\n15\n9\n-13\n16\n-4\n0\n-1\n8\n7\n1\n1\n");
+
+        assert!(run("func main() { print(1 << -1); }").is_err());
+
+        // '==' binds tighter than '&', so this is 'a & (4 == 4)', which mixes an int with a bool.
+        assert!(run("func main() { int a; print(a & 4 == 4); }").is_err());
+    }
+
+    #[test]
+    fn loops() {
+        // 'continue' in a for loop still runs the step.
+        let output = run("func main() {
+            for (int i = 0; i < 6; i = i + 1) {
+                if i % 2 == 0 {
+                    continue;
+                }
+                print(i);
+            }
+        }");
+        assert_eq!(output.unwrap(), "1\n3\n5\n");
+
+        // 'continue' in a do while loop still checks the condition.
+        let output = run("func main() {
+            int i;
+            i = 0;
+            do {
+                i = i + 1;
+                if i == 2 {
+                    continue;
+                }
+                print(i);
+            } while i < 4;
+            do {
+                print(i);
+            } while (i < 0);
+        }");
+        assert_eq!(output.unwrap(), "1\n3\n4\n4\n");
+
+        // 'break' only leaves the innermost loop.
+        let output = run("func main() {
+            int i;
+            int j;
+            for (i = 0; i < 3; i = i + 1) {
+                j = 0;
+                while j < 10 {
+                    if j > i {
+                        break;
+                    }
+                    print(i * 10 + j);
+                    j = j + 1;
+                }
+            }
+            for (;;) {
+                break;
+            }
+            print(i);
+        }");
+        assert_eq!(output.unwrap(), "0\n10\n11\n20\n21\n22\n3\n");
+
+        assert_eq!(run("func main() { break; }").unwrap_err(), "break statement is outside a loop");
+        assert_eq!(run("func main() { int i; if i == 0 { continue; } }").unwrap_err(), "continue statement is outside a loop");
+    }
+
+    #[test]
+    fn compound_assignment() {
+        let output = run("func main() {
+            int a;
+            int [4] array;
+            a = 10;
+            a += 5;
+            print(a);
+            a -= 3;
+            print(a);
+            a *= 2;
+            print(a);
+            a /= 5;
+            print(a);
+            a %= 3;
+            print(a);
+            a++;
+            print(a);
+            --a;
+            print(a);
+
+            for (int i = 0; i < 4; i++) {
+                array[i] = i * 10;
+            }
+            array[1] += 7;
+            array[2]--;
+            ++array[3];
+            array[a + 1] *= 3;
+            for (int j = 0; j < 4; j++) {
+                print(array[j]);
+            }
+        }");
+        assert_eq!(output.unwrap(), "15\n12\n24\n4\n1\n2\n1\n0\n17\n57\n31\n");
+
+        // the index of the element is only computed once.
+        let tokens = lex("int [8] a; int i; a[i * 2 + 1] += 1;").unwrap();
+        let mut codegen = CodeGenerator::new();
+        codegen.scopes.push(Scope::new());
+        let mut index = 0;
+        parse_statement(&tokens, &mut index, &mut codegen).unwrap();
+        parse_statement(&tokens, &mut index, &mut codegen).unwrap();
+        let code = parse_assignment(&tokens, &mut index, &mut codegen).unwrap();
+        assert_eq!(code.matches("%mult").count(), 1);
+        assert_eq!(code.matches("%add").count(), 2);
+    }
+
+    #[test]
+    fn if_statements() {
+        let output = run("func main() {
+            int i;
+            for (i = 0; i < 3; i = i + 1) {
+                if i == 0 {
+                    print(100);
+                } else if i == 1 {
+                    print(200);
+                } else {
+                    print(300);
+                }
+            }
+        }");
+        assert_eq!(output.unwrap(), "100\n200\n300\n");
+    }
+
+    #[test]
+    fn global_variables() {
+        let output = run("int count;
+        int [3] history;
+
+        func tick(int amount) -> int {
+            count += amount;
+            history[count % 3] = count;
+            return count;
+        }
+
+        func shadow(int count) -> int {
+            count = count * 100;
+            return count;
+        }
+
+        func main() {
+            int a;
+            a = tick(1);
+            a = tick(tick(1) + 2);
+            print(count);
+            print(history[0]);
+            print(history[1]);
+            print(shadow(5));
+            print(count);
+            int count;
+            count = 42;
+            print(count);
+            print(tick(1));
+        }");
+        assert_eq!(output.unwrap(), "6
+6
+1
+500
+6
+42
+7
+");
+
+        assert!(run("int g = 10; func main() {}").is_err());
+        assert!(run("func main() { print(g); } int g;").is_err());
+    }
+
+    #[test]
+    fn constants() {
+        let output = run("const int N = 10 * 4;
+        const int HALF = N / 2 - (1 << 2);
+        int [N] table;
+
+        func main() {
+            const int LAST = N - 1;
+            int [HALF + 1] local;
+            table[LAST] = HALF;
+            local[HALF] = -N;
+            print(table[LAST]);
+            print(local[16]);
+            print(~LAST);
+            print(N > HALF);
+        }");
+        assert_eq!(output.unwrap(), "16\n-40\n-40\n1\n");
+
+        // constants are folded away, and are not variables in the IR.
+        let tokens = lex("const int N = 3; func main() { print(N * N + 1); }").unwrap();
+        let ir = parse_program(&tokens, &mut 0).unwrap();
+        assert_eq!(ir, "%func main()\n%out 10\n%endfunc\n");
+
+        assert!(run("const int N = 3; func main() { N = 4; }").is_err());
+        assert!(run("func main() { const int N = 3; N += 1; }").is_err());
+        assert!(run("func main() { const int N = 3; int N; }").is_err());
+        assert!(run("func main() { int a; const int N = a + 1; }").is_err());
+        assert!(run("const int N = 3; const int N = 4; func main() {}").is_err());
+        assert!(run("func main() { int [0] a; }").is_err());
+        assert!(run("func main() { int n; int [n] a; }").is_err());
+    }
+
+    #[test]
+    fn array_parameters() {
+        let output = run("func sum(int [] a, int n) -> int {
+            int total = 0;
+            for (int i = 0; i < n; i++) {
+                total += a[i];
+            }
+            return total;
+        }
+
+        func sort(int [] a, int n) -> int {
+            for (int i = 0; i < n; i++) {
+                for (int j = 0; j + 1 < n - i; j++) {
+                    if a[j] > a[j + 1] {
+                        int t = a[j];
+                        a[j] = a[j + 1];
+                        a[j + 1] = t;
+                    }
+                }
+            }
+            return 0;
+        }
+
+        func main() {
+            int [5] numbers;
+            numbers[0] = 4;
+            numbers[1] = 9;
+            numbers[2] = 1;
+            numbers[3] = 7;
+            numbers[4] = 3;
+            print(sum(numbers, 5));
+            int x = sort(numbers, 5);
+            for (int i = 0; i < 5; i++) {
+                print(numbers[i]);
+            }
+        }");
+        assert_eq!(output.unwrap(), "24\n1\n3\n4\n7\n9\n");
+
+        // indexing is checked against the length of the caller's array.
+        let output = run("func get(int [] a, int i) -> int { return a[i]; }
+        func main() { int [3] a; print(get(a, 3)); }");
+        assert!(output.unwrap_err().contains("out of bounds"));
+
+        assert!(run("func f(int [] a) -> int { return 0; } func main() { int x; x = f(x); }").is_err());
+        assert!(run("func f(int a) -> int { return 0; } func main() { int [2] x; int y = f(x); }").is_err());
+    }
+
+    #[test]
+    fn multi_dimensional_arrays() {
+        let output = run("const int ROWS = 3;
+        int [ROWS][4] grid;
+
+        func main() {
+            for (int i = 0; i < ROWS; i++) {
+                for (int j = 0; j < 4; j++) {
+                    grid[i][j] = i * 10 + j;
+                }
+            }
+            grid[2][3] += 100;
+            print(grid[1][2]);
+            print(grid[2][3]);
+            int [2][2][2] cube;
+            cube[1][0][1] = 5;
+            print(cube[1][0][1]);
+        }");
+        assert_eq!(output.unwrap(), "12\n123\n5\n");
+
+        // every index is checked, so 'grid[0][j]' cannot read into the next row.
+        let output = run("int [3][4] grid; func main() { int j = 5; print(grid[0][j]); }");
+        assert!(output.unwrap_err().contains("Index 5. Dimension Length 4"));
+
+        assert!(run("func main() { int [3][4] grid; print(grid[0][7]); }").is_err());
+        assert!(run("func main() { int [3][4] grid; print(grid[1]); }").is_err());
+        assert!(run("func main() { int [3] a; print(a[1][1]); }").is_err());
+        assert!(run("func main() { int a; print(a[0]); }").is_err());
+    }
+
+    #[test]
+    fn array_initializers() {
+        let output = run("func total(int [] a) -> int {
+            int sum = 0;
+            for (int i = 0; i < len(a); i++) {
+                sum += a[i];
+            }
+            return sum;
+        }
+
+        func main() {
+            int [5] primes = {2, 3, 5, 7, 11};
+            const int COUNT = len(primes);
+            int [COUNT + 1] more = {1, primes[4] * 2};
+            print(COUNT);
+            print(total(primes));
+            print(more[1]);
+            print(more[5]);
+            print(total(more));
+        }");
+        assert_eq!(output.unwrap(), "5\n28\n22\n0\n23\n");
+
+        assert!(run("func main() { int [2] a = {1, 2, 3}; }").is_err());
+        assert!(run("int [2] a = {1, 2}; func main() {}").is_err());
+        assert!(run("func main() { int a; print(len(a)); }").is_err());
+    }
+
+    #[test]
+    fn switch_statements() {
+        let output = run("func name(int x) -> int {
+            switch (x) {
+            case 1: return 10;
+            case 50: return 20;
+            case -7: return 30;
+            default: return 40;
+            }
+            return 0;
+        }
+
+        func dense(int x) -> int {
+            int r = 0;
+            switch (x + 1) {
+            case 2: r = 1;
+            case 3: r = 2; break; r = 100;
+            case 5: r = 3;
+            case 6: r = 4;
+            }
+            return r;
+        }
+
+        func main() {
+            print(name(1));
+            print(name(50));
+            print(name(-7));
+            print(name(2));
+            for (int i = 0; i < 7; i++) {
+                print(dense(i));
+            }
+            int odd = 0;
+            int n = 0;
+            while (n < 6) {
+                n++;
+                switch (n % 2) {
+                case 0: continue;
+                }
+                odd += n;
+            }
+            print(odd);
+        }");
+        assert_eq!(output.unwrap(), "10\n20\n30\n40\n0\n1\n2\n0\n3\n4\n0\n9\n");
+
+        assert!(run("func main() { int x; int y; switch (x) { case y: print(1); } }").is_err());
+        assert!(run("func main() { int x; switch (x) { case 1: print(1); case 1: print(2); } }").is_err());
+        assert!(run("func main() { int x; switch (x) { default: print(1); default: print(2); } }").is_err());
+    }
+
+    #[test]
+    fn ternary_expressions() {
+        let output = run("func sign(int x) -> int {
+            return x < 0 ? -1 : x == 0 ? 0 : 1;
+        }
+
+        func main() {
+            const int SIZE = 1 > 2 ? 10 : 3;
+            int [SIZE] a = {4, 5, 6};
+            print(sign(-5));
+            print(sign(0));
+            print(sign(9));
+            print(SIZE);
+            int i = 7;
+            print(i < SIZE ? a[i] : 0);
+            print((i > 2 ? 1 : 2) + 10);
+        }");
+        assert_eq!(output.unwrap(), "-1\n0\n1\n3\n0\n11\n");
+
+        assert!(run("func main() { int x; print(x ? 1); }").is_err());
+    }
+
+    #[test]
+    fn block_scopes() {
+        let output = run("int g;
+        func main() {
+            int x = 1;
+            {
+                int x = x + 10;
+                int g = 5;
+                print(x);
+                print(g);
+            }
+            print(x);
+            for (int i = 0; i < 2; i++) {
+                int t;
+                t += i + 1;
+                print(t);
+            }
+            for (int i = 5; i < 7; i++) {
+                int t;
+                print(i + t);
+            }
+            g = 3;
+            print(g);
+        }");
+        assert_eq!(output.unwrap(), "11\n5\n1\n1\n2\n5\n6\n3\n");
+
+        // the variables of blocks that have ended are reused, instead of declaring new ones.
+        let tokens = lex("func main() {
+            { int a = 1; print(a); }
+            { int b = 2; print(b); }
+            { int c; print(c); }
+        }").unwrap();
+        let ir = parse_program(&tokens, &mut 0).unwrap();
+        assert_eq!(ir, "%func main()\n%int a\n%mov a, 1\n%out a\n%mov a, 2\n%out a\n%mov a, 0\n%out a\n%endfunc\n");
+
+        assert!(run("func main() { { int x; } print(x); }").is_err());
+        assert!(run("func main() { for (int i = 0; i < 2; i++) {} i = 1; }").is_err());
+        assert!(run("func main() { int x; int x; }").is_err());
+        assert!(run("func main(int x) { int x; }").is_err());
+        assert!(run("func main() { const int N = 1; { int N; } }").is_err());
+    }
+
+    #[test]
+    fn booleans() {
+        let output = run("func isEven(int n) -> int {
+            bool even = n % 2 == 0;
+            return even ? 1 : 0;
+        }
+
+        func count(bool [] flags, bool value) -> int {
+            int total = 0;
+            for (int i = 0; i < len(flags); i++) {
+                if flags[i] == value {
+                    total++;
+                }
+            }
+            return total;
+        }
+
+        func main() {
+            bool [4] flags = {true, false, true, true};
+            bool done = false;
+            int i = 0;
+            while !done {
+                i++;
+                done = i >= 3 || flags[i] == false;
+            }
+            print(i);
+            print(count(flags, true));
+            print(isEven(4) == 1 && !done);
+            print(true != false);
+
+            # the right side of '&&' is only evaluated when it is needed.
+            int [2] a;
+            int k = 5;
+            if k < 2 && a[k] == 0 {
+                print(100);
+            }
+            print(k >= 2 || a[k] == 0);
+        }");
+        assert_eq!(output.unwrap(), "1\n3\n0\n1\n1\n");
+
+        assert!(run("func main() { int x; if x { print(1); } }").is_err());
+        assert!(run("func main() { int x; while (x) { x = 1; } }").is_err());
+        assert!(run("func main() { bool b; print(b + 1); }").is_err());
+        assert!(run("func main() { bool b; b++; }").is_err());
+        assert!(run("func main() { bool b = 1; }").is_err());
+        assert!(run("func main() { int x = true; }").is_err());
+        assert!(run("func main() { print(1 == true); }").is_err());
+        assert!(run("func main() { print(1 && true); }").is_err());
+        assert!(run("func main() { print(!1); }").is_err());
+        assert!(run("func f(bool b) -> int { return 0; } func main() { print(f(1)); }").is_err());
+        assert!(run("func f(int [] a) -> int { return 0; } func main() { bool [2] b; print(f(b)); }").is_err());
+        assert!(run("func f(int a) -> int { return 0; } func main() { print(f(1, 2)); }").is_err());
+        assert!(run("func f() -> int { return true; } func main() {}").is_err());
+
+        // a call cut off in the middle of its arguments is an error, not a crash.
+        let gcd = "func main() { print(gcd(12, 1";
+        assert!(run(gcd).unwrap_err().contains("missing ')' after the arguments of 'gcd'"));
+        assert!(run("func f(int [] a) -> int { return 0; } func main() { int [2] p; print(f(p").is_err());
+    }
+
+    #[test]
+    fn strings_and_chars() {
+        let output = run(r#"func main() {
+            print("hello, \"world\"\n");
+            print "tab\there\n";
+            char c = 'a';
+            print(c);
+            print('\n');
+            print(c < 'b' && 'z' > c);
+            print(c == 'a');
+            char [3] word = {'h', 'i', '!'};
+            int i;
+            while i < len(word) {
+                print(word[i]);
+                i++;
+            }
+            print "\n";
+            switch c {
+            case 'a':
+                print("first\n");
+            case 'b':
+                print("second\n");
+            }
+            print('\\');
+            print('\'');
+            print("ü\n");
+        }"#);
+        assert_eq!(output.unwrap(), "hello, \"world\"\ntab\there\na\n1\n1\nhi!\nfirst\n\\'ü\n");
+
+        // the same text is only stored once.
+        let tokens = lex(r#"func main() { print("x"); print("x"); print("y"); }"#).unwrap();
+        let ir = parse_program(&tokens, &mut 0).unwrap();
+        assert!(ir.starts_with("%data _str1, \"x\"\n%data _str2, \"y\"\n"));
+
+        assert!(lex("func main() { print(\"abc); }").is_err());
+        assert!(lex("func main() { char c = ''; }").is_err());
+        assert!(lex("func main() { char c = 'ab'; }").is_err());
+        assert!(lex(r#"func main() { print("\q"); }"#).is_err());
+        assert!(run("func main() { char c = 1; }").is_err());
+        assert!(run("func main() { int x = 'a'; }").is_err());
+        assert!(run("func main() { print('a' + 1); }").is_err());
+        assert!(run("func main() { print('a' == 97); }").is_err());
+        assert!(run(r#"func main() { int x = "abc"; }"#).is_err());
+        assert!(run("func main() { switch 'a' { case 1: print(1); } }").is_err());
+    }
+
+    #[test]
+    fn string_type() {
+        let output = run(r#"
+        string greeting;
+
+        func isPalindrome(string s) -> int {
+            int i = 0;
+            int j = len(s) - 1;
+            while i < j {
+                if s[i] != s[j] {
+                    return 0;
+                }
+                i++;
+                j--;
+            }
+            return 1;
+        }
+
+        func countWords(string s) -> int {
+            int words = 0;
+            bool inWord = false;
+            for (int i = 0; i < len(s); i++) {
+                if s[i] == ' ' {
+                    inWord = false;
+                } else if !inWord {
+                    inWord = true;
+                    words++;
+                }
+            }
+            return words;
+        }
+
+        func main() {
+            greeting = "hello";
+            string name = "world";
+            string message = greeting + ", " + name + '!';
+            print(message);
+            print('\n');
+            print(len(message));
+            print(len("héllo"));
+            print(message[1]);
+            print('\n');
+            print(isPalindrome("racecar"));
+            print(isPalindrome(name));
+            print(countWords("  the quick  brown fox "));
+            print("apple" < "banana");
+            print("ab" < "abc");
+            print(name == "world" && name != greeting);
+            print(greeting > name);
+
+            string reversed;
+            for (int i = len(name) - 1; i >= 0; i--) {
+                reversed = reversed + name[i];
+            }
+            print(reversed + "\n");
+            print(true ? "yes\n" : "no\n");
+        }"#);
+        assert_eq!(output.unwrap(), "hello, world!\n13\n5\ne\n1\n0\n4\n1\n1\n1\n0\ndlrow\nyes\n");
+
+        assert!(run(r#"func main() { string s = "abc"; print(s[3]); }"#).is_err());
+        assert!(run(r#"func main() { string s = "abc"; s[0] = 'x'; }"#).is_err());
+        assert!(run(r#"func main() { string s = "abc"; s += "d"; }"#).is_err());
+        assert!(run(r#"func main() { string s = "abc"; print(s - "a"); }"#).is_err());
+        assert!(run(r#"func main() { string s = "abc"; print(s == 1); }"#).is_err());
+        assert!(run(r#"func main() { string s = "abc"; print(s + 1); }"#).is_err());
+        assert!(run(r#"func main() { string s = 'a'; }"#).is_err());
+        assert!(run(r#"func main() { string [3] s; }"#).is_err());
+        assert!(run(r#"func main() { print(len(1)); }"#).is_err());
+        assert!(run(r#"func f() -> int { return "abc"; } func main() {}"#).is_err());
+
+        // strings are returned by value, like they are passed.
+        let output = run(r#"func greet(string s) -> string { return s + "!"; }
+        func first_word(string s) -> (string, int) {
+            for (int i = 0; i < len(s); i++) {
+                if s[i] == ' ' {
+                    string word;
+                    for (int j = 0; j < i; j++) {
+                        word = word + s[j];
+                    }
+                    return word, i;
+                }
+            }
+            return "none", 0;
+        }
+        func main() {
+            string name = "world";
+            print(greet(name));
+            string word;
+            int length;
+            word, length = first_word("hello there");
+            print(word);
+            print(length);
+            word, length = first_word("x");
+            print(word);
+        }"#);
+        assert_eq!(output.unwrap(), "world!hello5\nnone");
+        assert!(run(r#"func f() -> string { return 1; } func main() {}"#).is_err());
+        assert!(run(r#"func f() -> string { return "a"; } func main() { int n = f(); }"#).is_err());
+        assert!(run(r#"func f() -> (string, int) { return "a", 1; } func main() { int a; int b; a, b = f(); }"#).is_err());
+        assert!(run(r#"func main() { bool b; read b; }"#).is_err());
+    }
+
+    #[test]
+    fn long_integers() {
+        let output = run("long total;
+        func factorial(int n) -> int {
+            long product = 1;
+            for (int i = 2; i <= n; i++) {
+                product *= i;
+            }
+            total = product;
+            return (int) (product % 1000000007);
+        }
+        func main() {
+            print(factorial(20));
+            print(total);
+            long a = 0;
+            long b = 1;
+            for (int i = 0; i < 90; i++) {
+                long next = a + b;
+                a = b;
+                b = next;
+            }
+            print(a);
+            long big = 3000000000;
+            print(big + 1);
+            print((int) big);
+            print(-big);
+            print(big << 4);
+            print((long) 2147483647 + 1);
+            print(2147483647 + 1);
+            int i = 7;
+            big = i;
+            print(big == 7);
+            print((char) 104);
+            print((int) 'h');
+        }");
+        assert_eq!(output.unwrap(), "146326063\n2432902008176640000\n2880067194370816120\n3000000001\n-1294967296\n-3000000000\n48000000000\n2147483648\n-2147483648\n1\nh104\n");
+
+        // ints and longs only mix through a cast, and narrowing needs an explicit '(int)'.
+        assert!(run("func main() { long a = 5; int i = 2; print(a + i); }").unwrap_err().contains("cannot mix int and long"));
+        assert!(run("func main() { long a = 5; int i = 2; print(a + (long) i); }").is_ok());
+        assert!(run("func main() { long a = 5; int i = a; }").unwrap_err().contains("'(int)'"));
+        assert!(run("func main() { long a = 5; int i = 1; i += a; }").is_err());
+        assert!(run("func main() { long [3] a; }").is_err());
+        assert!(run("func main() { long a; switch a { default: print(1); } }").is_err());
+        assert!(run("func main() -> int { long a; return a; }").is_err());
+        assert!(run("func main() { print((long) true); }").is_err());
+        assert!(run("func main() { long a = 9223372036854775808; }").is_err());
+
+        // an int constant chosen over a long is a long, whether or not the condition is constant.
+        let mixed = "long L = 3000000000; print((false ? L : 2147483647) + 1); bool c = false; print((c ? L : 2147483647) + 1);";
+        assert_eq!(run(&format!("func main() {{ {mixed} }}")).unwrap(), "2147483648\n2147483648\n");
+    }
+
+    #[test]
+    fn floats() {
+        let output = run("func first_guess(float x) -> float {
+            if x < 1 {
+                return 1.0;
+            }
+            return x / 2;
+        }
+        func newton(float x) -> (float, int) {
+            float guess = first_guess(x);
+            float change = 1.0;
+            int steps = 0;
+            while change > 1e-12 || change < -1e-12 {
+                float next = (guess + x / guess) / 2;
+                change = next - guess;
+                guess = next;
+                steps++;
+            }
+            return guess, steps;
+        }
+        func main() {
+            float root;
+            int steps;
+            root, steps = newton(2);
+            print(steps);
+            print(root);
+            int [5] scores = {90, 85, 77, 100, 64};
+            int total = 0;
+            for (int i = 0; i < 5; i++) {
+                total += scores[i];
+            }
+            float average = (float) total / 5;
+            print(average);
+            print((int) average);
+            print((int) -average);
+            print(-average);
+            print(0.1 + 0.2);
+            print(1.5e3 * 2);
+            float x = 1;
+            x /= 4;
+            x++;
+            print(x);
+            print(x >= 1.25 && x != 2.0);
+            print(1.0 / 3 * 3 == 1.0);
+            print((float) (long) 1e15);
+        }");
+        assert_eq!(output.unwrap(), "6\n1.414213562373095\n83.2\n83\n-83\n-83.2\n0.30000000000000004\n3000.0\n1.25\n1\n1\n1000000000000000.0\n");
+
+        // ints and floats only mix through a conversion, and floats have no remainder or bitwise operators.
+        assert!(run("func main() { float x = 2.5; int n = 3; print(x + n); }").unwrap_err().contains("cannot mix float and int"));
+        assert!(run("func main() { float x = 2.5; int n = 3; print(x + (float) n); }").is_ok());
+        assert!(run("func main() { float x = 2.5; int n = x; }").unwrap_err().contains("'(int)'"));
+        assert!(run("func main() { float x; int n = 3; x = n; }").unwrap_err().contains("'(float)'"));
+        assert!(run("func main() { float x = 2.5; print(x % 2); }").is_err());
+        assert!(run("func main() { float x = 2.5; x %= 2; }").is_err());
+        assert!(run("func main() { float x = 2.5; print(x << 1); }").is_err());
+        assert!(run("func f(int n) -> float { return n; } func main() {}").unwrap_err().contains("'(float)'"));
+        assert_eq!(run("func f() -> float { return 1; } func main() { print(f()); }").unwrap(), "1.0\n");
+        assert!(run("func f() -> float { return 1.0; } func main() { int n = f(); }").is_err());
+        assert!(run("func f() -> (float, int) { return 1.0, 2; } func main() { int a; int b; a, b = f(); }").is_err());
+        assert!(run("func main() -> float { return 1.0; }").is_err());
+        assert!(run("func main() { float x; if x { print(1); } }").is_err());
+        assert!(run("func main() { print((int) 3e10); }").is_err());
+        assert!(run("func main() { float x = 3e10; print((int) x); }").unwrap_err().contains("does not fit"));
+        assert!(run("func main() { float x = 1e999; }").is_err());
+
+        // arrays of floats, and arrays of structs with float fields, are '%float[]'s.
+        let output = run("float [2] scale;
+        struct Sample { int id; float value; }
+        func mean(float [] values) -> float {
+            float total = 0.0;
+            for (int i = 0; i < len(values); i++) {
+                total += values[i];
+            }
+            return total / (float) len(values);
+        }
+        func main() {
+            float [4] a = {1.5, 2, 0.25};
+            a[3] = a[0] * 2;
+            a[1] += 0.5;
+            print(a[1]);
+            print(mean(a));
+            float [2][2] grid;
+            grid[1][0] = 4.5;
+            print(grid[1][0] + grid[0][1]);
+            scale[1] = 3;
+            print(scale[1]);
+            Sample [2] samples;
+            samples[1].id = 7;
+            samples[1].value = 0.5;
+            print(samples[1].value * 4);
+            print(len(a));
+        }");
+        assert_eq!(output.unwrap(), "2.5\n1.8125\n4.5\n3.0\n2.0\n4\n");
+        assert!(run("func main() { float [2] a; int n = a[0]; }").is_err());
+        assert!(run("func main() { int n = 1; float [2] a = {1.5, n}; }").is_err());
+        assert!(run("func f(int [] a) {} func main() { float [2] a; f(a); }").is_err());
+        assert!(run("func f(float [] a) {} func main() { int [2] a; f(a); }").is_err());
+
+        // an int constant chosen over a float is a float, whether or not the condition is constant.
+        let mixed = "print(false ? 1.5 : 2); bool c = false; print(c ? 1.5 : 2); print(true ? 3 : 0.5);";
+        assert_eq!(run(&format!("func main() {{ {mixed} }}")).unwrap(), "2.0\n2.0\n3.0\n");
+    }
+
+    #[test]
+    fn structs() {
+        let output = run("struct Point { int x; int y; }
+        Point origin;
+        func distance(Point a, Point b) -> int {
+            int dx = a.x - b.x;
+            int dy = a.y - b.y;
+            return dx * dx + dy * dy;
+        }
+        func farthest(Point [] points) -> int {
+            int best = 0;
+            for (int i = 1; i < len(points); i++) {
+                if distance(points[i], origin) > distance(points[best], origin) {
+                    best = i;
+                }
+            }
+            return best;
+        }
+        func main() {
+            origin.x = 1;
+            Point p = {4, 5};
+            Point q = p;
+            q.y -= 10;
+            print(p.y);
+            print(q.y);
+            print(distance(p, q));
+            Point [3] points;
+            points[0] = p;
+            points[1] = q;
+            points[2].x = -6;
+            points[2].y += 2;
+            print(farthest(points));
+            p = points[2];
+            print(p.x);
+        }");
+        assert_eq!(output.unwrap(), "5\n-5\n100\n2\n-6\n");
+
+        // struct variables are only used through their fields, and only structs of the same type can be copied.
+        assert!(run("struct Point { int x; int y; } func main() { Point p; print(p.z); }").unwrap_err().contains("has no field named 'z'"));
+        assert!(run("struct Point { int x; int y; } func main() { Point p; p.z = 1; }").unwrap_err().contains("has no field named 'z'"));
+        assert!(run("struct Point { int x; int y; } func main() { Point p; print(p); }").unwrap_err().contains("'p.x'"));
+        assert!(run("struct Point { int x; int y; } func main() { Point p; p += 1; }").is_err());
+        assert!(run("struct Point { int x; } struct Size { int x; } func main() { Point p; Size s; p = s; }").unwrap_err().contains("must be Point, not Size"));
+        assert!(run("struct Point { int x; } func f(Point p) -> int { return p.x; } func main() { print(f(3)); }").is_err());
+        assert!(run("struct Point { int x; } func main() { Point p = {1, 2}; }").is_err());
+        assert!(run("struct Point { int x; } struct Point { int y; } func main() { }").is_err());
+        assert!(run("struct Point { int x; int x; } func main() { }").is_err());
+        assert!(run("struct Name { string s; } func main() { Name [2] names; }").is_err());
+    }
+
+    #[test]
+    fn enums() {
+        let output = run("enum Color { Red, Green, Blue }
+        Color favorite;
+        func score(Color color) -> int {
+            switch color {
+            case Red: return 1;
+            case Green, Blue: return 2;
+            }
+            return 0;
+        }
+        func main() {
+            favorite = Blue;
+            Color [3] colors = {Green, Red, Blue};
+            int total = 0;
+            for (int i = 0; i < len(colors); i++) {
+                total += score(colors[i]);
+                if colors[i] < favorite {
+                    total += 10;
+                }
+            }
+            print(total);
+            print((int) favorite);
+            print(colors[0] == Green);
+        }");
+        assert_eq!(output.unwrap(), "25\n2\n1\n");
+
+        // a switch over an enum without a default warns about the variants it misses.
+        let warnings = |code: &str| parse_program_with_warnings(&lex(code).unwrap(), &mut 0).unwrap().1;
+        let missing = warnings("enum Color { Red, Green, Blue } func main() { Color c; switch c { case Green: print(1); } }");
+        assert_eq!(missing, vec![String::from("The switch over Color has no case for 'Red', 'Blue' and no default")]);
+        assert!(warnings("enum Color { Red, Green } func main() { Color c; switch c { case Red, Green: print(1); } }").is_empty());
+        assert!(warnings("enum Color { Red, Green } func main() { Color c; switch c { case Red: print(1); default: print(2); } }").is_empty());
+
+        // variants are only used as values of their own enum.
+        assert!(run("enum Color { Red, Green } func main() { Color c = 1; }").unwrap_err().contains("must be Color, not int"));
+        assert!(run("enum Color { Red, Green } func main() { int n = Red; }").unwrap_err().contains("'(int)'"));
+        assert!(run("enum Color { Red, Green } func main() { Color c; print(c + 1); }").is_err());
+        assert!(run("enum Color { Red } enum Size { Small } func main() { print(Red == Small); }").unwrap_err().contains("Cannot compare Color with Size"));
+        assert!(run("enum Color { Red } func main() { Color c; switch c { case 0: print(1); } }").is_err());
+        assert!(run("enum Color { Red, Green } enum Light { Red } func main() { }").is_err());
+        assert!(run("enum Color { Red } const int Red = 2; func main() { }").is_err());
+        assert!(run("enum Color { Red } func main() { int Red = 2; }").is_err());
+        assert!(run("enum Color { Red } struct Color { int x; } func main() { }").is_err());
+        assert!(run("enum Color { } func main() { }").is_err());
+    }
+
+    #[test]
+    fn tuple_returns() {
+        let output = run("func divmod(int a, int b) -> (int, int) {
+            return a / b, a % b;
+        }
+        func bounds(int [] a) -> (int, int) {
+            int low = a[0];
+            int high = a[0];
+            for (int i = 1; i < len(a); i++) {
+                low = a[i] < low ? a[i] : low;
+                high = a[i] > high ? a[i] : high;
+            }
+            return low, high;
+        }
+        func parity(int n) -> (bool, char) {
+            if n % 2 == 0 {
+                return true, 'e';
+            }
+            return false, 'o';
+        }
+        func square(long n) -> long {
+            return n * n;
+        }
+        func main() {
+            int q;
+            int r;
+            q, r = divmod(7, 2);
+            print(q);
+            print(r);
+            int [5] a = {4, -8, 15, 16, 2};
+            long low;
+            long high;
+            low, high = bounds(a);
+            print(low);
+            print(high);
+            bool even;
+            char letter;
+            even, letter = parity(q);
+            print(even);
+            print(letter);
+            print(square(100000));
+        }");
+        assert_eq!(output.unwrap(), "3\n1\n-8\n16\n0\no10000000000\n");
+
+        // every value is returned and assigned, with the types the function declares.
+        let divmod = "func divmod(int a, int b) -> (int, int) { return a / b, a % b; }";
+        assert!(run(&format!("{divmod} func main() {{ print(divmod(7, 2)); }}")).unwrap_err().contains("returns 2 values"));
+        assert!(run(&format!("{divmod} func main() {{ int q; int r; int s; q, r, s = divmod(7, 2); }}")).is_err());
+        assert!(run(&format!("{divmod} func main() {{ int q; string r; q, r = divmod(7, 2); }}")).is_err());
+        assert!(run(&format!("{divmod} func main() {{ int q; q, q = divmod(7, 2); }}")).is_err());
+        assert!(run(&format!("{divmod} func main() {{ int q; int r; q, r = 3; }}")).is_err());
+        let targets = "Only variables can receive multiple return values";
+        assert!(run(&format!("{divmod} func main() {{ int [2] a; int r; a[0], r = divmod(7, 2); }}")).unwrap_err().contains(targets));
+        assert!(run(&format!("{divmod} func main() {{ int [2] a; int q; q, a[1] = divmod(7, 2); }}")).unwrap_err().contains(targets));
+        let point = "struct Point { int x; int y; }";
+        assert!(run(&format!("{point} {divmod} func main() {{ Point p; int r; p.x, r = divmod(7, 2); }}")).unwrap_err().contains(targets));
+        assert!(run("func f() -> (int, int) { return 1; } func main() { }").unwrap_err().contains("returns 2 values, but 1 were returned"));
+        assert!(run("func f() -> (int, bool) { return 1, 2; } func main() { }").is_err());
+        assert!(run("struct Point { int x; } func f() -> Point { Point p; return p; } func main() { }").unwrap_err().contains("cannot return structs"));
+        assert!(run("func f() -> long { return 1; } func main() { int n = f(); }").is_err());
+    }
+
+    #[test]
+    fn void_functions() {
+        let output = run("int total;
+        func add(int amount) {
+            if amount < 0 {
+                return;
+            }
+            total += amount;
+        }
+        func report(int value) -> int {
+            print(value);
+            return value;
+        }
+        func firstOver(int limit) -> int {
+            int n = 1;
+            while true {
+                if n * n > limit {
+                    return n;
+                }
+                n++;
+            }
+        }
+        func sign(int x) -> int {
+            if x < 0 {
+                return -1;
+            } else if x == 0 {
+                return 0;
+            } else {
+                return 1;
+            }
+        }
+        func main() {
+            add(5);
+            add(-1);
+            add(2);
+            report(total);
+            print(firstOver(50));
+            print(sign(-4));
+        }");
+        assert_eq!(output.unwrap(), "7\n8\n-1\n");
+
+        // return values must match the return type, and a function with a return type returns on every path.
+        assert!(run("func f() { return 1; } func main() { }").unwrap_err().contains("does not return a value"));
+        assert!(run("func f() -> int { return; } func main() { }").unwrap_err().contains("must have one"));
+        assert!(run("func f() { } func main() { int x = f(); }").unwrap_err().contains("cannot be used in an expression"));
+        assert!(run("func f() { } func main() { print(f()); }").is_err());
+        assert!(run("func f(int x) -> int { if x > 0 { return 1; } } func main() { }").unwrap_err().contains("can reach its end"));
+        assert!(run("func f(int x) -> int { while x > 0 { return 1; } } func main() { }").is_err());
+        assert!(run("func f(int x) -> int { switch x { case 1: return 1; } } func main() { }").is_err());
+        assert!(run("func f(int x) -> int { switch x { case 1: return 1; default: return 2; } } func main() { }").is_ok());
+        assert!(run("func f(int x) -> int { do { return 1; } while (x > 0); } func main() { }").is_ok());
+        assert_eq!(run("func f(int x) -> int { if x > 0 { return 1; } else { exit(2); } } func main() { print(f(1)); f(0); print(3); }").unwrap(), "1\n");
+        assert!(run("func f(int x) -> int { if x > 0 { exit(2); } } func main() { }").is_err());
+        assert!(can_reach_end("%jmp :nowhere\n").unwrap_err().contains("':nowhere'"));
+        assert!(can_reach_end("%branch_if x, :nowhere\n").is_err());
+        assert!(can_reach_end("%switch x, :nowhere\n").is_err());
+        assert!(run("func main() { g(); }").is_err());
+    }
+
+    #[test]
+    fn imports() {
+        use crate::load_program;
+        use crate::source_file;
+        use std::fs;
+
+        // compiles and runs a program made of several files, naming the file of any error.
+        fn run_file(filename: &std::path::Path) -> Result<String, String> {
+            let source = load_program(filename.to_str().unwrap())?;
+            let mut index = 0;
+            let ir = match parse_program(&source.tokens, &mut index) {
+            Ok(ir) => ir,
+            Err(e) => return Err(format!("Error in \"{}\": {e}", source_file(&source, index))),
+            };
+            execute_ir_output(&ir)
+        }
+
+        let directory = std::env::temp_dir().join(format!("teh_tarik_imports_{}", std::process::id()));
+        fs::create_dir_all(directory.join("lib")).unwrap();
+        let write = |name: &str, code: &str| fs::write(directory.join(name), code).unwrap();
+        write("lib/util.tt", "func magnitude(int x) -> int { return x < 0 ? -x : x; }");
+        write("lib/math.tt", "import \"util.tt\";
+        func divisor(int a, int b) -> int {
+            while b != 0 {
+                int t = a % b;
+                a = b;
+                b = t;
+            }
+            return magnitude(a);
+        }");
+        write("main.tt", "import \"lib/math.tt\";
+        import \"lib/util.tt\";
+        func main() {
+            print(divisor(-12, 18));
+            print(magnitude(-3));
+        }");
+        assert_eq!(run_file(&directory.join("main.tt")).unwrap(), "6\n3\n");
+
+        // cycles, functions declared in two files, missing files and misplaced imports are errors.
+        write("a.tt", "import \"b.tt\"; func main() { }");
+        write("b.tt", "import \"a.tt\";");
+        assert!(run_file(&directory.join("a.tt")).unwrap_err().contains("The imports form a cycle"));
+        write("twice.tt", "import \"lib/util.tt\"; func magnitude(int x) -> int { return x; } func main() { }");
+        let error = run_file(&directory.join("twice.tt")).unwrap_err();
+        assert!(error.contains("twice.tt") && error.contains("Function 'magnitude' is already declared in") && error.contains("util.tt"));
+        write("missing.tt", "import \"nowhere.tt\"; func main() { }");
+        assert!(run_file(&directory.join("missing.tt")).unwrap_err().contains("Cannot import"));
+        write("late.tt", "func main() { } import \"lib/util.tt\";");
+        assert!(run_file(&directory.join("late.tt")).unwrap_err().contains("Imports must come before"));
+
+        // an error inside of an imported file names that file.
+        write("lib/broken.tt", "func broken() -> int { return y; }");
+        write("uses_broken.tt", "import \"lib/broken.tt\"; func main() { print(1); }");
+        let error = run_file(&directory.join("uses_broken.tt")).unwrap_err();
+        assert!(error.contains("broken.tt\": 'y' is not declared"), "{error}");
+        write("own_error.tt", "import \"lib/util.tt\"; func main() { print(z); }");
+        assert!(run_file(&directory.join("own_error.tt")).unwrap_err().contains("own_error.tt\": 'z' is not declared"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn builtins() {
+        let output = run("func check(int x) {
+            assert(x > 0);
+            if x > 100 {
+                exit(2);
+            }
+        }
+        func main() {
+            int big_value = pow(2, 10);
+            print(abs(-7));
+            print(min(3, -9) + max(3, -9));
+            print(big_value);
+            print(gcd(-84, 36));
+            print(sqrt_int(99));
+            check(big_value);
+            print(1);
+        }");
+        assert_eq!(output.unwrap(), "7\n-6\n1024\n12\n9\n");
+
+        // builtins take ints, except 'assert' which takes a bool, and 'assert' and 'exit' return nothing.
+        assert!(run("func main() { assert(1 < 0); }").unwrap_err().contains("Assertion failed"));
+        assert!(run("func main() { assert(1); }").unwrap_err().contains("must be bool"));
+        assert!(run("func main() { int x = min(1); }").unwrap_err().contains("takes 2 arguments"));
+        assert!(run("func main() { long x = 5; print(abs(x)); }").is_err());
+        assert!(run("func main() { int x = exit(1); }").unwrap_err().contains("does not return a value"));
+        assert!(run("func main() { int x = pow(2, -1); }").unwrap_err().contains("negative exponent"));
+
+        // 'abs' and 'gcd' are never negative, so a result that does not fit in an int is an error.
+        assert!(run("func main() { print(abs(-2147483648)); }").unwrap_err().contains("abs(-2147483648) does not fit in an int"));
+        assert!(run("func main() { int x = -2147483648; print(gcd(x, 0)); }").unwrap_err().contains("gcd(-2147483648, 0) does not fit in an int"));
+        assert!(run("func main() { int x = -2147483648; print(gcd(x, x)); }").is_err());
+        assert_eq!(run("func main() { print(abs(-2147483647)); print(gcd(-2147483648, 6)); }").unwrap(), "2147483647\n2\n");
+
+        // a function cannot have the name of a builtin.
+        let error = run("func abs(int x) -> int { return x; } func main() { }").unwrap_err();
+        assert!(error.contains("'abs' is a builtin function"), "{error}");
+    }
+}